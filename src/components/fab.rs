@@ -1,37 +1,58 @@
-use log::{debug, info};
+use log::{debug, info, warn};
+use crate::utils::escape_attr;
 
 /// Component for handling Floating Action Bar (FAB) functionality
-pub struct FabComponent;
+pub struct FabComponent {
+    /// Action buttons offered on wiki pages
+    actions: Vec<FabActionTemplate>,
+}
 
 impl FabComponent {
-    /// Create a new FAB component
+    /// Create a new FAB component with the default "raw" and "edit" actions
     pub fn new() -> Self {
         debug!("Creating new FabComponent");
-        Self
+        Self::with_actions(FabActionTemplate::defaults())
+    }
+
+    /// Create a FAB component offering the given actions
+    pub fn with_actions(actions: Vec<FabActionTemplate>) -> Self {
+        debug!("Creating new FabComponent with {} actions", actions.len());
+        Self { actions }
     }
 
     /// Generate FAB actions for a given path
+    ///
+    /// The page's markdown file is taken to be `path` itself when it ends in
+    /// `.md`, otherwise `path` with `.md` appended.
     pub fn generate_actions(&self, path: &str) -> Vec<FabAction> {
-        debug!("Generating FAB actions for path: '{}'", path);
+        let file = if path.ends_with(".md") { path.to_string() } else { format!("{}.md", path) };
+        self.generate_page_actions(path, Some(&file))
+    }
+
+    /// Generate FAB actions for a page served from a markdown file
+    ///
+    /// Pages get one action per configured template; the root gets none.
+    /// Actions referring to `{file}` are left out when there is no file, as for
+    /// directory listings.
+    pub fn generate_page_actions(&self, path: &str, file: Option<&str>) -> Vec<FabAction> {
+        debug!("Generating FAB actions for path: '{}' (file: {:?})", path, file);
         
         let mut actions = Vec::new();
         
         if !path.is_empty() {
-            // Add raw view action
-            let raw_href = format!("/raw/{}", path);
-            actions.push(FabAction {
-                href: raw_href,
-                title: "View raw markdown".to_string(),
-                class: "fab-action-raw".to_string(),
-            });
-            
-            // Add edit action (placeholder for future implementation)
-            let edit_href = format!("/edit/{}", path);
-            actions.push(FabAction {
-                href: edit_href,
-                title: "Edit this page".to_string(),
-                class: "fab-action-edit".to_string(),
-            });
+            for template in &self.actions {
+                let href = template.href.replace("{path}", path);
+                let href = match file {
+                    Some(file) => href.replace("{file}", file),
+                    None if template.href.contains("{file}") => continue,
+                    None => href,
+                };
+                actions.push(FabAction {
+                    href,
+                    title: template.title.clone(),
+                    class: template.class.clone(),
+                });
+            }
         }
         
         debug!("Generated {} FAB actions for path: '{}'", actions.len(), path);
@@ -66,7 +87,7 @@ impl FabComponent {
             for action in actions {
                 html.push_str(&format!(
                     "<a href=\"{}\" title=\"{}\" class=\"{}\"></a>",
-                    action.href, action.title, action.class
+                    escape_attr(&action.href), escape_attr(&action.title), escape_attr(&action.class)
                 ));
             }
             html.push_str("</div>");
@@ -86,7 +107,7 @@ impl FabComponent {
         debug!("Generating home page FAB HTML with {} actions", actions.len());
         let start_time = std::time::Instant::now();
         
-        let mut html = format!("<div class=\"fab glass fab-home\" id=\"fab\">");
+        let mut html = "<div class=\"fab glass fab-home\" id=\"fab\">".to_string();
         html.push_str("<div class=\"fab-menu\">");
         
         // Home button (hidden on home page)
@@ -105,7 +126,7 @@ impl FabComponent {
             for action in actions {
                 html.push_str(&format!(
                     "<a href=\"{}\" title=\"{}\" class=\"{}\"></a>",
-                    action.href, action.title, action.class
+                    escape_attr(&action.href), escape_attr(&action.title), escape_attr(&action.class)
                 ));
             }
             html.push_str("</div>");
@@ -128,8 +149,140 @@ pub struct FabAction {
     pub class: String,
 }
 
+/// Configured FAB action, turned into a `FabAction` for each page
+///
+/// `{path}` in the href is replaced with the page path and `{file}` with the
+/// path of its markdown file, e.g. `guide/index.md` for `guide`.
+#[derive(Debug, Clone)]
+pub struct FabActionTemplate {
+    pub href: String,
+    pub title: String,
+    pub class: String,
+}
+
+impl FabActionTemplate {
+    /// Create an action template
+    pub fn new(href: &str, title: &str, class: &str) -> Self {
+        Self {
+            href: href.to_string(),
+            title: title.to_string(),
+            class: class.to_string(),
+        }
+    }
+
+    /// The "view raw" and "edit" actions shown when nothing else is configured
+    pub fn defaults() -> Vec<Self> {
+        ["raw", "edit"].iter().filter_map(|name| Self::builtin(name)).collect()
+    }
+
+    /// Built-in action by name: `raw`, `edit`, `print` or `history`
+    pub fn builtin(name: &str) -> Option<Self> {
+        let template = match name {
+            "raw" => Self::new("/raw/{file}", "View raw markdown", "fab-action-raw"),
+            "edit" => Self::new("/edit/{path}", "Edit this page", "fab-action-edit"),
+            "print" => Self::new("javascript:window.print()", "Print this page", "fab-action-print"),
+            "history" => Self::new("/history/{path}", "Page history", "fab-action-history"),
+            _ => return None,
+        };
+        Some(template)
+    }
+
+    /// Action linking to the page's source at `url`, typically on a Git host
+    ///
+    /// `{path}` in `url` stands for the markdown file, so it becomes `{file}`
+    /// and the link is left out where there is no file.
+    pub fn edit_source(url: &str) -> Self {
+        Self::new(&url.replace("{path}", "{file}"), "Edit source", "fab-action-source")
+    }
+
+    /// Parse a configured action: a built-in name, or `title|href|class` with the class optional
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        if !spec.contains('|') {
+            let builtin = Self::builtin(spec);
+            if builtin.is_none() {
+                warn!("Unknown FAB action '{}', ignoring", spec);
+            }
+            return builtin;
+        }
+
+        let mut parts = spec.split('|').map(str::trim);
+        let title = parts.next().unwrap_or("");
+        let href = parts.next().unwrap_or("");
+        let class = parts.next().unwrap_or("fab-action-custom");
+        if title.is_empty() || href.is_empty() {
+            warn!("Invalid FAB action '{}', expected `title|href|class`, ignoring", spec);
+            return None;
+        }
+        Some(Self::new(href, title, class))
+    }
+}
+
 impl Default for FabComponent {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hrefs(actions: &[FabAction]) -> Vec<&str> {
+        actions.iter().map(|action| action.href.as_str()).collect()
+    }
+
+    #[test]
+    fn default_actions_are_raw_and_edit() {
+        let actions = FabComponent::new().generate_actions("guide/intro");
+        assert_eq!(hrefs(&actions), ["/raw/guide/intro.md", "/edit/guide/intro"]);
+        assert_eq!(actions[0].class, "fab-action-raw");
+    }
+
+    #[test]
+    fn configured_actions_render_in_order() {
+        let actions = ["print", "Copy link|/copy/{path}", "history", "bogus", "|/no-title"]
+            .iter()
+            .filter_map(|spec| FabActionTemplate::parse(spec))
+            .collect();
+        let fab = FabComponent::with_actions(actions);
+        let actions = fab.generate_actions("guide/intro");
+        assert_eq!(hrefs(&actions), ["javascript:window.print()", "/copy/guide/intro", "/history/guide/intro"]);
+        assert_eq!(actions[1].title, "Copy link");
+        assert_eq!(actions[1].class, "fab-action-custom");
+
+        let html = fab.generate_fab_html("guide/intro", &actions);
+        assert!(html.contains("<a href=\"/copy/guide/intro\" title=\"Copy link\" class=\"fab-action-custom\"></a>"), "{html}");
+        assert!(!html.contains("fab-action-edit"));
+    }
+
+    #[test]
+    fn file_actions_are_left_out_without_a_file() {
+        let actions = FabComponent::new().generate_page_actions("guide", None);
+        assert_eq!(hrefs(&actions), ["/edit/guide"]);
+    }
+
+    #[test]
+    fn empty_path_has_no_actions() {
+        let fab = FabComponent::with_actions(vec![FabActionTemplate::new("/copy", "Copy", "copy")]);
+        assert!(fab.generate_actions("").is_empty());
+        assert!(FabComponent::new().generate_actions("").is_empty());
+        assert!(!fab.generate_fab_html("", &[]).contains("fab-actions"));
+    }
+
+    #[test]
+    fn no_configured_actions_means_none_on_pages() {
+        assert!(FabComponent::with_actions(Vec::new()).generate_actions("guide/intro").is_empty());
+    }
+
+    #[test]
+    fn edit_source_links_to_the_page_file() {
+        let fab = FabComponent::with_actions(vec![FabActionTemplate::edit_source(
+            "https://github.com/org/repo/edit/main/wiki/{path}",
+        )]);
+        let actions = fab.generate_page_actions("guide/setup/intro", Some("guide/setup/intro.md"));
+        assert_eq!(hrefs(&actions), ["https://github.com/org/repo/edit/main/wiki/guide/setup/intro.md"]);
+        assert_eq!(actions[0].class, "fab-action-source");
+        assert!(fab.generate_page_actions("guide", None).is_empty());
+    }
+}
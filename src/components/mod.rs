@@ -2,6 +2,6 @@ pub mod fab;
 pub mod navigation;
 pub mod templates;
 
-pub use fab::FabComponent;
-pub use navigation::NavigationComponent;
+pub use fab::{FabActionTemplate, FabComponent};
+pub use navigation::{NavigationComponent, SidebarCache};
 pub use templates::TemplateComponent;
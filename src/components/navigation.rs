@@ -1,18 +1,95 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
 use log::{debug, info};
 use crate::errors::WikiError;
-use crate::services::FileService;
+use crate::services::{FileService, MarkdownService};
+use crate::types::DirEntry;
+use crate::utils::{escape_attr, escape_html, Frontmatter};
+
+/// Sidebar entry with its display label and ordering weight
+struct NavEntry {
+    entry: DirEntry,
+    /// Link text, unescaped
+    label: String,
+    weight: Option<i64>,
+}
+
+/// Sidebar entry and, for directories within the depth limit, its sorted children
+struct NavNode {
+    entry: NavEntry,
+    children: Vec<NavNode>,
+}
+
+/// Sidebar tree shared across requests, rebuilt when the wiki files change
+///
+/// The tree is keyed by a fingerprint of the names, sizes and modification
+/// times of everything the sidebar shows, so edits on disk are picked up
+/// without reading any file content on a cache hit.
+#[derive(Default)]
+pub struct SidebarCache {
+    cached: RwLock<Option<(u64, Arc<Vec<NavNode>>)>>,
+}
+
+impl SidebarCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop the cached tree so the next render rebuilds it
+    pub fn invalidate(&self) {
+        debug!("Sidebar cache invalidated");
+        *self.cached.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Whether a tree is currently cached
+    pub fn is_populated(&self) -> bool {
+        self.cached.read().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+}
+
+/// Sub-directory levels shown below the top level of the sidebar; unlimited by default
+pub const DEFAULT_NAV_DEPTH: usize = usize::MAX;
 
 /// Component for handling navigation and sidebar generation
 pub struct NavigationComponent {
     file_service: FileService,
+    cache: Option<Arc<SidebarCache>>,
+    /// Sub-directory levels expanded below the top level
+    depth: usize,
+    /// List pages marked `draft: true`
+    show_drafts: bool,
 }
 
 impl NavigationComponent {
     /// Create a new navigation component
     pub fn new(file_service: FileService) -> Self {
         debug!("Creating new NavigationComponent");
-        Self { file_service }
+        Self { file_service, cache: None, depth: DEFAULT_NAV_DEPTH, show_drafts: false }
+    }
+
+    /// Create a navigation component that reuses a shared sidebar tree
+    pub fn with_cache(file_service: FileService, cache: Arc<SidebarCache>) -> Self {
+        debug!("Creating new NavigationComponent with sidebar cache");
+        Self { file_service, cache: Some(cache), depth: DEFAULT_NAV_DEPTH, show_drafts: false }
+    }
+
+    /// Expand directories this many levels below the top level; 0 lists only top-level entries
+    ///
+    /// Deeper directories are shown as links to their listings.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// List pages marked `draft: true`, which are left out by default
+    pub fn with_drafts(mut self, show_drafts: bool) -> Self {
+        self.show_drafts = show_drafts;
+        self
     }
 
     /// Build sidebar HTML with table of contents
@@ -35,6 +112,38 @@ impl NavigationComponent {
         Ok(result)
     }
 
+    /// Build a breadcrumb trail such as `Home / guide / intro`
+    ///
+    /// Ancestors link to their directory URLs; the last crumb is plain text.
+    pub fn build_breadcrumbs(&self, current_path: &str) -> String {
+        let segments: Vec<&str> = current_path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+
+        let mut html = String::from("<nav class=\"breadcrumbs\">");
+        if segments.is_empty() {
+            html.push_str("Home");
+        } else {
+            html.push_str("<a href=\"/\">Home</a>");
+        }
+
+        let mut href = String::from("/");
+        for (i, segment) in segments.iter().enumerate() {
+            let label = segment.strip_suffix(".md").unwrap_or(segment);
+            html.push_str(" / ");
+            if i + 1 == segments.len() {
+                html.push_str(&escape_html(label));
+            } else {
+                href.push_str(segment);
+                href.push('/');
+                html.push_str(&format!("<a href=\"{}\">{}</a>", escape_attr(&href), escape_html(label)));
+            }
+        }
+        html.push_str("</nav>");
+        html
+    }
+
     /// Build basic sidebar HTML
     pub fn build_sidebar_html(&self, current_path: &str) -> Result<String, WikiError> {
         debug!("Building basic sidebar HTML for path: '{}'", current_path);
@@ -45,88 +154,424 @@ impl NavigationComponent {
         html.push_str("<h3>Navigation</h3>");
         
         // Always list from root directory for consistent navigation
-        let entries = self.file_service.list_directory(Path::new(""))?;
-        debug!("Found {} entries in root directory", entries.len());
+        let tree = self.tree()?;
+        debug!("Found {} entries in root directory", tree.len());
         
         html.push_str("<ul class=\"nav-list\">");
-        for entry in entries {
-            if !entry.name.starts_with('.') && entry.name != "index.md" { // Skip hidden files and index.md
-                // entry_path should always be just the entry name for navigation structure
-                let entry_path = entry.name.clone();
-                
-                let href = if entry.is_dir {
-                    format!("/{}", entry_path)
-                } else {
-                    format!("/{}", entry_path.replace(".md", ""))
-                };
-                
-                let display_name = if entry.is_dir {
-                    entry.name.clone()
-                } else {
-                    entry.name.trim_end_matches(".md").to_string()
-                };
-                
-                let is_current = current_path == entry_path || 
-                    (entry.is_dir && current_path.starts_with(&format!("{}/", entry_path)));
-                
-                let current_class = if is_current { " class=\"current\"" } else { "" };
-                
-                if entry.is_dir {
-                    html.push_str(&format!("<li class=\"nav-item has-sub{}\">", current_class));
-                    // details/summary provides native expand/collapse without JavaScript
-                    let open_attr = if is_current { " open" } else { "" };
-                    html.push_str(&format!("<details{}>", open_attr));
-                    html.push_str(&format!("<summary>{}</summary>", display_name));
-                    html.push_str("<ul class=\"nav-sub-list\">");
-                    
-                    // Recursively list sub-directories and files
-                    debug!("Listing sub-directory: {:?}", entry_path);
-                    if let Ok(sub_entries) = self.file_service.list_directory(Path::new(&entry_path)) {
-                        debug!("Found {} sub-entries in {:?}", sub_entries.len(), entry_path);
-                        for sub_entry in sub_entries {
-                            if !sub_entry.name.starts_with('.') && sub_entry.name != "index.md" {
-                                let sub_href = if sub_entry.is_dir {
-                                    format!("/{}/{}", entry_path, sub_entry.name)
-                                } else {
-                                    format!("/{}/{}", entry_path, sub_entry.name.replace(".md", ""))
-                                };
-                                
-                                let sub_display_name = if sub_entry.is_dir {
-                                    sub_entry.name.clone()
-                                } else {
-                                    sub_entry.name.trim_end_matches(".md").to_string()
-                                };
-                                
-                                let sub_is_current = current_path == format!("{}/{}", entry_path, sub_entry.name) ||
-                                    (sub_entry.is_dir && current_path.starts_with(&format!("{}/{}/", entry_path, sub_entry.name)));
-                                
-                                let sub_current_class = if sub_is_current { " class=\"current\"" } else { "" };
-                                
-                                html.push_str(&format!("<li{}>", sub_current_class));
-                                html.push_str(&format!("<a href=\"{}\">{}</a>", sub_href, sub_display_name));
-                                html.push_str("</li>");
-                            }
-                        }
-                    }
-                    
-                    html.push_str("</ul>");
-                    html.push_str("</details>");
-                    html.push_str("</li>");
-                } else {
-                    html.push_str(&format!("<li{}>", current_class));
-                    html.push_str(&format!("<a href=\"{}\">{}</a>", href, display_name));
-                    html.push_str("</li>");
-                }
-            }
+        for node in tree.iter() {
+            Self::render_node(&mut html, node, current_path, true);
         }
         html.push_str("</ul>");
         html.push_str("</div>");
         
-
-        
         let duration = start_time.elapsed();
         info!("Basic sidebar HTML built in {:?}ms for path: '{}'", duration.as_millis(), current_path);
         
         Ok(html)
     }
+
+    /// Render one sidebar entry, recursing into expanded directories
+    fn render_node(html: &mut String, node: &NavNode, current_path: &str, top_level: bool) {
+        let NavEntry { entry, label, .. } = &node.entry;
+        let entry_path = entry.path.to_string_lossy();
+
+        // Pages are requested with or without their .md extension
+        let is_current = current_path == entry_path ||
+            (!entry.is_dir && current_path == entry_path.trim_end_matches(".md")) ||
+            (entry.is_dir && current_path.starts_with(&format!("{}/", entry_path)));
+        let expanded = entry.is_dir && !node.children.is_empty();
+
+        let mut classes = Vec::new();
+        if expanded {
+            if top_level {
+                classes.push("nav-item");
+            }
+            classes.push("has-sub");
+        }
+        if is_current {
+            classes.push("current");
+        }
+        if classes.is_empty() {
+            html.push_str("<li>");
+        } else {
+            html.push_str(&format!("<li class=\"{}\">", classes.join(" ")));
+        }
+
+        if expanded {
+            // details/summary provides native expand/collapse without JavaScript
+            let open_attr = if is_current { " open" } else { "" };
+            html.push_str(&format!("<details{}>", open_attr));
+            html.push_str(&format!("<summary>{}</summary>", escape_html(label)));
+            html.push_str("<ul class=\"nav-sub-list\">");
+            for child in &node.children {
+                Self::render_node(html, child, current_path, false);
+            }
+            html.push_str("</ul>");
+            html.push_str("</details>");
+        } else {
+            let href = if entry.is_dir {
+                format!("/{}/", entry_path)
+            } else {
+                format!("/{}", entry_path.replace(".md", ""))
+            };
+            html.push_str(&format!("<a href=\"{}\">{}</a>", escape_attr(&href), escape_html(label)));
+        }
+        html.push_str("</li>");
+    }
+
+    /// Sidebar tree, from the cache when the files it covers are unchanged
+    fn tree(&self) -> Result<Arc<Vec<NavNode>>, WikiError> {
+        let Some(cache) = &self.cache else {
+            return self.build_tree().map(Arc::new);
+        };
+
+        let (fingerprint, _) = self.tree_version()?;
+        if let Some((cached_fingerprint, tree)) = cache.cached.read().unwrap_or_else(|e| e.into_inner()).as_ref()
+            && *cached_fingerprint == fingerprint
+        {
+            debug!("Reusing cached sidebar tree");
+            return Ok(tree.clone());
+        }
+
+        debug!("Sidebar cache miss, rebuilding tree");
+        let tree = Arc::new(self.build_tree()?);
+        *cache.cached.write().unwrap_or_else(|e| e.into_inner()) = Some((fingerprint, tree.clone()));
+        Ok(tree)
+    }
+
+    /// Read the sidebar tree from disk down to the configured depth
+    fn build_tree(&self) -> Result<Vec<NavNode>, WikiError> {
+        let entries = self.sorted_entries(Path::new(""))?;
+        Ok(self.build_nodes(entries, self.depth))
+    }
+
+    /// Attach children to directory entries, descending `depth` more levels
+    fn build_nodes(&self, entries: Vec<NavEntry>, depth: usize) -> Vec<NavNode> {
+        entries
+            .into_iter()
+            .map(|entry| {
+                let children = if entry.entry.is_dir && depth > 0 {
+                    debug!("Listing sub-directory: {:?}", entry.entry.path);
+                    let sub_entries = self.sorted_entries(&entry.entry.path).unwrap_or_default();
+                    self.build_nodes(sub_entries, depth - 1)
+                } else {
+                    Vec::new()
+                };
+                NavNode { entry, children }
+            })
+            .collect()
+    }
+
+    /// Fingerprint of the names, sizes and modification times the sidebar covers,
+    /// with the newest modification time among them
+    ///
+    /// Only metadata is read, so this is cheap enough to check on every request.
+    pub fn tree_version(&self) -> Result<(u64, Option<SystemTime>), WikiError> {
+        let mut hasher = DefaultHasher::new();
+        self.depth.hash(&mut hasher);
+        self.show_drafts.hash(&mut hasher);
+        // The root itself changes when top-level entries come and go
+        let mut newest = self.file_service.get_metadata(Path::new("")).ok().and_then(|meta| meta.modified().ok());
+        let entries = self.file_service.list_directory(Path::new(""))?;
+        self.hash_entries(entries, self.depth, &mut hasher, &mut newest);
+        Ok((hasher.finish(), newest))
+    }
+
+    /// Hash a directory's entries and, `depth` levels down, their subdirectories
+    fn hash_entries(&self, entries: Vec<DirEntry>, depth: usize, hasher: &mut DefaultHasher, newest: &mut Option<SystemTime>) {
+        for entry in entries {
+            self.hash_entry(&entry, hasher, newest);
+            if entry.is_dir && depth > 0 && !entry.name.starts_with('.') {
+                let sub_entries = self.file_service.list_directory(&entry.path).unwrap_or_default();
+                self.hash_entries(sub_entries, depth - 1, hasher, newest);
+            }
+        }
+    }
+
+    /// Feed one entry's identity and metadata into the fingerprint
+    fn hash_entry(&self, entry: &DirEntry, hasher: &mut DefaultHasher, newest: &mut Option<SystemTime>) {
+        entry.path.hash(hasher);
+        if let Ok(metadata) = self.file_service.get_metadata(&entry.path) {
+            metadata.len().hash(hasher);
+            let modified = metadata.modified().ok();
+            modified.hash(hasher);
+            *newest = (*newest).max(modified);
+        }
+    }
+
+    /// List the visible entries of a directory in sidebar order
+    ///
+    /// Hidden files, index.md and drafts are skipped. Entries with a frontmatter `weight`
+    /// come first, lowest weight first; the rest follow. Ties sort by label.
+    fn sorted_entries(&self, dir: &Path) -> Result<Vec<NavEntry>, WikiError> {
+        let mut entries: Vec<NavEntry> = self.file_service
+            .list_directory(dir)?
+            .into_iter()
+            .filter(|entry| !entry.name.starts_with('.') && entry.name != "index.md")
+            .filter_map(|entry| self.nav_entry(entry))
+            .collect();
+
+        entries.sort_by(|a, b| {
+            let by_weight = match (a.weight, b.weight) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            by_weight.then_with(|| a.label.to_lowercase().cmp(&b.label.to_lowercase()))
+        });
+        Ok(entries)
+    }
+
+    /// Read the label and weight of an entry, or `None` for a hidden draft
+    ///
+    /// Files use their own title and frontmatter; directories take their weight
+    /// from index.md or README.md.
+    fn nav_entry(&self, entry: DirEntry) -> Option<NavEntry> {
+        if entry.is_dir {
+            let weight = ["index.md", "README.md"]
+                .iter()
+                .find_map(|name| self.file_service.read_file(&entry.path.join(name)).ok())
+                .and_then(|content| Self::weight(&content));
+            return Some(NavEntry { label: entry.name.clone(), weight, entry });
+        }
+
+        let stem = entry.name.trim_end_matches(".md");
+        if !entry.name.ends_with(".md") {
+            return Some(NavEntry { label: stem.to_string(), weight: None, entry });
+        }
+
+        let content = self.file_service.read_file(&entry.path).ok();
+        if !self.show_drafts && content.as_deref().is_some_and(|content| Frontmatter::parse(content).is_draft()) {
+            debug!("Hiding draft {:?} from the sidebar", entry.path);
+            return None;
+        }
+        let title = content
+            .as_deref()
+            .and_then(|content| MarkdownService::new().extract_title(content));
+        Some(NavEntry {
+            label: title.unwrap_or_else(|| stem.to_string()),
+            weight: content.as_deref().and_then(Self::weight),
+            entry,
+        })
+    }
+
+    /// Numeric `weight` from a page's frontmatter
+    fn weight(content: &str) -> Option<i64> {
+        Frontmatter::parse(content).get("weight")?.trim().parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::PathFilter;
+    use tempfile::TempDir;
+
+    /// A wiki in a temporary directory holding `pages`
+    fn wiki(pages: &[(&str, &str)]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in pages {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn navigation(dir: &TempDir) -> NavigationComponent {
+        NavigationComponent::new(FileService::new(dir.path().to_path_buf()))
+    }
+
+    #[test]
+    fn breadcrumbs_link_every_ancestor() {
+        let dir = wiki(&[]);
+        assert_eq!(
+            navigation(&dir).build_breadcrumbs("guide/setup/intro.md"),
+            "<nav class=\"breadcrumbs\"><a href=\"/\">Home</a> / <a href=\"/guide/\">guide</a> / \
+             <a href=\"/guide/setup/\">setup</a> / intro</nav>"
+        );
+    }
+
+    #[test]
+    fn root_breadcrumb_is_only_home() {
+        let dir = wiki(&[]);
+        assert_eq!(navigation(&dir).build_breadcrumbs(""), "<nav class=\"breadcrumbs\">Home</nav>");
+        assert_eq!(navigation(&dir).build_breadcrumbs("/"), "<nav class=\"breadcrumbs\">Home</nav>");
+    }
+
+    #[test]
+    fn breadcrumb_segments_are_escaped() {
+        let dir = wiki(&[]);
+        let crumbs = navigation(&dir).build_breadcrumbs("a\"b/<c>");
+        assert!(crumbs.contains("<a href=\"/a&quot;b/\">a&quot;b</a>"), "{crumbs}");
+        assert!(crumbs.ends_with(" / &lt;c&gt;</nav>"));
+    }
+
+    #[test]
+    fn sidebar_labels_come_from_frontmatter_and_headings() {
+        let dir = wiki(&[
+            ("getting-started.md", "---\ntitle: \"Getting Started\"\n---\n# Ignored heading\n"),
+            ("heading.md", "# From <the> Heading\n"),
+            ("plain.md", "No title here.\n"),
+        ]);
+        let sidebar = navigation(&dir).build_sidebar_html("").unwrap();
+
+        assert!(sidebar.contains("<a href=\"/getting-started\">Getting Started</a>"), "{sidebar}");
+        assert!(sidebar.contains("<a href=\"/heading\">From &lt;the&gt; Heading</a>"));
+        assert!(sidebar.contains("<a href=\"/plain\">plain</a>"));
+        assert!(!sidebar.contains(">getting-started<"));
+    }
+
+    /// Labels of the sidebar links and summaries, in order
+    fn labels(sidebar: &str) -> Vec<String> {
+        sidebar
+            .split(['<', '>'])
+            .collect::<Vec<_>>()
+            .windows(2)
+            .filter(|pair| pair[0].starts_with("a href") || pair[0] == "summary")
+            .map(|pair| pair[1].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn weights_come_before_alphabetical_order() {
+        let dir = wiki(&[
+            ("advanced.md", "---\nweight: 2\n---\n# Advanced\n"),
+            ("introduction.md", "---\nweight: 1\n---\n# Introduction\n"),
+            ("zeta.md", "# Zeta\n"),
+            ("alpha.md", "# Alpha\n"),
+            ("beta.md", "---\nweight: oops\n---\n# Beta\n"),
+            ("reference/index.md", "---\nweight: -1\n---\n# Reference\n"),
+            ("reference/api.md", "# API\n"),
+        ]);
+        let labels = labels(&navigation(&dir).build_sidebar_html("").unwrap());
+        assert_eq!(labels, ["reference", "API", "Introduction", "Advanced", "Alpha", "Beta", "Zeta"]);
+    }
+
+    #[test]
+    fn equal_weights_sort_by_label() {
+        let dir = wiki(&[
+            ("b.md", "---\nweight: 5\n---\n# Bravo\n"),
+            ("a.md", "---\nweight: 5\n---\n# Charlie\n"),
+            ("c.md", "---\nweight: 5\n---\n# alpha\n"),
+        ]);
+        assert_eq!(labels(&navigation(&dir).build_sidebar_html("").unwrap()), ["alpha", "Bravo", "Charlie"]);
+    }
+
+    #[test]
+    fn unchanged_tree_reuses_the_cached_sidebar() {
+        let dir = wiki(&[("a.md", "# A\n"), ("guide/b.md", "# B\n")]);
+        let cache = Arc::new(SidebarCache::new());
+        let navigation = NavigationComponent::with_cache(FileService::new(dir.path().to_path_buf()), cache.clone());
+
+        let first = navigation.tree().unwrap();
+        assert!(cache.is_populated());
+        let second = navigation.tree().unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(navigation.build_sidebar_html("").unwrap(), navigation.build_sidebar_html("").unwrap());
+    }
+
+    #[test]
+    fn changes_and_invalidation_rebuild_the_sidebar() {
+        let dir = wiki(&[("a.md", "# A\n"), ("guide/b.md", "# B\n")]);
+        let cache = Arc::new(SidebarCache::new());
+        let navigation = NavigationComponent::with_cache(FileService::new(dir.path().to_path_buf()), cache.clone());
+        let first = navigation.tree().unwrap();
+
+        std::fs::write(dir.path().join("guide/b.md"), "# Renamed B\n").unwrap();
+        let changed = navigation.tree().unwrap();
+        assert!(!Arc::ptr_eq(&first, &changed));
+        assert!(navigation.build_sidebar_html("").unwrap().contains("Renamed B"));
+
+        cache.invalidate();
+        assert!(!cache.is_populated());
+        assert!(!Arc::ptr_eq(&changed, &navigation.tree().unwrap()));
+    }
+
+    const DEEP: &[(&str, &str)] = &[("top.md", "# Top\n"), ("a/b/c/deep.md", "# Deep\n")];
+
+    /// Sidebar of the four-level fixture expanded `depth` levels
+    fn deep_sidebar(depth: usize) -> String {
+        let dir = wiki(DEEP);
+        navigation(&dir).with_depth(depth).build_sidebar_html("").unwrap()
+    }
+
+    #[test]
+    fn depth_zero_lists_only_top_level_entries() {
+        let sidebar = deep_sidebar(0);
+        assert_eq!(labels(&sidebar), ["a", "Top"]);
+        assert!(sidebar.contains("<a href=\"/a/\">a</a>"));
+        assert!(!sidebar.contains("<details"));
+    }
+
+    #[test]
+    fn depth_limits_how_far_directories_expand() {
+        assert_eq!(labels(&deep_sidebar(1)), ["a", "b", "Top"]);
+        assert!(deep_sidebar(1).contains("<a href=\"/a/b/\">b</a>"));
+
+        assert_eq!(labels(&deep_sidebar(2)), ["a", "b", "c", "Top"]);
+        assert!(deep_sidebar(2).contains("<a href=\"/a/b/c/\">c</a>"));
+
+        let sidebar = deep_sidebar(3);
+        assert_eq!(labels(&sidebar), ["a", "b", "c", "Deep", "Top"]);
+        assert!(sidebar.contains("<a href=\"/a/b/c/deep\">Deep</a>"));
+        assert_eq!(sidebar.matches("<details>").count(), 3);
+        assert_eq!(deep_sidebar(DEFAULT_NAV_DEPTH), sidebar);
+    }
+
+    #[test]
+    fn nested_tree_opens_the_ancestors_of_the_current_page() {
+        let dir = wiki(&[
+            ("guide/setup/linux/install.md", "# Install\n"),
+            ("guide/setup/windows.md", "# Windows\n"),
+            ("guide/faq.md", "# FAQ\n"),
+            ("reference/api.md", "# API\n"),
+        ]);
+        let sidebar = navigation(&dir).build_sidebar_html("guide/setup/linux/install").unwrap();
+
+        assert!(sidebar.contains("<li class=\"current\"><a href=\"/guide/setup/linux/install\">Install</a></li>"), "{sidebar}");
+        assert!(sidebar.contains("<li class=\"nav-item has-sub current\"><details open><summary>guide</summary>"));
+        assert!(sidebar.contains("<li class=\"has-sub current\"><details open><summary>setup</summary>"));
+        assert!(sidebar.contains("<li class=\"has-sub current\"><details open><summary>linux</summary>"));
+        // Branches off the current path stay collapsed
+        assert!(sidebar.contains("<li class=\"nav-item has-sub\"><details><summary>reference</summary>"));
+        assert!(sidebar.contains("<li><a href=\"/guide/faq\">FAQ</a></li>"));
+    }
+
+    #[test]
+    fn drafts_are_hidden_unless_enabled() {
+        let dir = wiki(&[("guide/plan.md", "---\ndraft: true\n---\n# Plan\n"), ("guide/intro.md", "# Intro\n")]);
+        assert_eq!(labels(&navigation(&dir).build_sidebar_html("").unwrap()), ["guide", "Intro"]);
+        assert_eq!(
+            labels(&navigation(&dir).with_drafts(true).build_sidebar_html("").unwrap()),
+            ["guide", "Intro", "Plan"]
+        );
+    }
+
+    #[test]
+    fn excluded_hidden_and_index_entries_are_skipped() {
+        let dir = wiki(&[
+            ("index.md", "# Home\n"),
+            (".hidden.md", "# Hidden\n"),
+            ("drafts/idea.md", "# Idea\n"),
+            ("guide/index.md", "# Guide\n"),
+            ("guide/intro.md", "# Intro\n"),
+            ("notes.txt", "plain text\n"),
+        ]);
+        let file_service = FileService::new(dir.path().to_path_buf()).with_exclude(PathFilter::new(&["drafts".to_string()]));
+        let sidebar = NavigationComponent::new(file_service).build_sidebar_html("").unwrap();
+        assert_eq!(labels(&sidebar), ["guide", "Intro", "notes.txt"]);
+    }
+
+    #[test]
+    fn toc_follows_the_tree() {
+        let dir = wiki(&[("a.md", "# A\n")]);
+        let navigation = navigation(&dir);
+        let toc = "<ul><li><a href=\"#x\">X</a></li></ul>";
+        let sidebar = navigation.build_sidebar_with_toc("a", toc).unwrap();
+        assert!(sidebar.starts_with(&navigation.build_sidebar_html("a").unwrap()));
+        assert!(sidebar.ends_with(&format!("<div class=\"sidebar-toc\"><h4 class=\"sidebar-toc-title\">On This Page</h4>{toc}</div>")));
+        assert_eq!(navigation.build_sidebar_with_toc("a", "").unwrap(), navigation.build_sidebar_html("a").unwrap());
+    }
 }
@@ -1,14 +1,245 @@
-use log::{debug, info};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+use log::{debug, info, warn};
 use crate::errors::WikiError;
+use crate::services::SearchIndex;
+use crate::types::TemplateContext;
+use crate::utils::{escape_attr, escape_html, format_rfc3339, page_url};
+
+/// Default location of the page shell template
+const DEFAULT_TEMPLATE_PATH: &str = "static/html/base.html";
+
+/// Site name used when none is configured
+pub const DEFAULT_SITE_TITLE: &str = "Strata Wiki";
+
+/// Colour schemes a site can use; `auto` follows the browser's preference
+pub const THEMES: &[&str] = &["auto", "light", "dark"];
+
+/// Theme used when none is configured, matching the browser's preference
+pub const DEFAULT_THEME: &str = "auto";
+
+/// Button substituted for `{{THEME_TOGGLE}}` when enabled
+///
+/// The script switches the page between light and dark and remembers the
+/// choice in `localStorage`.
+const THEME_TOGGLE: &str = concat!(
+    "<button class=\"theme-toggle glass\" type=\"button\" aria-label=\"Toggle dark mode\">◐</button>",
+    "<script>(function(){var r=document.documentElement,k=\"strata-theme\",b=document.currentScript.previousElementSibling;",
+    "function set(t){r.classList.remove(\"theme-auto\",\"theme-light\",\"theme-dark\");r.classList.add(\"theme-\"+t)}",
+    "b.addEventListener(\"click\",function(){var dark=r.classList.contains(\"theme-dark\")||",
+    "(r.classList.contains(\"theme-auto\")&&matchMedia(\"(prefers-color-scheme:dark)\").matches);",
+    "var t=dark?\"light\":\"dark\";set(t);localStorage.setItem(k,t)})})()</script>",
+);
+
+/// Head script applying the theme saved by the toggle before the page is painted
+const THEME_SCRIPT: &str = concat!(
+    "<script>(function(){try{var t=localStorage.getItem(\"strata-theme\"),r=document.documentElement;",
+    "if(t===\"light\"||t===\"dark\"){r.classList.remove(\"theme-auto\",\"theme-light\",\"theme-dark\");",
+    "r.classList.add(\"theme-\"+t)}}catch(e){}})()</script>",
+);
+
+/// Stylesheet link substituted for the `{{STYLE}}` placeholder
+const STYLE_LINK: &str = "<link rel=\"stylesheet\" href=\"/static/css/strata.css\">";
 
 /// Component for handling HTML template rendering
-pub struct TemplateComponent;
+pub struct TemplateComponent {
+    template_path: PathBuf,
+    /// Re-read the template on every render instead of using the cached copy
+    dev_mode: bool,
+    /// Template loaded at construction, `None` when the file is missing
+    cached_template: Option<String>,
+    /// Site name for `{{SITE_TITLE}}` and the `<title>` suffix
+    site_title: String,
+    /// Colour scheme, one of `THEMES`, set as `theme-<name>` by `{{THEME_CLASS}}`
+    theme: String,
+    /// Fill `{{THEME_TOGGLE}}` with a light/dark switch
+    theme_toggle: bool,
+    /// Index the `{{RECENT}}` list is read from, with the number of pages to list
+    recent: Option<(Arc<SearchIndex>, usize)>,
+    /// When the component was built; configuration and the cached template date from then
+    created: SystemTime,
+}
 
 impl TemplateComponent {
-    /// Create a new template component
+    /// Create a new template component using the default `base.html`
     pub fn new() -> Self {
-        debug!("Creating new TemplateComponent");
-        Self
+        Self::with_template(PathBuf::from(DEFAULT_TEMPLATE_PATH), false)
+    }
+
+    /// Create a template component for a specific `base.html`
+    ///
+    /// The template is read once here; in dev mode it is re-read on every render
+    /// so edits show up without a restart.
+    pub fn with_template(template_path: PathBuf, dev_mode: bool) -> Self {
+        debug!("Creating new TemplateComponent with template: {:?} (dev mode: {})", template_path, dev_mode);
+        let cached_template = if dev_mode {
+            None
+        } else {
+            Self::load_template(&template_path)
+        };
+        Self {
+            template_path,
+            dev_mode,
+            cached_template,
+            site_title: DEFAULT_SITE_TITLE.to_string(),
+            theme: DEFAULT_THEME.to_string(),
+            theme_toggle: false,
+            recent: None,
+            created: SystemTime::now(),
+        }
+    }
+
+    /// Use a different site name in page titles and the `{{SITE_TITLE}}` placeholder
+    pub fn with_site_title(mut self, site_title: &str) -> Self {
+        self.site_title = site_title.to_string();
+        self
+    }
+
+    /// Use a colour scheme from `THEMES`, optionally with a button to switch it
+    pub fn with_theme(mut self, theme: &str, toggle: bool) -> Self {
+        self.theme = theme.to_string();
+        self.theme_toggle = toggle;
+        self
+    }
+
+    /// Class naming the theme, e.g. `theme-dark`, for the `<html>` element
+    pub fn theme_class(&self) -> String {
+        format!("theme-{}", self.theme)
+    }
+
+    /// Stylesheet link for the `<head>`, followed by the script restoring a
+    /// theme chosen with the toggle so the page does not flash the default first
+    pub fn head_styles(&self) -> String {
+        let script = if self.theme_toggle { THEME_SCRIPT } else { "" };
+        format!("{}{}", STYLE_LINK, script)
+    }
+
+    /// List the `limit` most recently modified pages in `index` for `{{RECENT}}`
+    pub fn with_recent_pages(mut self, index: Arc<SearchIndex>, limit: usize) -> Self {
+        self.recent = (limit > 0).then_some((index, limit));
+        self
+    }
+
+    /// Fingerprint and last change of what shapes a page besides its source and
+    /// the sidebar: the settings this component was built with, the template
+    /// file in dev mode, and the `{{RECENT}}` list when the template shows it
+    ///
+    /// Nothing is rendered, so this can validate a conditional request up front.
+    pub fn version(&self) -> (u64, SystemTime) {
+        let mut hasher = DefaultHasher::new();
+        let mut modified = self.created;
+        self.created.hash(&mut hasher);
+        if self.dev_mode && let Ok(metadata) = std::fs::metadata(&self.template_path) {
+            metadata.len().hash(&mut hasher);
+            if let Ok(template_modified) = metadata.modified() {
+                template_modified.hash(&mut hasher);
+                modified = modified.max(template_modified);
+            }
+        }
+        if let Some((index, limit)) = &self.recent
+            && self.base_template().is_some_and(|template| placeholders(&template).contains(&"RECENT"))
+        {
+            for page in index.recent_pages(*limit) {
+                page.path.hash(&mut hasher);
+                page.modified.hash(&mut hasher);
+                modified = modified.max(page.modified);
+            }
+        }
+        (hasher.finish(), modified)
+    }
+
+    /// "Recently updated" section linking the newest pages, or an empty string
+    /// when none are configured
+    pub fn recent_pages_html(&self) -> String {
+        let Some((index, limit)) = &self.recent else {
+            return String::new();
+        };
+        let pages = index.recent_pages(*limit);
+        if pages.is_empty() {
+            return String::new();
+        }
+
+        let mut html = String::from("<section class=\"recent-pages\"><h2>Recently updated</h2><ul>");
+        for page in pages {
+            html.push_str(&format!(
+                "<li><a href=\"{}\">{}</a>",
+                escape_attr(&page_url(&page.path)),
+                escape_html(&page.title)
+            ));
+            if let Some(stamp) = format_rfc3339(page.modified) {
+                html.push_str(&format!(
+                    " <time datetime=\"{}\">{}</time>",
+                    escape_attr(&stamp),
+                    escape_html(stamp.get(..10).unwrap_or(&stamp))
+                ));
+            }
+            html.push_str("</li>");
+        }
+        html.push_str("</ul></section>");
+        html
+    }
+
+    /// Document title such as `Intro — My Wiki`, or just the site name for untitled pages
+    fn document_title(&self, title: &str) -> String {
+        if title.trim().is_empty() || title == self.site_title {
+            escape_html(&self.site_title)
+        } else {
+            format!("{} — {}", escape_html(title), escape_html(&self.site_title))
+        }
+    }
+
+    /// Description and OpenGraph `<meta>` tags for a page
+    ///
+    /// Untitled pages use the site name as their `og:title`; the description
+    /// tags are left out when there is no description.
+    fn meta_tags(&self, title: &str, description: Option<&str>) -> String {
+        let og_title = if title.trim().is_empty() { &self.site_title } else { title };
+        let mut tags = format!(
+            "<meta property=\"og:title\" content=\"{}\">\n    <meta property=\"og:site_name\" content=\"{}\">",
+            escape_attr(og_title),
+            escape_attr(&self.site_title)
+        );
+        if let Some(description) = description {
+            tags.push_str(&format!(
+                "\n    <meta name=\"description\" content=\"{0}\">\n    <meta property=\"og:description\" content=\"{0}\">",
+                escape_attr(description)
+            ));
+        }
+        tags
+    }
+
+    /// Read the template file, logging when it is unavailable
+    fn load_template(path: &PathBuf) -> Option<String> {
+        match std::fs::read_to_string(path) {
+            Ok(template) => {
+                debug!("Loaded page template from {:?}", path);
+                let unknown: Vec<&str> = placeholders(&template)
+                    .into_iter()
+                    .filter(|name| !PLACEHOLDERS.contains(name))
+                    .collect();
+                if !unknown.is_empty() {
+                    warn!("Page template {:?} has unknown placeholders, they will be left empty: {}", path, unknown.join(", "));
+                }
+                Some(template)
+            }
+            Err(e) => {
+                warn!("Page template {:?} unavailable ({}), using built-in shell", path, e);
+                None
+            }
+        }
+    }
+
+    /// Current page template, if one is available
+    fn base_template(&self) -> Option<Cow<'_, str>> {
+        if self.dev_mode {
+            Self::load_template(&self.template_path).map(Cow::Owned)
+        } else {
+            self.cached_template.as_deref().map(Cow::Borrowed)
+        }
     }
 
     /// Render a page with navigation
@@ -22,7 +253,14 @@ impl TemplateComponent {
         debug!("Rendering page with navigation, title: '{}'", title);
         let start_time = std::time::Instant::now();
         
-        let html = self.render_shell_template(sidebar, content, fab, title)?;
+        let html = self.render_shell_template(&TemplateContext {
+            title: title.to_string(),
+            content: content.to_string(),
+            sidebar: sidebar.to_string(),
+            fab: fab.to_string(),
+            toc: None,
+            description: None,
+        })?;
         
         let duration = start_time.elapsed();
         info!("Page with navigation rendered in {:?}ms, title: '{}'", duration.as_millis(), title);
@@ -30,19 +268,27 @@ impl TemplateComponent {
         Ok(html)
     }
 
-    /// Render a page with navigation and table of contents
+    /// Render a page with navigation, table of contents and an optional description
     pub fn render_page_with_nav_and_toc(
         &self,
         sidebar: &str,
         content: &str,
         fab: &str,
         title: &str,
-        _toc: &str,
+        toc: &str,
+        description: Option<&str>,
     ) -> Result<String, WikiError> {
         debug!("Rendering page with navigation and TOC, title: '{}'", title);
         let start_time = std::time::Instant::now();
         
-        let html = self.render_shell_template(sidebar, content, fab, title)?;
+        let html = self.render_shell_template(&TemplateContext {
+            title: title.to_string(),
+            content: content.to_string(),
+            sidebar: sidebar.to_string(),
+            fab: fab.to_string(),
+            toc: Some(toc.to_string()).filter(|toc| !toc.is_empty()),
+            description: description.map(str::to_string),
+        })?;
         
         let duration = start_time.elapsed();
         info!("Page with navigation and TOC rendered in {:?}ms, title: '{}'", duration.as_millis(), title);
@@ -50,28 +296,91 @@ impl TemplateComponent {
         Ok(html)
     }
 
+    /// Render the "page not found" page inside the normal shell
+    pub fn render_not_found(&self, sidebar: &str, fab: &str, path: &str) -> Result<String, WikiError> {
+        debug!("Rendering 404 page for '{}'", path);
+        let content = format!(
+            r#"<div class="error-container">
+    <div class="error-icon">404</div>
+    <h1 class="error-title">Page Not Found</h1>
+    <p class="error-message">There is no page at <code>/{}</code>. It might have been deleted, renamed, or you may have typed the wrong URL.</p>
+    <div class="error-actions">
+        <a href="/" class="error-btn primary">🏠 Go Home</a>
+        <a href="/search" class="error-btn secondary">🔍 Search Wiki</a>
+    </div>
+</div>"#,
+            escape_html(path)
+        );
+        self.render_shell_template(&TemplateContext {
+            title: "Page Not Found".to_string(),
+            content,
+            sidebar: sidebar.to_string(),
+            fab: fab.to_string(),
+            toc: None,
+            description: None,
+        })
+    }
+
     /// Render the shell template with all components
-    fn render_shell_template(
-        &self,
-        sidebar: &str,
-        content: &str,
-        fab: &str,
-        title: &str,
-    ) -> Result<String, WikiError> {
+    ///
+    /// Templates place the table of contents with `{{TOC}}`, which is left empty
+    /// for pages without one, the description and OpenGraph tags with `{{META}}`,
+    /// the recently updated pages with `{{RECENT}}`, which are only listed
+    /// when the template uses it, and the theme with `{{THEME_CLASS}}` and
+    /// `{{THEME_TOGGLE}}`. With the toggle, `{{STYLE}}` also restores the saved
+    /// theme, since it is the one placeholder every template has in `<head>`.
+    pub fn render_shell_template(&self, context: &TemplateContext) -> Result<String, WikiError> {
         debug!("Rendering shell template");
+        let TemplateContext { title, content, sidebar, fab, toc, description } = context;
+        let meta = self.meta_tags(title, description.as_deref());
+        let theme_class = escape_attr(&self.theme_class());
+        let theme_toggle = if self.theme_toggle { THEME_TOGGLE } else { "" };
+        let style = self.head_styles();
+        
+        if let Some(template) = self.base_template() {
+            let toc_html = match toc {
+                Some(toc) => format!("<nav class=\"page-toc\">{}</nav>", toc),
+                None => String::new(),
+            };
+            let document_title = self.document_title(title);
+            let site_title = escape_html(&self.site_title);
+            let recent = if placeholders(&template).contains(&"RECENT") {
+                self.recent_pages_html()
+            } else {
+                String::new()
+            };
+            let html = substitute(&template, |name| match name {
+                "TITLE" => Some(document_title.as_str()),
+                "SITE_TITLE" => Some(site_title.as_str()),
+                "STYLE" => Some(style.as_str()),
+                "META" => Some(meta.as_str()),
+                "SIDEBAR" => Some(sidebar.as_str()),
+                "TOC" => Some(toc_html.as_str()),
+                "CONTENT" => Some(content.as_str()),
+                "FAB" => Some(fab.as_str()),
+                "RECENT" => Some(recent.as_str()),
+                "THEME_CLASS" => Some(theme_class.as_str()),
+                "THEME_TOGGLE" => Some(theme_toggle),
+                _ => None,
+            });
+            debug!("Shell template rendered from {:?}", self.template_path);
+            return Ok(html);
+        }
         
         let mut html = String::new();
         html.push_str("<!doctype html>\n");
-        html.push_str("<html lang=\"en\">\n");
+        html.push_str(&format!("<html lang=\"en\" class=\"{}\">\n", theme_class));
         html.push_str("<head>\n");
         html.push_str("    <meta charset=\"utf-8\">\n");
         html.push_str("    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
-        html.push_str(&format!("    <title>{} - Strata Wiki</title>\n", title));
-        html.push_str("    <link rel=\"stylesheet\" href=\"/static/css/strata.css\">\n");
+        html.push_str(&format!("    <title>{}</title>\n", self.document_title(title)));
+        html.push_str(&format!("    {}\n", meta));
+        html.push_str(&format!("    {}\n", style));
         html.push_str("</head>\n");
         html.push_str("<body>\n");
         html.push_str("    <div class=\"layout\">\n");
         html.push_str("        <aside class=\"sidebar glass\">");
+        html.push_str(&format!("<a class=\"site-title\" href=\"/\">{}</a>", escape_html(&self.site_title)));
         html.push_str(sidebar);
         html.push_str("</aside>\n");
         html.push_str("        <main class=\"content\">\n");
@@ -82,6 +391,7 @@ impl TemplateComponent {
         html.push_str("    </div>\n");
         html.push_str("    <a class=\"back-to-top glass\" href=\"#top\" aria-label=\"Back to top\">↑</a>\n");
         html.push_str(fab);
+        html.push_str(theme_toggle);
         html.push_str("\n</body>\n");
         html.push_str("</html>");
 
@@ -90,8 +400,205 @@ impl TemplateComponent {
     }
 }
 
+/// Placeholders filled in by `render_shell_template`
+const PLACEHOLDERS: &[&str] = &["TITLE", "SITE_TITLE", "META", "STYLE", "SIDEBAR", "TOC", "CONTENT", "FAB", "RECENT", "THEME_CLASS", "THEME_TOGGLE"];
+
+/// Names of the `{{NAME}}` placeholders in a template, in order of appearance
+fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        match placeholder_name(after) {
+            Some((name, len)) => {
+                names.push(name);
+                rest = &after[len..];
+            }
+            None => rest = after,
+        }
+    }
+    names
+}
+
+/// Parse `NAME}}` at the start of `text`, returning the trimmed name and the length consumed
+fn placeholder_name(text: &str) -> Option<(&str, usize)> {
+    let end = text.find("}}")?;
+    let name = text[..end].trim();
+    if name.is_empty() || name.contains(['{', '}', '\n']) {
+        return None;
+    }
+    Some((name, end + 2))
+}
+
+/// Fill a template's placeholders in one pass, so substituted values are never rescanned
+///
+/// Placeholders `value` has no value for are dropped from the output.
+fn substitute<'a>(template: &str, value: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut html = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        html.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match placeholder_name(after) {
+            Some((name, len)) => {
+                match value(name) {
+                    Some(text) => html.push_str(text),
+                    None => debug!("Dropping unknown template placeholder {{{{{}}}}}", name),
+                }
+                rest = &after[len..];
+            }
+            None => {
+                html.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    html.push_str(rest);
+    html
+}
+
 impl Default for TemplateComponent {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(content: &str) -> TemplateContext {
+        TemplateContext {
+            title: "Page".to_string(),
+            content: content.to_string(),
+            sidebar: String::new(),
+            fab: String::new(),
+            toc: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn template_is_read_once_and_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("base.html");
+        std::fs::write(&path, "<main>{{CONTENT}}</main>").unwrap();
+
+        let templates = TemplateComponent::with_template(path.clone(), false);
+        assert_eq!(templates.render_shell_template(&context("one")).unwrap(), "<main>one</main>");
+
+        // Later renders use the copy read at construction, not the file
+        std::fs::write(&path, "<div>{{CONTENT}}</div>").unwrap();
+        assert_eq!(templates.render_shell_template(&context("two")).unwrap(), "<main>two</main>");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(templates.render_shell_template(&context("three")).unwrap(), "<main>three</main>");
+    }
+
+    #[test]
+    fn dev_mode_rereads_the_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("base.html");
+        std::fs::write(&path, "<main>{{CONTENT}}</main>").unwrap();
+
+        let templates = TemplateComponent::with_template(path.clone(), true);
+        assert_eq!(templates.render_shell_template(&context("one")).unwrap(), "<main>one</main>");
+        std::fs::write(&path, "<div>{{CONTENT}}</div>").unwrap();
+        assert_eq!(templates.render_shell_template(&context("two")).unwrap(), "<div>two</div>");
+    }
+
+    fn template(dir: &tempfile::TempDir, html: &str) -> TemplateComponent {
+        let path = dir.path().join("base.html");
+        std::fs::write(&path, html).unwrap();
+        TemplateComponent::with_template(path, false)
+    }
+
+    #[test]
+    fn site_title_fills_its_placeholder_and_suffixes_page_titles() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = template(&dir, "<title>{{TITLE}}</title><a>{{SITE_TITLE}}</a>").with_site_title("My <Wiki>");
+        assert_eq!(
+            templates.render_shell_template(&context("")).unwrap(),
+            "<title>Page — My &lt;Wiki&gt;</title><a>My &lt;Wiki&gt;</a>"
+        );
+        let untitled = TemplateContext { title: String::new(), ..context("") };
+        assert_eq!(
+            templates.render_shell_template(&untitled).unwrap(),
+            "<title>My &lt;Wiki&gt;</title><a>My &lt;Wiki&gt;</a>"
+        );
+    }
+
+    #[test]
+    fn built_in_shell_shows_the_site_title() {
+        let templates = TemplateComponent::with_template(PathBuf::from("/nonexistent/base.html"), false).with_site_title("Team Docs");
+        let html = templates.render_shell_template(&context("")).unwrap();
+        assert!(html.contains("<title>Page — Team Docs</title>"), "{html}");
+        assert!(html.contains("<a class=\"site-title\" href=\"/\">Team Docs</a>"));
+    }
+
+    #[test]
+    fn toc_placeholder_receives_the_table_of_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = template(&dir, "{{TOC}}<main>{{CONTENT}}</main>");
+        let with_toc = TemplateContext { toc: Some("<ul class=\"toc\"></ul>".to_string()), ..context("body") };
+        assert_eq!(
+            templates.render_shell_template(&with_toc).unwrap(),
+            "<nav class=\"page-toc\"><ul class=\"toc\"></ul></nav><main>body</main>"
+        );
+        assert_eq!(templates.render_shell_template(&context("body")).unwrap(), "<main>body</main>");
+    }
+
+    #[test]
+    fn templates_without_a_toc_placeholder_still_render() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = template(&dir, "<main>{{CONTENT}}</main>");
+        let with_toc = TemplateContext { toc: Some("<ul class=\"toc\"></ul>".to_string()), ..context("body") };
+        assert_eq!(templates.render_shell_template(&with_toc).unwrap(), "<main>body</main>");
+    }
+
+    #[test]
+    fn unknown_placeholders_are_dropped_with_a_warning() {
+        let log = crate::logger::captured_log();
+        let dir = tempfile::tempdir().unwrap();
+        let templates = template(&dir, "<main>{{CONTENT}}</main>{{FOOTR}}{{ SIDEBAR }}{{not closed");
+        assert_eq!(templates.render_shell_template(&context("body")).unwrap(), "<main>body</main>{{not closed");
+
+        let log = std::fs::read_to_string(log).unwrap();
+        let warning = log.lines().find(|line| line.contains("FOOTR")).unwrap_or_else(|| panic!("no warning in {log}"));
+        assert!(warning.contains(" WARN ") && warning.contains("unknown placeholders"), "{warning}");
+        assert!(!warning.contains("CONTENT") && !warning.contains("SIDEBAR"));
+    }
+
+    #[test]
+    fn configured_theme_sets_the_html_class() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = template(&dir, "<html class=\"{{THEME_CLASS}}\"><head>{{STYLE}}</head>{{THEME_TOGGLE}}</html>")
+            .with_theme("dark", false);
+        assert_eq!(
+            templates.render_shell_template(&context("")).unwrap(),
+            format!("<html class=\"theme-dark\"><head>{}</head></html>", STYLE_LINK)
+        );
+
+        let built_in = TemplateComponent::with_template(dir.path().join("missing.html"), false).with_theme("light", false);
+        let html = built_in.render_shell_template(&context("")).unwrap();
+        assert!(html.contains("<html lang=\"en\" class=\"theme-light\">"), "{html}");
+        assert!(!html.contains("<script>"));
+        assert_eq!(TemplateComponent::new().theme_class(), "theme-auto");
+    }
+
+    #[test]
+    fn theme_toggle_restores_the_saved_theme_in_the_head() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = template(&dir, "<head>{{STYLE}}</head><body>{{THEME_TOGGLE}}</body>").with_theme("auto", true);
+        let html = templates.render_shell_template(&context("")).unwrap();
+        let head = &html[..html.find("</head>").unwrap()];
+        assert!(head.contains(STYLE_LINK) && head.contains("localStorage.getItem(\"strata-theme\")"), "{html}");
+        let body = &html[html.find("<body>").unwrap()..];
+        assert!(body.contains("class=\"theme-toggle glass\""));
+        assert!(body.contains("localStorage.setItem(k,t)"));
+
+        let built_in = TemplateComponent::with_template(dir.path().join("missing.html"), false).with_theme("auto", true);
+        let html = built_in.render_shell_template(&context("")).unwrap();
+        let head = &html[..html.find("</head>").unwrap()];
+        assert!(head.contains(THEME_SCRIPT), "{html}");
+    }
+}
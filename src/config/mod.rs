@@ -1,12 +1,208 @@
-use std::path::PathBuf;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use log::{info, warn};
+use crate::components::navigation::DEFAULT_NAV_DEPTH;
+use crate::components::templates::{DEFAULT_SITE_TITLE, DEFAULT_THEME, THEMES};
+use crate::services::file_service::{DEFAULT_CONTENT_CACHE_BYTES, DEFAULT_MAX_FILE_BYTES};
+
+/// Longest request path accepted unless configured otherwise, in bytes
+pub const DEFAULT_MAX_PATH_LEN: usize = 1024;
+
+/// Config file read by `Config::load` unless `STRATA_CONFIG` names another
+pub const DEFAULT_CONFIG_FILE: &str = "strata.toml";
+
+/// Environment variables and the config keys they set
+const ENV_KEYS: &[(&str, &str)] = &[
+    ("STRATA_BASE_DIR", "base_dir"),
+    ("STRATA_STATIC_DIR", "static_dir"),
+    ("STRATA_TEMPLATE_DIR", "template_dir"),
+    ("STRATA_PORT", "port"),
+    ("STRATA_HOST", "host"),
+    ("STRATA_LISTEN", "listen"),
+    ("STRATA_SITE_TITLE", "site_title"),
+    ("STRATA_THEME", "theme"),
+    ("STRATA_THEME_TOGGLE", "theme_toggle"),
+    ("STRATA_DEV_MODE", "dev_mode"),
+    ("STRATA_SHOW_DRAFTS", "show_drafts"),
+    ("STRATA_SEARCH_REINDEX_SECS", "search_reindex_secs"),
+    ("STRATA_SLOW_REQUEST_MS", "slow_request_ms"),
+    ("STRATA_MAX_PATH_LEN", "max_path_len"),
+    ("STRATA_MAX_FILE_BYTES", "max_file_bytes"),
+    ("STRATA_CONTENT_CACHE_BYTES", "content_cache_bytes"),
+    ("STRATA_EXCLUDE", "exclude"),
+    ("STRATA_NAV_DEPTH", "nav_depth"),
+    ("STRATA_FAB_ACTIONS", "fab_actions"),
+    ("STRATA_EDIT_URL", "edit_url"),
+    ("STRATA_RECENT_PAGES", "recent_pages"),
+    ("STRATA_ALLOW_RAW_HTML", "markdown.allow_raw_html"),
+    ("STRATA_MATH", "markdown.math"),
+];
 
 /// Application configuration and constants
 pub struct Config {
     pub base_dir: Arc<PathBuf>,
     pub static_dir: Arc<PathBuf>,
+    /// Theme directory with a `base.html` and assets that take precedence over `static_dir`
+    pub template_dir: Option<PathBuf>,
     pub port: u16,
     pub host: String,
+    /// Listen address overriding `host` and `port`: `tcp://host:port` or
+    /// `unix:/path/to/socket`; empty to use `host` and `port`
+    pub listen: String,
+    /// Site name shown in page titles
+    pub site_title: String,
+    /// Colour scheme: `auto` to follow the browser, `light` or `dark`
+    pub theme: String,
+    /// Show a button that switches between light and dark
+    pub theme_toggle: bool,
+    /// Reload templates on every request instead of caching them
+    pub dev_mode: bool,
+    /// Serve, list and index pages marked `draft: true`; always on in dev mode
+    pub show_drafts: bool,
+    /// Seconds between background rebuilds of the search index
+    pub search_reindex_secs: u64,
+    /// Requests taking longer than this many milliseconds are logged as slow
+    pub slow_request_ms: u64,
+    /// Longest request path, in bytes; longer page, raw and static requests get 400
+    pub max_path_len: usize,
+    /// Largest file, in bytes, that is read into memory to render, index or serve
+    pub max_file_bytes: u64,
+    /// Memory, in bytes, for caching unchanged page sources; 0 disables the cache,
+    /// which is always off in dev mode
+    pub content_cache_bytes: usize,
+    /// Glob patterns for paths hidden from navigation, listings and search
+    pub exclude: Vec<String>,
+    /// Sub-directory levels expanded in the sidebar; 0 lists only top-level entries,
+    /// all levels are shown by default
+    pub nav_depth: usize,
+    /// Page actions in the floating action bar: built-in names (`raw`, `edit`,
+    /// `print`, `history`) or `title|href|class` with `{path}` or `{file}` in the href
+    pub fab_actions: Vec<String>,
+    /// Link to a page's source for editing, e.g.
+    /// `https://github.com/org/repo/edit/main/wiki/{path}`, where `{path}` is the
+    /// page's markdown file relative to the wiki root; empty for no link
+    pub edit_url: String,
+    /// Pages listed as recently updated on the home page listing and by the
+    /// `{{RECENT}}` template placeholder; 0 disables the list
+    pub recent_pages: usize,
+    /// Search ranking and matching options
+    pub search: SearchConfig,
+    /// Markdown rendering options
+    pub markdown: MarkdownConfig,
+}
+
+/// Search ranking weights and matching options
+#[derive(Debug, Clone)]
+pub struct SearchConfig {
+    /// Score for the term appearing anywhere in the page
+    pub phrase_weight: f32,
+    /// Score per query word (longer than 2 characters) appearing in the page
+    pub word_weight: f32,
+    /// Score for the term appearing in the first line
+    pub title_phrase_weight: f32,
+    /// Score per query word appearing in the first line
+    pub title_word_weight: f32,
+    /// Score for the term appearing in the frontmatter
+    pub frontmatter_weight: f32,
+    /// Score per heading containing the term
+    pub heading_weight: f32,
+    /// Match only against page titles and frontmatter, ignoring the body
+    pub title_only: bool,
+    /// Results scoring below this are dropped
+    pub min_relevance: f32,
+    /// Non-overlapping excerpts shown per result
+    pub max_excerpts: usize,
+    /// Match query terms with their exact case
+    pub case_sensitive: bool,
+    /// Only match terms that are not part of a longer word
+    pub whole_word: bool,
+    /// Also match words a small number of edits away from the query words
+    pub fuzzy: bool,
+    /// Fuzzy matching runs only when exact matching finds fewer results than this
+    pub fuzzy_min_results: usize,
+    /// Score per query word matched only approximately
+    pub fuzzy_weight: f32,
+    /// Recently updated pages suggested for empty or very short queries
+    pub suggestion_count: usize,
+    /// Directory levels indexed for search; 1 covers only top-level pages,
+    /// all levels are indexed by default
+    pub max_search_depth: usize,
+}
+
+impl SearchConfig {
+    /// Create search options with the default weights
+    pub fn new() -> Self {
+        Self {
+            phrase_weight: 20.0,
+            word_weight: 3.0,
+            title_phrase_weight: 15.0,
+            title_word_weight: 5.0,
+            frontmatter_weight: 10.0,
+            heading_weight: 8.0,
+            title_only: false,
+            min_relevance: 0.0,
+            max_excerpts: 1,
+            case_sensitive: false,
+            whole_word: false,
+            fuzzy: false,
+            fuzzy_min_results: 3,
+            fuzzy_weight: 1.0,
+            suggestion_count: 5,
+            max_search_depth: usize::MAX,
+        }
+    }
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Markdown rendering options
+#[derive(Debug, Clone)]
+pub struct MarkdownConfig {
+    /// Pass HTML in pages through unescaped, both block-level tag lines and tags
+    /// inside text; when off, every `<` and `>` outside code is shown as text
+    pub allow_raw_html: bool,
+    /// Wrap `$...$` and `$$...$$` in math containers for a client-side typesetter
+    pub math: bool,
+    /// Shallowest heading level listed in the table of contents
+    pub toc_min_level: usize,
+    /// Deepest heading level listed in the table of contents
+    pub toc_max_level: usize,
+    /// Reading speed used for the reading-time estimate
+    pub words_per_minute: usize,
+    /// Turn straight quotes into curly ones, `--` and `---` into en and em
+    /// dashes, and `...` into an ellipsis, outside code
+    pub smart_punctuation: bool,
+    /// Replace shortcodes such as `:rocket:` with their emoji, outside code
+    pub emoji: bool,
+    /// `TERM=expansion` pairs wrapped in `<abbr>` on their first use in a page
+    pub abbreviations: Vec<String>,
+}
+
+impl MarkdownConfig {
+    /// Create rendering options with the safe defaults
+    pub fn new() -> Self {
+        Self {
+            allow_raw_html: false,
+            math: false,
+            toc_min_level: 1,
+            toc_max_level: 6,
+            words_per_minute: 200,
+            smart_punctuation: false,
+            emoji: false,
+            abbreviations: Vec::new(),
+        }
+    }
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Config {
@@ -15,8 +211,27 @@ impl Config {
         Self {
             base_dir: Arc::new(PathBuf::from("wiki")),
             static_dir: Arc::new(PathBuf::from("static")),
+            template_dir: None,
             port: 5004,
             host: "0.0.0.0".to_string(),
+            listen: String::new(),
+            site_title: DEFAULT_SITE_TITLE.to_string(),
+            theme: DEFAULT_THEME.to_string(),
+            theme_toggle: false,
+            dev_mode: false,
+            show_drafts: false,
+            search_reindex_secs: 60,
+            slow_request_ms: 1000,
+            max_path_len: DEFAULT_MAX_PATH_LEN,
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            content_cache_bytes: DEFAULT_CONTENT_CACHE_BYTES,
+            exclude: Vec::new(),
+            nav_depth: DEFAULT_NAV_DEPTH,
+            fab_actions: vec!["raw".to_string(), "edit".to_string()],
+            edit_url: String::new(),
+            recent_pages: 5,
+            search: SearchConfig::new(),
+            markdown: MarkdownConfig::new(),
         }
     }
 
@@ -27,17 +242,249 @@ impl Config {
         port: Option<u16>,
         host: Option<String>,
     ) -> Self {
+        let defaults = Self::new();
         Self {
             base_dir: Arc::new(base_dir),
             static_dir: Arc::new(static_dir),
-            port: port.unwrap_or(5004),
-            host: host.unwrap_or_else(|| "0.0.0.0".to_string()),
+            port: port.unwrap_or(defaults.port),
+            host: host.unwrap_or_else(|| defaults.host.clone()),
+            ..defaults
+        }
+    }
+
+    /// Load configuration from defaults, then the config file, then the environment
+    pub fn load() -> Self {
+        let path = std::env::var("STRATA_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_FILE.to_string());
+        let mut config = Self::new();
+        if Path::new(&path).is_file() {
+            config.apply_file(Path::new(&path));
+        }
+        config.apply_env();
+        config
+    }
+
+    /// Create configuration from a TOML file, using defaults for missing keys
+    pub fn from_file(path: &Path) -> Self {
+        let mut config = Self::new();
+        config.apply_file(path);
+        config
+    }
+
+    /// Create configuration from `STRATA_*` environment variables, using defaults for unset ones
+    pub fn from_env() -> Self {
+        let mut config = Self::new();
+        config.apply_env();
+        config
+    }
+
+    /// Override settings with values from a TOML file
+    ///
+    /// Only the flat subset the config needs is understood: `key = value` lines
+    /// with strings, integers, floats, booleans and string arrays, plus
+    /// `[search]` and `[markdown]` tables. Arrays may span several lines and
+    /// their strings may contain commas; nested arrays, inline tables and
+    /// multi-line strings are not supported.
+    pub fn apply_file(&mut self, path: &Path) {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Failed to read config file {:?}: {}", path, e);
+                return;
+            }
+        };
+        info!("Loading configuration from {:?}", path);
+
+        let mut table = String::new();
+        let mut lines = content.lines().enumerate();
+        while let Some((number, line)) = lines.next() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                table = name.trim().to_string();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                warn!("{:?} line {}: expected `key = value`, ignoring", path, number + 1);
+                continue;
+            };
+            let key = if table.is_empty() {
+                key.trim().to_string()
+            } else {
+                format!("{}.{}", table, key.trim())
+            };
+
+            // An array continues on the following lines until its closing bracket
+            let mut value = value.trim().to_string();
+            while value.starts_with('[') && !array_is_closed(&value) {
+                let Some((_, next)) = lines.next() else {
+                    break;
+                };
+                value.push(' ');
+                value.push_str(strip_comment(next).trim());
+            }
+
+            match parse_toml_value(&value) {
+                Some(TomlValue::Scalar(value)) => self.set(&key, &value),
+                Some(TomlValue::Array(items)) => self.set_list(&key, items),
+                None => warn!("{:?} line {}: invalid value for '{}', ignoring", path, number + 1, key),
+            }
+        }
+    }
+
+    /// Override settings with values from `STRATA_*` environment variables
+    pub fn apply_env(&mut self) {
+        for (var, key) in ENV_KEYS {
+            if let Ok(value) = std::env::var(var) {
+                self.set(key, &value);
+            }
+        }
+    }
+
+    /// Set one setting from its textual value, keeping the current value if it does not parse
+    fn set(&mut self, key: &str, value: &str) {
+        fn parse<T: std::str::FromStr>(key: &str, value: &str, target: &mut T) {
+            match value.trim().parse() {
+                Ok(parsed) => *target = parsed,
+                Err(_) => warn!("Invalid value '{}' for config key '{}', keeping current setting", value, key),
+            }
+        }
+
+        match key {
+            "base_dir" => self.base_dir = Arc::new(PathBuf::from(value)),
+            "static_dir" => self.static_dir = Arc::new(PathBuf::from(value)),
+            "template_dir" => {
+                self.template_dir = if value.trim().is_empty() { None } else { Some(PathBuf::from(value)) }
+            }
+            "port" => parse(key, value, &mut self.port),
+            "host" => self.host = value.to_string(),
+            "listen" => self.listen = value.trim().to_string(),
+            "site_title" => self.site_title = value.to_string(),
+            "theme" => match value.trim().to_lowercase() {
+                theme if THEMES.contains(&theme.as_str()) => self.theme = theme,
+                _ => warn!("Invalid value '{}' for config key '{}', keeping current setting", value, key),
+            },
+            "theme_toggle" => parse(key, value, &mut self.theme_toggle),
+            "dev_mode" => parse(key, value, &mut self.dev_mode),
+            "show_drafts" => parse(key, value, &mut self.show_drafts),
+            "search_reindex_secs" => parse(key, value, &mut self.search_reindex_secs),
+            "slow_request_ms" => parse(key, value, &mut self.slow_request_ms),
+            "max_path_len" => parse(key, value, &mut self.max_path_len),
+            "max_file_bytes" => parse(key, value, &mut self.max_file_bytes),
+            "content_cache_bytes" => parse(key, value, &mut self.content_cache_bytes),
+            "nav_depth" => parse(key, value, &mut self.nav_depth),
+            "edit_url" => self.edit_url = value.trim().to_string(),
+            "recent_pages" => parse(key, value, &mut self.recent_pages),
+            "search.phrase_weight" => parse(key, value, &mut self.search.phrase_weight),
+            "search.word_weight" => parse(key, value, &mut self.search.word_weight),
+            "search.title_phrase_weight" => parse(key, value, &mut self.search.title_phrase_weight),
+            "search.title_word_weight" => parse(key, value, &mut self.search.title_word_weight),
+            "search.frontmatter_weight" => parse(key, value, &mut self.search.frontmatter_weight),
+            "search.heading_weight" => parse(key, value, &mut self.search.heading_weight),
+            "search.title_only" => parse(key, value, &mut self.search.title_only),
+            "search.min_relevance" => parse(key, value, &mut self.search.min_relevance),
+            "search.max_excerpts" => parse(key, value, &mut self.search.max_excerpts),
+            "search.case_sensitive" => parse(key, value, &mut self.search.case_sensitive),
+            "search.whole_word" => parse(key, value, &mut self.search.whole_word),
+            "search.fuzzy" => parse(key, value, &mut self.search.fuzzy),
+            "search.fuzzy_min_results" => parse(key, value, &mut self.search.fuzzy_min_results),
+            "search.fuzzy_weight" => parse(key, value, &mut self.search.fuzzy_weight),
+            "search.suggestion_count" => parse(key, value, &mut self.search.suggestion_count),
+            "search.max_search_depth" => parse(key, value, &mut self.search.max_search_depth),
+            "markdown.allow_raw_html" => parse(key, value, &mut self.markdown.allow_raw_html),
+            "markdown.math" => parse(key, value, &mut self.markdown.math),
+            "markdown.toc_min_level" => parse(key, value, &mut self.markdown.toc_min_level),
+            "markdown.toc_max_level" => parse(key, value, &mut self.markdown.toc_max_level),
+            "markdown.words_per_minute" => parse(key, value, &mut self.markdown.words_per_minute),
+            "markdown.smart_punctuation" => parse(key, value, &mut self.markdown.smart_punctuation),
+            "markdown.emoji" => parse(key, value, &mut self.markdown.emoji),
+            "fab_actions" | "exclude" | "markdown.abbreviations" => {
+                self.set_list(key, value.split(',').map(str::to_string).collect())
+            }
+            _ => warn!("Unknown config key '{}', ignoring", key),
+        }
+    }
+
+    /// Set a list setting, dropping blank items
+    fn set_list(&mut self, key: &str, items: Vec<String>) {
+        let items = items
+            .into_iter()
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect();
+        match key {
+            "fab_actions" => self.fab_actions = items,
+            "exclude" => self.exclude = items,
+            "markdown.abbreviations" => self.markdown.abbreviations = items,
+            _ => warn!("Config key '{}' does not take a list, ignoring", key),
+        }
+    }
+
+    /// Path of the page shell template, from the theme directory when one is set
+    pub fn template_path(&self) -> PathBuf {
+        match &self.template_dir {
+            Some(dir) => dir.join("base.html"),
+            None => self.static_dir.join("html").join("base.html"),
+        }
+    }
+
+    /// Get the TCP socket address for binding from `host` and `port`
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(resolve_host(&self.host), self.port)
+    }
+
+    /// Where to accept connections: `listen` when set, otherwise `host` and `port`
+    ///
+    /// `unix:` addresses name a socket path; `tcp://` addresses take a host and
+    /// port like the `host` setting. Unrecognized values fall back to `host`
+    /// and `port` with a warning.
+    pub fn listen_addr(&self) -> ListenAddr {
+        let listen = self.listen.trim();
+        if listen.is_empty() {
+            return ListenAddr::Tcp(self.socket_addr());
+        }
+
+        if let Some(path) = listen.strip_prefix("unix:") {
+            // Accept both `unix:/run/strata.sock` and `unix:///run/strata.sock`
+            let path = path.strip_prefix("//").unwrap_or(path);
+            if !path.is_empty() {
+                return ListenAddr::Unix(PathBuf::from(path));
+            }
+        } else if let Some(addr) = listen.strip_prefix("tcp://")
+            && let Some((host, port)) = addr.rsplit_once(':')
+            && let Ok(port) = port.parse()
+        {
+            return ListenAddr::Tcp(SocketAddr::new(resolve_host(host), port));
         }
+
+        warn!("Invalid listen address '{}', using {}:{} instead", self.listen, self.host, self.port);
+        ListenAddr::Tcp(self.socket_addr())
     }
+}
+
+/// Address the server accepts connections on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    /// Path of a Unix domain socket
+    Unix(PathBuf),
+}
 
-    /// Get the socket address for binding
-    pub fn socket_addr(&self) -> std::net::SocketAddr {
-        std::net::SocketAddr::from(([0, 0, 0, 0], self.port))
+/// Parse a host setting into an IP address
+///
+/// Accepts IPv4 or IPv6 addresses (optionally bracketed) or `localhost`;
+/// anything else falls back to all IPv4 interfaces with a warning.
+fn resolve_host(host: &str) -> IpAddr {
+    let trimmed = host.trim();
+    let trimmed = trimmed.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(trimmed);
+    if trimmed.eq_ignore_ascii_case("localhost") {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    } else {
+        trimmed.parse().unwrap_or_else(|_| {
+            warn!("Invalid host '{}', binding to 0.0.0.0 instead", host);
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+        })
     }
 }
 
@@ -46,3 +493,275 @@ impl Default for Config {
         Self::new()
     }
 }
+
+/// Drop a trailing `#` comment that is not inside a quoted string
+fn strip_comment(line: &str) -> &str {
+    let mut end = line.len();
+    scan_unquoted(line, |i, ch| {
+        if ch == '#' && i < end {
+            end = i;
+        }
+    });
+    &line[..end]
+}
+
+/// A parsed TOML value: scalars keep their text, arrays hold their strings
+#[derive(Debug, PartialEq)]
+enum TomlValue {
+    Scalar(String),
+    Array(Vec<String>),
+}
+
+/// Whether an array value has reached its closing bracket, ignoring brackets in strings
+fn array_is_closed(raw: &str) -> bool {
+    let mut depth = 0;
+    let mut closed = false;
+    scan_unquoted(raw, |_, ch| match ch {
+        '[' => depth += 1,
+        ']' => {
+            depth -= 1;
+            closed = depth == 0;
+        }
+        _ => {}
+    });
+    closed
+}
+
+/// Call `visit` with the position of every character outside a quoted string
+fn scan_unquoted(raw: &str, mut visit: impl FnMut(usize, char)) {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, ch) in raw.char_indices() {
+        match (quote, ch) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, c) => visit(i, c),
+        }
+        escaped = false;
+    }
+}
+
+/// Turn a raw TOML value into a scalar or an array of strings
+fn parse_toml_value(raw: &str) -> Option<TomlValue> {
+    let Some(inner) = raw.strip_prefix('[') else {
+        return parse_toml_scalar(raw).map(TomlValue::Scalar);
+    };
+    let inner = inner.strip_suffix(']')?;
+
+    let mut commas = Vec::new();
+    scan_unquoted(inner, |i, ch| if ch == ',' { commas.push(i) });
+    let mut items = Vec::new();
+    let mut start = 0;
+    for end in commas.into_iter().chain([inner.len()]) {
+        let item = inner[start..end].trim();
+        start = end + 1;
+        if item.is_empty() {
+            // A trailing comma is allowed; empty items elsewhere are not
+            if start > inner.len() {
+                break;
+            }
+            return None;
+        }
+        items.push(parse_toml_scalar(item)?);
+    }
+    Some(TomlValue::Array(items))
+}
+
+/// Turn a TOML scalar into its textual value: strings are unquoted, other
+/// scalars kept as written
+fn parse_toml_scalar(raw: &str) -> Option<String> {
+    if let Some(inner) = raw.strip_prefix('\'') {
+        // Literal strings have no escapes
+        return inner.strip_suffix('\'').map(str::to_string);
+    }
+
+    let Some(inner) = raw.strip_prefix('"') else {
+        // Bare scalars: integers, floats, booleans
+        return (!raw.is_empty() && !raw.contains(char::is_whitespace)).then(|| raw.replace('_', ""));
+    };
+    let inner = inner.strip_suffix('"')?;
+
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            value.push(ch);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            'r' => value.push('\r'),
+            '"' => value.push('"'),
+            '\\' => value.push('\\'),
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Configuration from a TOML file with the given contents
+    fn from_toml(toml: &str) -> Config {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("strata.toml");
+        std::fs::write(&path, toml).unwrap();
+        Config::from_file(&path)
+    }
+
+    #[test]
+    fn full_file_sets_every_section() {
+        let config = from_toml(
+            r#"
+            # Site settings
+            base_dir = "pages"
+            static_dir = 'assets'
+            port = 8080
+            host = "127.0.0.1"
+            site_title = "Team # Wiki"  # the hash in the string is kept
+            theme = "dark"
+            show_drafts = true
+            max_file_bytes = 1_048_576
+            exclude = ["drafts/**", "*.tmp"]
+
+            [search]
+            phrase_weight = 30.5
+            fuzzy = true
+            max_search_depth = 3
+
+            [markdown]
+            math = true
+            abbreviations = ["HTML: Hypertext Markup Language"]
+            "#,
+        );
+
+        assert_eq!(*config.base_dir, PathBuf::from("pages"));
+        assert_eq!(*config.static_dir, PathBuf::from("assets"));
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.host, "127.0.0.1");
+        assert_eq!(config.site_title, "Team # Wiki");
+        assert_eq!(config.theme, "dark");
+        assert!(config.show_drafts);
+        assert_eq!(config.max_file_bytes, 1_048_576);
+        assert_eq!(config.exclude, ["drafts/**", "*.tmp"]);
+        assert_eq!(config.search.phrase_weight, 30.5);
+        assert!(config.search.fuzzy);
+        assert_eq!(config.search.max_search_depth, 3);
+        assert!(config.markdown.math);
+        assert_eq!(config.markdown.abbreviations, ["HTML: Hypertext Markup Language"]);
+    }
+
+    #[test]
+    fn partial_file_keeps_defaults_and_skips_invalid_values() {
+        let config = from_toml("port = \"not a port\"\nsite_title = \"Docs\"\ntheme = \"neon\"\nunknown = 1\n");
+        let defaults = Config::new();
+
+        assert_eq!(config.site_title, "Docs");
+        assert_eq!(config.port, defaults.port);
+        assert_eq!(config.theme, defaults.theme);
+        assert_eq!(config.base_dir, defaults.base_dir);
+        assert_eq!(config.search.phrase_weight, defaults.search.phrase_weight);
+        assert_eq!(config.search.max_search_depth, usize::MAX);
+    }
+
+    #[test]
+    fn array_strings_may_hold_commas_and_span_lines() {
+        let config = from_toml(
+            "[markdown]\n\
+             abbreviations = [\n  \"CSS: Cascading Style Sheets\",  # styles\n  'A, B: letters',\n]\n\
+             [search]\n\
+             fuzzy = true\n",
+        );
+
+        assert_eq!(config.markdown.abbreviations, ["CSS: Cascading Style Sheets", "A, B: letters"]);
+        assert!(config.search.fuzzy);
+    }
+
+    #[test]
+    fn array_values_parse_into_items() {
+        let array = |items: &[&str]| Some(TomlValue::Array(items.iter().map(|item| item.to_string()).collect()));
+        assert_eq!(parse_toml_value("[]"), array(&[]));
+        assert_eq!(parse_toml_value("[\"a, b\", 'c]', \"d\",]"), array(&["a, b", "c]", "d"]));
+        assert_eq!(parse_toml_value("[\"a\",, \"b\"]"), None);
+        assert_eq!(parse_toml_value("[\"a\""), None);
+        assert!(array_is_closed("[\"]\", \"[\"]"));
+        assert!(!array_is_closed("[\"a\","));
+    }
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("custom.toml");
+        std::fs::write(&path, "port = 6000\nsite_title = \"From file\"\n").unwrap();
+
+        // No other test reads these variables
+        unsafe {
+            std::env::set_var("STRATA_CONFIG", &path);
+            std::env::set_var("STRATA_PORT", "7000");
+            std::env::set_var("STRATA_EXCLUDE", "private/**, *.bak");
+        }
+        let config = Config::load();
+        unsafe {
+            std::env::remove_var("STRATA_CONFIG");
+            std::env::remove_var("STRATA_PORT");
+            std::env::remove_var("STRATA_EXCLUDE");
+        }
+
+        assert_eq!(config.port, 7000);
+        assert_eq!(config.site_title, "From file");
+        assert_eq!(config.exclude, ["private/**", "*.bak"]);
+    }
+
+    #[test]
+    fn custom_config_keeps_the_other_defaults() {
+        let config = Config::with_custom(PathBuf::from("docs"), PathBuf::from("public"), Some(9000), None);
+        assert_eq!(*config.base_dir, PathBuf::from("docs"));
+        assert_eq!(*config.static_dir, PathBuf::from("public"));
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.host, Config::new().host);
+        assert_eq!(config.site_title, Config::new().site_title);
+    }
+
+    #[test]
+    fn socket_addr_honors_the_host() {
+        let addr = |host: &str| Config { host: host.to_string(), port: 5004, ..Config::new() }.socket_addr();
+        assert_eq!(addr("127.0.0.1"), "127.0.0.1:5004".parse().unwrap());
+        assert_eq!(addr("::1"), "[::1]:5004".parse().unwrap());
+        assert_eq!(addr("[::1]"), "[::1]:5004".parse().unwrap());
+        assert_eq!(addr("localhost"), "127.0.0.1:5004".parse().unwrap());
+        assert_eq!(addr("not a host"), "0.0.0.0:5004".parse().unwrap());
+    }
+
+    #[test]
+    fn listen_distinguishes_unix_sockets_from_tcp() {
+        let listen = |listen: &str| Config { listen: listen.to_string(), port: 5004, ..Config::new() }.listen_addr();
+        assert_eq!(listen("unix:/run/strata.sock"), ListenAddr::Unix(PathBuf::from("/run/strata.sock")));
+        assert_eq!(listen("unix:///run/strata.sock"), ListenAddr::Unix(PathBuf::from("/run/strata.sock")));
+        assert_eq!(listen("unix:strata.sock"), ListenAddr::Unix(PathBuf::from("strata.sock")));
+        assert_eq!(listen("tcp://127.0.0.1:8080"), ListenAddr::Tcp("127.0.0.1:8080".parse().unwrap()));
+        assert_eq!(listen("tcp://[::1]:8080"), ListenAddr::Tcp("[::1]:8080".parse().unwrap()));
+        assert_eq!(listen(" tcp://localhost:9000 "), ListenAddr::Tcp("127.0.0.1:9000".parse().unwrap()));
+    }
+
+    #[test]
+    fn unset_or_invalid_listen_falls_back_to_host_and_port() {
+        let fallback = ListenAddr::Tcp(Config { port: 5004, ..Config::new() }.socket_addr());
+        for listen in ["", "unix:", "tcp://127.0.0.1", "tcp://127.0.0.1:port", "http://127.0.0.1:80", "/run/strata.sock"] {
+            let config = Config { listen: listen.to_string(), port: 5004, ..Config::new() };
+            assert_eq!(config.listen_addr(), fallback, "{listen:?}");
+        }
+    }
+
+    #[test]
+    fn listen_is_read_from_the_file() {
+        assert_eq!(from_toml("listen = \"unix:/run/strata.sock\"\n").listen_addr(), ListenAddr::Unix(PathBuf::from("/run/strata.sock")));
+    }
+}
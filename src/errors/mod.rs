@@ -1,5 +1,7 @@
 use std::io;
-use axum::{http::StatusCode, response::{IntoResponse, Response}};
+use axum::{http::{header, StatusCode}, response::{IntoResponse, Response}};
+
+use crate::utils::escape_json;
 
 /// Custom error types for the wiki application
 #[derive(Debug)]
@@ -7,6 +9,9 @@ pub enum WikiError {
     Io(io::Error),
     NotFound,
     InvalidPath,
+    BadRequest(String),
+    /// File exceeds the configured size limit; holds its size in bytes
+    FileTooLarge(u64),
     TemplateError(String),
     SearchError(String),
     NavigationError(String),
@@ -19,36 +24,87 @@ impl From<io::Error> for WikiError {
     }
 }
 
-impl IntoResponse for WikiError {
-    fn into_response(self) -> Response {
+impl WikiError {
+    /// HTTP status code reported for this error
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            WikiError::NotFound => StatusCode::NOT_FOUND,
+            WikiError::InvalidPath | WikiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            WikiError::FileTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            WikiError::Io(e) => match e.kind() {
+                io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+                io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            WikiError::TemplateError(_)
+            | WikiError::SearchError(_)
+            | WikiError::NavigationError(_)
+            | WikiError::RenderError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Human-readable message for the response body
+    pub fn message(&self) -> String {
         match self {
-            WikiError::NotFound => (StatusCode::NOT_FOUND, "Not found").into_response(),
-            WikiError::InvalidPath => (StatusCode::BAD_REQUEST, "Invalid path").into_response(),
-            WikiError::Io(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("I/O error: {}", e),
-            )
-                .into_response(),
-            WikiError::TemplateError(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Template error: {}", e),
-            )
-                .into_response(),
-            WikiError::SearchError(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Search error: {}", e),
-            )
-                .into_response(),
-            WikiError::NavigationError(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Navigation error: {}", e),
-            )
-                .into_response(),
-            WikiError::RenderError(e) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Render error: {}", e),
-            )
-                .into_response(),
+            WikiError::NotFound => "Not found".to_string(),
+            WikiError::InvalidPath => "Invalid path".to_string(),
+            WikiError::BadRequest(e) => format!("Bad request: {}", e),
+            WikiError::Io(e) => format!("I/O error: {}", e),
+            WikiError::FileTooLarge(size) => format!("File too large to display ({} bytes)", size),
+            WikiError::TemplateError(e) => format!("Template error: {}", e),
+            WikiError::SearchError(e) => format!("Search error: {}", e),
+            WikiError::NavigationError(e) => format!("Navigation error: {}", e),
+            WikiError::RenderError(e) => format!("Render error: {}", e),
         }
     }
+
+    /// Render the error as a JSON body for API routes
+    pub fn into_json_response(self) -> Response {
+        let body = format!("{{\"error\":\"{}\"}}", escape_json(&self.message()));
+        (
+            self.status_code(),
+            [(header::CONTENT_TYPE, "application/json")],
+            body,
+        )
+            .into_response()
+    }
+}
+
+impl IntoResponse for WikiError {
+    fn into_response(self) -> Response {
+        (self.status_code(), self.message()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_map_by_kind() {
+        let io = |kind| WikiError::from(io::Error::new(kind, "synthesized"));
+        assert_eq!(io(io::ErrorKind::PermissionDenied).status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(io(io::ErrorKind::NotFound).status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(io(io::ErrorKind::Other).status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn bad_requests_are_400() {
+        assert_eq!(WikiError::BadRequest("no query".to_string()).status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(WikiError::InvalidPath.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn oversized_files_are_413() {
+        assert_eq!(WikiError::FileTooLarge(1 << 30).status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn error_bodies_are_plain_text() {
+        let response = WikiError::from(io::Error::new(io::ErrorKind::PermissionDenied, "denied")).into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "I/O error: denied");
+    }
 }
@@ -1,127 +1,284 @@
 use axum::{
     extract::{Path as AxumPath, RawQuery, State},
-    http::{header, Response},
+    http::{header, HeaderMap, Response, StatusCode},
     response::{Html, IntoResponse},
+    routing::get,
     body::Body,
+    Router,
 };
-use std::path::Path;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use crate::errors::WikiError;
-use crate::types::AppState;
-use crate::utils::{escape_attr, escape_html, last_modified_html, normalize_path, parse_query_param};
+use crate::middleware::{accepts_encoding, add_vary, compress_response, log_requests, track_metrics};
+use crate::types::{AppState, DirEntry, MarkdownResult, RecentPage};
+use crate::utils::{ensure_safe_path, escape_attr, escape_html, escape_json, encode_url_path, format_rfc3339, format_size, last_modified_html, normalize_path, page_url, parse_query_param, url_path, Frontmatter};
 use crate::services::{FileService, SearchService, MarkdownService};
-use crate::components::{FabComponent, NavigationComponent, TemplateComponent};
+use crate::services::search_service::MIN_QUERY_CHARS;
+use crate::components::{FabComponent, NavigationComponent};
+
+/// All routes with the compression, metrics and request-logging layers
+///
+/// Requests taking longer than `slow_request` are logged as slow.
+pub fn router(state: AppState, slow_request: Duration) -> Router {
+    let metrics = state.metrics.clone();
+    // `get` routes also answer HEAD: handlers run as for GET and axum drops the
+    // body, so headers (content-type, length, caching, encoding) stay identical
+    Router::new()
+        .route("/", get(handle_root))
+        .route("/search", get(handle_search))
+        .route("/sitemap.xml", get(handle_sitemap))
+        .route("/healthz", get(handle_healthz))
+        .route("/readyz", get(handle_readyz))
+        .route("/metrics", get(handle_metrics))
+        .route("/api/search", get(handle_api_search))
+        .route("/api/linkcheck", get(handle_api_linkcheck))
+        .route("/api/page", get(handle_api_page))
+        .route("/api/page/*path", get(handle_api_page))
+        .route("/raw/*path", get(handle_raw))
+        .route("/static/*path", get(handle_static))
+        .route("/*path", get(handle_path))
+        .layer(axum::middleware::from_fn(compress_response))
+        .layer(axum::middleware::from_fn_with_state(metrics, track_metrics))
+        .layer(axum::middleware::from_fn_with_state(slow_request, log_requests))
+        .with_state(state)
+}
+
+/// File service for the wiki directory, honoring the configured excludes
+fn wiki_file_service(state: &AppState) -> FileService {
+    let file_service = FileService::new(state.base_dir.as_ref().clone())
+        .with_exclude(state.exclude.clone())
+        .with_max_file_bytes(state.max_file_bytes);
+    match &state.content_cache {
+        Some(cache) => file_service.with_cache(cache.clone()),
+        None => file_service,
+    }
+}
+
+/// Sidebar navigation over the wiki, sharing the cached tree
+fn navigation_component(state: &AppState, file_service: FileService) -> NavigationComponent {
+    NavigationComponent::with_cache(file_service, state.sidebar_cache.clone())
+        .with_depth(state.nav_depth)
+        .with_drafts(state.show_drafts)
+}
+
+/// Read a page's source, or `None` for a draft while drafts are hidden
+fn read_visible_page(state: &AppState, file_service: &FileService, page: &Path) -> Result<Option<String>, WikiError> {
+    let content = file_service.read_file(page)?;
+    if !state.show_drafts && Frontmatter::parse(&content).is_draft() {
+        log::debug!("Hiding draft page {:?}", page);
+        return Ok(None);
+    }
+    Ok(Some(content))
+}
+
+/// Read a page's source, treating a hidden draft as missing
+fn read_page(state: &AppState, file_service: &FileService, page: &Path) -> Result<String, WikiError> {
+    read_visible_page(state, file_service, page)?.ok_or(WikiError::NotFound)
+}
+
+/// Floating action bar with the configured page actions
+fn fab_component(state: &AppState) -> FabComponent {
+    FabComponent::with_actions(state.fab_actions.clone())
+}
+
+/// Markdown renderer with the configured rendering options, resolving relative URLs
+/// against the page at `page` (relative to the wiki root)
+fn markdown_service(state: &AppState, page: &Path) -> MarkdownService {
+    MarkdownService::new()
+        .with_config(state.markdown_config.clone())
+        .with_page_path(page)
+}
 
 /// Handle root path requests
-pub async fn handle_root(State(state): State<AppState>) -> Result<impl IntoResponse, WikiError> {
-    let file_service = FileService::new(state.base_dir.as_ref().clone());
-    let navigation = NavigationComponent::new(file_service.clone());
-    let fab = FabComponent::new();
-    let templates = TemplateComponent::new();
+pub async fn handle_root(
+    State(state): State<AppState>,
+    RawQuery(raw): RawQuery,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, WikiError> {
+    let file_service = wiki_file_service(&state);
+    let navigation = navigation_component(&state, file_service.clone());
+    let fab = fab_component(&state);
+    let templates = &state.templates;
     
     // Check for index.md or README.md
     let index_md = state.base_dir.join("index.md");
     let readme_md = state.base_dir.join("README.md");
     
-    if index_md.is_file() {
-        let content = file_service.read_file(Path::new("index.md"))?;
-        let markdown_service = MarkdownService::new();
-        let result = markdown_service.render_with_toc(&content)?;
-        let meta = last_modified_html(&index_md);
-        let body = format!("{}{}", meta, result.html);
-        let actions = fab.generate_actions("");
-        let fab_html = fab.generate_home_fab_html(&actions);
-        let sidebar = navigation.build_sidebar_html("")?;
-        let page = templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, result.title.as_deref().unwrap_or("Wiki"), &result.toc)?;
-        return Ok(Html(page).into_response());
-    }
-    
-    if readme_md.is_file() {
-        let content = file_service.read_file(Path::new("README.md"))?;
-        let markdown_service = MarkdownService::new();
-        let result = markdown_service.render_with_toc(&content)?;
-        let meta = last_modified_html(&readme_md);
-        let body = format!("{}{}", meta, result.html);
-        let actions = fab.generate_actions("");
-        let fab_html = fab.generate_home_fab_html(&actions);
-        let sidebar = navigation.build_sidebar_html("")?;
-        let page = templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, result.title.as_deref().unwrap_or("Wiki"), &result.toc)?;
-        return Ok(Html(page).into_response());
+    // A draft index page falls back to the next option, as if it were missing
+    if index_md.is_file() && let Some(content) = read_visible_page(&state, &file_service, Path::new("index.md"))? {
+        let response = markdown_page_response(&state, &navigation, &headers, &index_md, || {
+            let markdown_service = markdown_service(&state, Path::new("index.md"));
+            let result = markdown_service.render_with_toc(&content)?;
+            let meta = page_meta_html(&index_md, &result);
+            let body = format!("{}{}{}", navigation.build_breadcrumbs(""), meta, result.html);
+            let actions = fab.generate_actions("");
+            let fab_html = fab.generate_home_fab_html(&actions);
+            let sidebar = navigation.build_sidebar_html("")?;
+            templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, result.title.as_deref().unwrap_or(""), &result.toc, result.description.as_deref())
+        })?;
+        return Ok(response);
+    }
+    
+    if readme_md.is_file() && let Some(content) = read_visible_page(&state, &file_service, Path::new("README.md"))? {
+        let response = markdown_page_response(&state, &navigation, &headers, &readme_md, || {
+            let markdown_service = markdown_service(&state, Path::new("README.md"));
+            let result = markdown_service.render_with_toc(&content)?;
+            let meta = page_meta_html(&readme_md, &result);
+            let body = format!("{}{}{}", navigation.build_breadcrumbs(""), meta, result.html);
+            let actions = fab.generate_actions("");
+            let fab_html = fab.generate_home_fab_html(&actions);
+            let sidebar = navigation.build_sidebar_html("")?;
+            templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, result.title.as_deref().unwrap_or(""), &result.toc, result.description.as_deref())
+        })?;
+        return Ok(response);
     }
     
     // Show directory listing
-    let html = render_directory_listing(&file_service, "")?;
+    let listing = render_directory_listing(&state, &file_service, "", &raw.unwrap_or_default())?;
+    let html = format!("{}{}{}", navigation.build_breadcrumbs(""), listing, templates.recent_pages_html());
     let sidebar = navigation.build_sidebar_html("")?;
     let actions = fab.generate_actions("");
     let fab_html = fab.generate_home_fab_html(&actions);
-    let page = templates.render_page_with_nav(&sidebar, &html, &fab_html, "Wiki")?;
+    let page = templates.render_page_with_nav(&sidebar, &html, &fab_html, "")?;
     Ok(Html(page).into_response())
 }
 
-/// Handle path requests
+/// Handle path requests, answering missing pages with the styled 404 page
 pub async fn handle_path(
     State(state): State<AppState>,
     AxumPath(path): AxumPath<String>,
-) -> Result<impl IntoResponse, WikiError> {
+    RawQuery(raw): RawQuery,
+    headers: HeaderMap,
+) -> Response<Body> {
+    match serve_wiki_path(&state, &path, &raw.unwrap_or_default(), &headers).await {
+        Ok(resp) => resp,
+        Err(e) if e.status_code() == StatusCode::NOT_FOUND => not_found_response(&state, &path),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Render the styled 404 page with the sidebar and FAB, status 404
+fn not_found_response(state: &AppState, path: &str) -> Response<Body> {
+    let normalized = normalize_path(path);
+    let file_service = wiki_file_service(state);
+    let navigation = navigation_component(state, file_service);
+    let fab = fab_component(state);
+    let fab_html = fab.generate_home_fab_html(&fab.generate_actions(""));
+
+    let page = navigation
+        .build_sidebar_html("")
+        .and_then(|sidebar| state.templates.render_not_found(&sidebar, &fab_html, &normalized));
+    match page {
+        Ok(page) => (StatusCode::NOT_FOUND, Html(page)).into_response(),
+        Err(e) => {
+            log::error!("Failed to render 404 page: {:?}", e);
+            WikiError::NotFound.into_response()
+        }
+    }
+}
+
+/// A 301 redirect to a decoded URL path, percent-encoded, keeping the raw query string
+fn permanent_redirect(path: &str, query: &str) -> Result<Response<Body>, WikiError> {
+    let mut location = encode_url_path(path);
+    if !query.is_empty() {
+        location.push('?');
+        location.push_str(query);
+    }
+    let value = header::HeaderValue::from_str(&location)
+        .map_err(|_| WikiError::BadRequest(format!("cannot redirect to '{}'", location)))?;
+
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = StatusCode::MOVED_PERMANENTLY;
+    resp.headers_mut().insert(header::LOCATION, value);
+    Ok(resp)
+}
+
+/// Resolve a wiki path to a rendered page, directory listing or file
+///
+/// `query` is the raw query string, used to pick the page of a directory listing.
+async fn serve_wiki_path(
+    state: &AppState,
+    path: &str,
+    query: &str,
+    headers: &HeaderMap,
+) -> Result<Response<Body>, WikiError> {
     log::info!("Path request received: '{}'", path);
+    ensure_path_len(state, path)?;
     
-    let normalized = normalize_path(&path);
+    let normalized = normalize_path(path);
+    ensure_safe_path(&normalized)?;
     let requested = state.base_dir.join(&normalized);
     
     log::debug!("Normalized path: '{}', requested: {:?}", normalized, requested);
     
-    let file_service = FileService::new(state.base_dir.as_ref().clone());
-    let navigation = NavigationComponent::new(file_service.clone());
-    let fab = FabComponent::new();
-    let templates = TemplateComponent::new();
+    let file_service = wiki_file_service(state);
+    let navigation = navigation_component(state, file_service.clone());
+    let fab = fab_component(state);
+    let templates = &state.templates;
     
     // First check if the exact path exists
     if requested.exists() {
+        if requested.is_dir() && !path.ends_with('/') && !normalized.is_empty() {
+            // Directory URLs end in a slash so relative links resolve inside them
+            log::debug!("Redirecting directory '{}' to '/{}/'", path, normalized);
+            return permanent_redirect(&format!("/{}/", normalized), query);
+        }
+
         if requested.is_dir() {
             log::debug!("Path is a directory, checking for index files");
             // Check for index.md or README.md in directory
             let index_md = requested.join("index.md");
             let readme_md = requested.join("README.md");
+            // Paths relative to the wiki root for FileService
+            let index_page = format!("{}/index.md", normalized);
+            let readme_page = format!("{}/README.md", normalized);
             
-            if index_md.is_file() {
+            if index_md.is_file() && let Some(content) = read_visible_page(state, &file_service, Path::new(&index_page))? {
                 log::debug!("Found index.md in directory");
-                // Convert full path to relative path for FileService
-                let content = file_service.read_file(Path::new(&format!("{}/index.md", normalized)))?;
-                let markdown_service = MarkdownService::new();
-                let result = markdown_service.render_with_toc(&content)?;
-                let meta = last_modified_html(&index_md);
-                let body = format!("{}{}", meta, result.html);
-                let actions = fab.generate_actions(&normalized);
-                let fab_html = fab.generate_fab_html(&normalized, &actions);
-                let sidebar = navigation.build_sidebar_with_toc(&normalized, &result.toc)?;
-                let title = result.title.as_deref().unwrap_or(&normalized);
-                let page = templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, title, &result.toc)?;
+                let page = index_page;
+                let response = markdown_page_response(state, &navigation, headers, &index_md, || {
+                    let markdown_service = markdown_service(state, Path::new(&page));
+                    let result = markdown_service.render_with_toc(&content)?;
+                    let meta = page_meta_html(&index_md, &result);
+                    let body = format!("{}{}{}", navigation.build_breadcrumbs(&normalized), meta, result.html);
+                    let actions = fab.generate_page_actions(&normalized, Some(&page));
+                    let fab_html = fab.generate_fab_html(&normalized, &actions);
+                    let sidebar = navigation.build_sidebar_with_toc(&normalized, &result.toc)?;
+                    let title = result.title.as_deref().unwrap_or(&normalized);
+                    templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, title, &result.toc, result.description.as_deref())
+                })?;
                 log::info!("Serving index.md for directory: '{}'", normalized);
-                return Ok(Html(page).into_response());
+                return Ok(response);
             }
             
-            if readme_md.is_file() {
+            if readme_md.is_file() && let Some(content) = read_visible_page(state, &file_service, Path::new(&readme_page))? {
                 log::debug!("Found README.md in directory");
-                // Convert full path to relative path for FileService
-                let content = file_service.read_file(Path::new(&format!("{}/README.md", normalized)))?;
-                let markdown_service = MarkdownService::new();
-                let result = markdown_service.render_with_toc(&content)?;
-                let meta = last_modified_html(&readme_md);
-                let body = format!("{}{}", meta, result.html);
-                let actions = fab.generate_actions(&normalized);
-                let fab_html = fab.generate_fab_html(&normalized, &actions);
-                let sidebar = navigation.build_sidebar_with_toc(&normalized, &result.toc)?;
-                let title = result.title.as_deref().unwrap_or(&normalized);
-                let page = templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, title, &result.toc)?;
+                let page = readme_page;
+                let response = markdown_page_response(state, &navigation, headers, &readme_md, || {
+                    let markdown_service = markdown_service(state, Path::new(&page));
+                    let result = markdown_service.render_with_toc(&content)?;
+                    let meta = page_meta_html(&readme_md, &result);
+                    let body = format!("{}{}{}", navigation.build_breadcrumbs(&normalized), meta, result.html);
+                    let actions = fab.generate_page_actions(&normalized, Some(&page));
+                    let fab_html = fab.generate_fab_html(&normalized, &actions);
+                    let sidebar = navigation.build_sidebar_with_toc(&normalized, &result.toc)?;
+                    let title = result.title.as_deref().unwrap_or(&normalized);
+                    templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, title, &result.toc, result.description.as_deref())
+                })?;
                 log::info!("Serving README.md for directory: '{}'", normalized);
-                return Ok(Html(page).into_response());
+                return Ok(response);
             }
             
             // Directory listing
             log::debug!("No index files found, generating directory listing");
-            let html = render_directory_listing(&file_service, &normalized)?;
+            let listing = render_directory_listing(state, &file_service, &normalized, query)?;
+            let html = format!("{}{}", navigation.build_breadcrumbs(&normalized), listing);
             let sidebar = navigation.build_sidebar_html(&normalized)?;
-            let actions = fab.generate_actions(&normalized);
+            let actions = fab.generate_page_actions(&normalized, None);
             let fab_html = fab.generate_fab_html(&normalized, &actions);
             let page = templates.render_page_with_nav(&sidebar, &html, &fab_html, &normalized)?;
             log::info!("Serving directory listing for: '{}'", normalized);
@@ -130,7 +287,7 @@ pub async fn handle_path(
         
         if requested.is_file() {
             log::debug!("Path is a file, serving via static handler");
-            return serve_path(&state, &normalized, &requested).await;
+            return serve_path(state, &normalized, &requested, headers).await;
         }
     }
     
@@ -140,46 +297,51 @@ pub async fn handle_path(
         log::debug!("Found .md variant: {:?}", md_variant);
         let relative_path = md_variant.strip_prefix(&*state.base_dir)
             .map_err(|_| WikiError::InvalidPath)?;
-        let content = file_service.read_file(relative_path)?;
-        let markdown_service = MarkdownService::new();
-        let result = markdown_service.render_with_toc(&content)?;
-        let meta = last_modified_html(&md_variant);
-        let body = format!("{}{}", meta, result.html);
-        let actions = fab.generate_actions(&normalized);
-        let fab_html = fab.generate_fab_html(&normalized, &actions);
-        let sidebar = navigation.build_sidebar_with_toc(&normalized, &result.toc)?;
-        let title = result.title.as_deref().unwrap_or(&normalized);
-        let page = templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, title, &result.toc)?;
+        let content = read_page(state, &file_service, relative_path)?;
+        let response = markdown_page_response(state, &navigation, headers, &md_variant, || {
+            let markdown_service = markdown_service(state, relative_path);
+            let result = markdown_service.render_with_toc(&content)?;
+            let meta = page_meta_html(&md_variant, &result);
+            let body = format!("{}{}{}", navigation.build_breadcrumbs(&normalized), meta, result.html);
+            let actions = fab.generate_page_actions(&normalized, Some(&url_path(relative_path)));
+            let fab_html = fab.generate_fab_html(&normalized, &actions);
+            let sidebar = navigation.build_sidebar_with_toc(&normalized, &result.toc)?;
+            let title = result.title.as_deref().unwrap_or(&normalized);
+            templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, title, &result.toc, result.description.as_deref())
+        })?;
         log::info!("Serving .md file: '{}'", normalized);
-        return Ok(Html(page).into_response());
+        return Ok(response);
     }
     
     log::warn!("Path not found: '{}'", normalized);
     Err(WikiError::NotFound)
 }
 
-async fn serve_path(state: &AppState, req_path: &str, path: &Path) -> Result<Response<Body>, WikiError> {
-    let file_service = FileService::new(state.base_dir.as_ref().clone());
+async fn serve_path(state: &AppState, req_path: &str, path: &Path, headers: &HeaderMap) -> Result<Response<Body>, WikiError> {
+    let file_service = wiki_file_service(state);
     
     if is_markdown(path) {
         // Convert full path to relative path for FileService
         let relative_path = path.strip_prefix(&*state.base_dir)
             .map_err(|_| WikiError::InvalidPath)?;
-        let content = file_service.read_file(relative_path)?;
-        let markdown_service = MarkdownService::new();
-        let result = markdown_service.render_with_toc(&content)?;
-        let meta = last_modified_html(path);
-        let body = format!("{}{}", meta, result.html);
-        let fab = FabComponent::new();
-        let actions = fab.generate_actions(req_path);
-        let fab_html = fab.generate_fab_html(req_path, &actions);
-        let navigation = NavigationComponent::new(file_service);
-        let sidebar = navigation.build_sidebar_with_toc(req_path, &result.toc)?;
-        let templates = TemplateComponent::new();
-        let page = templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, result.title.as_deref().unwrap_or(req_path), &result.toc)?;
-        return Ok(Html(page).into_response());
+        let content = read_page(state, &file_service, relative_path)?;
+        let navigation = navigation_component(state, file_service.clone());
+        let response = markdown_page_response(state, &navigation, headers, path, || {
+            let markdown_service = markdown_service(state, relative_path);
+            let result = markdown_service.render_with_toc(&content)?;
+            let meta = page_meta_html(path, &result);
+            let body = format!("{}{}{}", navigation.build_breadcrumbs(req_path), meta, result.html);
+            let fab = fab_component(state);
+            let actions = fab.generate_page_actions(req_path, Some(&url_path(relative_path)));
+            let fab_html = fab.generate_fab_html(req_path, &actions);
+            let sidebar = navigation.build_sidebar_with_toc(req_path, &result.toc)?;
+            let templates = &state.templates;
+            templates.render_page_with_nav_and_toc(&sidebar, &body, &fab_html, result.title.as_deref().unwrap_or(req_path), &result.toc, result.description.as_deref())
+        })?;
+        return Ok(response);
     }
 
+    ensure_file_size(state, path, std::fs::metadata(path)?.len())?;
     let bytes = std::fs::read(path)?;
     let content_type = file_service.content_type_for(path);
     let mut resp = Response::new(Body::from(bytes));
@@ -187,9 +349,62 @@ async fn serve_path(state: &AppState, req_path: &str, path: &Path) -> Result<Res
     Ok(resp)
 }
 
+/// Entries per directory listing page unless `per_page` asks for another size
+const DEFAULT_LISTING_PER_PAGE: usize = 100;
+
+/// Largest `per_page` accepted for directory listings
+const MAX_LISTING_PER_PAGE: usize = 1000;
+
+/// Reject request paths over the configured length before doing any work on them
+fn ensure_path_len(state: &AppState, path: &str) -> Result<(), WikiError> {
+    if path.len() > state.max_path_len {
+        log::warn!("Rejected request path of {} bytes (limit {})", path.len(), state.max_path_len);
+        return Err(WikiError::BadRequest(format!("path longer than {} bytes", state.max_path_len)));
+    }
+    Ok(())
+}
+
+/// Refuse to read more than `max_file_bytes` of a file into memory for one response
+fn ensure_file_size(state: &AppState, path: &Path, size: u64) -> Result<(), WikiError> {
+    if size > state.max_file_bytes {
+        log::warn!("File {:?} is {} bytes, over the {} byte limit", path, size, state.max_file_bytes);
+        return Err(WikiError::FileTooLarge(size));
+    }
+    Ok(())
+}
+
 /// Render directory listing HTML
-fn render_directory_listing(file_service: &FileService, req_path: &str) -> Result<String, WikiError> {
-    let entries = file_service.list_directory(Path::new(req_path))?;
+///
+/// Hidden files and the directory's own index.md or README.md are skipped;
+/// directories come first, then files, each sorted by name. Long listings are
+/// split into pages chosen by the `page` and `per_page` query parameters.
+fn render_directory_listing(
+    state: &AppState,
+    file_service: &FileService,
+    req_path: &str,
+    query: &str,
+) -> Result<String, WikiError> {
+    let mut entries = file_service.list_directory_with_meta(Path::new(req_path))?;
+    entries.retain(|meta| is_listed(state, file_service, &meta.entry, meta.modified));
+    entries.sort_by(|a, b| {
+        let (a, b) = (&a.entry, &b.entry);
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let per_page_param = parse_query_param(query, "per_page");
+    let per_page = per_page_param
+        .parse::<usize>()
+        .map_or(DEFAULT_LISTING_PER_PAGE, |n| n.clamp(1, MAX_LISTING_PER_PAGE));
+    let page_count = entries.len().div_ceil(per_page).max(1);
+    let page = parse_query_param(query, "page")
+        .parse::<usize>()
+        .map_or(1, |n| n.clamp(1, page_count));
+    let total = entries.len();
+    let entries: Vec<_> = entries.into_iter().skip((page - 1) * per_page).take(per_page).collect();
+
     let mut html = String::new();
     
     // Heading and parent link
@@ -198,7 +413,7 @@ fn render_directory_listing(file_service: &FileService, req_path: &str) -> Resul
     
     if !req_path.is_empty() {
         if let Some((parent, _)) = req_path.rsplit_once('/') {
-            let back = if parent.is_empty() { "/".to_string() } else { format!("/{}", parent) };
+            let back = if parent.is_empty() { "/".to_string() } else { format!("/{}/", parent) };
             html.push_str(&format!("<p><a href=\"{}\">⬑ Up</a></p>", escape_attr(&back)));
         } else {
             html.push_str("<p><a href=\"/\">⬑ Up</a></p>");
@@ -206,10 +421,11 @@ fn render_directory_listing(file_service: &FileService, req_path: &str) -> Resul
     }
     
     html.push_str("<ul class=\"listing\">\n");
-    for entry in entries {
+    for meta in entries {
+        let entry = meta.entry;
         let href = if req_path.is_empty() {
             if entry.is_dir {
-            format!("/{}", entry.name)
+            format!("/{}/", entry.name)
             } else {
                 // For markdown files, remove .md extension in the URL
                 let name_without_ext = entry.name.trim_end_matches(".md");
@@ -217,24 +433,110 @@ fn render_directory_listing(file_service: &FileService, req_path: &str) -> Resul
             }
         } else {
             if entry.is_dir {
-            format!("/{}/{}", req_path, entry.name)
+            format!("/{}/{}/", req_path, entry.name)
             } else {
                 // For markdown files, remove .md extension in the URL
                 let name_without_ext = entry.name.trim_end_matches(".md");
                 format!("/{}/{}", req_path, name_without_ext)
             }
         };
+        // Directories show how many listed entries they hold, files their size and date;
+        // children are only stat'ed, their draft status comes from the search index
+        let details = if entry.is_dir {
+            match file_service.list_directory_with_meta(&entry.path) {
+                Ok(children) => {
+                    let count = children
+                        .iter()
+                        .filter(|child| is_listed(state, file_service, &child.entry, child.modified))
+                        .count();
+                    format!("{} {}", count, if count == 1 { "item" } else { "items" })
+                }
+                Err(_) => String::new(),
+            }
+        } else {
+            match format_rfc3339(meta.modified) {
+                Some(date) => format!(
+                    "{} · <time datetime=\"{}\">{}</time>",
+                    format_size(meta.size),
+                    escape_attr(&date),
+                    escape_html(&date)
+                ),
+                None => format_size(meta.size),
+            }
+        };
         let display = if entry.is_dir { 
             format!("{}/", entry.name) 
         } else { 
             entry.name 
         };
-        html.push_str(&format!("  <li><a href=\"{}\">{}</a></li>\n", escape_attr(&href), escape_html(&display)));
+        html.push_str(&format!(
+            "  <li><a href=\"{}\">{}</a><span class=\"entry-meta\">{}</span></li>\n",
+            escape_attr(&href),
+            escape_html(&display),
+            details
+        ));
     }
     html.push_str("</ul>\n");
+
+    if page_count > 1 {
+        // Links keep an explicit page size so every page splits the same way
+        let page_href = |page: usize| {
+            if per_page_param.is_empty() {
+                format!("?page={}", page)
+            } else {
+                format!("?page={}&per_page={}", page, per_page)
+            }
+        };
+        html.push_str("<nav class=\"pagination\">");
+        if page > 1 {
+            html.push_str(&format!("<a href=\"{}\" rel=\"prev\">← Previous</a>", escape_attr(&page_href(page - 1))));
+        }
+        html.push_str(&format!("<span>Page {} of {} ({} entries)</span>", page, page_count, total));
+        if page < page_count {
+            html.push_str(&format!("<a href=\"{}\" rel=\"next\">Next →</a>", escape_attr(&page_href(page + 1))));
+        }
+        html.push_str("</nav>\n");
+    }
     Ok(html)
 }
 
+/// Last-modified line and reading-time estimate shown above a rendered page
+fn page_meta_html(path: &Path, result: &MarkdownResult) -> String {
+    let reading_time = match result.reading_minutes {
+        0 => "< 1 min read".to_string(),
+        minutes => format!("~{} min read", minutes),
+    };
+    format!("{}<p class=\"reading-time\">{}</p>", last_modified_html(path), escape_html(&reading_time))
+}
+
+/// Whether a directory listing shows `entry`
+///
+/// Dotfiles, the directory's own index.md or README.md, and hidden drafts are
+/// left out; excluded paths never reach the listing in the first place. Draft
+/// status comes from the search index while the page's modification time
+/// `modified` matches the indexed one; only new or changed pages are read.
+fn is_listed(state: &AppState, file_service: &FileService, entry: &DirEntry, modified: SystemTime) -> bool {
+    let is_index = !entry.is_dir
+        && (entry.name.eq_ignore_ascii_case("index.md") || entry.name.eq_ignore_ascii_case("README.md"));
+    if entry.name.starts_with('.') || is_index {
+        return false;
+    }
+    if state.show_drafts || entry.is_dir || !is_markdown(&entry.path) {
+        return true;
+    }
+    !state
+        .search_index
+        .is_hidden_draft(&url_path(&entry.path), modified)
+        .unwrap_or_else(|| is_draft(file_service, &entry.path))
+}
+
+/// Whether the page at `page` is marked `draft: true`
+fn is_draft(file_service: &FileService, page: &Path) -> bool {
+    file_service
+        .read_file(page)
+        .is_ok_and(|content| Frontmatter::parse(&content).is_draft())
+}
+
 /// Check if a file is markdown
 fn is_markdown(path: &Path) -> bool {
     path.extension()
@@ -252,46 +554,45 @@ pub async fn handle_search(
     let query = parse_query_param(&raw_query, "q");
     
     log::info!("Search request received for query: '{}'", query);
+    state.metrics.record_search();
     log::debug!("Raw query string: '{:?}'", raw_query);
     
     // Check for potentially problematic queries
-    let query = if query.len() > 1000 {
-        log::warn!("Very long search query received ({} chars), truncating", query.len());
-        // Truncate very long queries to prevent issues
-        &query[..1000]
-    } else {
-        &query
-    };
+    let query = truncate_query(&query);
     
     let start_time = std::time::Instant::now();
     
-    let file_service = FileService::new(state.base_dir.as_ref().clone());
-    let search_service = SearchService::new(file_service.clone());
+    let file_service = wiki_file_service(&state);
+    let search_service = SearchService::new(file_service.clone(), state.search_index.clone(), state.search_config.clone())
+        .with_drafts(state.show_drafts);
     
-    log::debug!("Search service created, starting search...");
-    
-    let results = match search_service.search(&query) {
-        Ok(results) => {
-            log::info!("Search completed successfully, found {} results", results.len());
-            results
-        }
-        Err(e) => {
-            log::error!("Search failed: {:?}", e);
-            return Err(e);
-        }
+    let search_content = if SearchService::is_short_query(query) {
+        // Too little to search on; point at recent pages instead of scanning the index
+        render_search_suggestions(query, &search_service.suggestions())
+    } else {
+        log::debug!("Search service created, starting search...");
+        let results = match search_service.search(query) {
+            Ok(results) => {
+                log::info!("Search completed successfully, found {} results", results.len());
+                results
+            }
+            Err(e) => {
+                log::error!("Search failed: {:?}", e);
+                return Err(e);
+            }
+        };
+        render_search_results(query, &results)
     };
     
-    let search_content = render_search_results(&query, &results);
-    
     log::debug!("Search results rendered, creating response...");
     
     // Use template component for consistent rendering
-    let navigation = NavigationComponent::new(file_service);
+    let navigation = navigation_component(&state, file_service);
     let sidebar = navigation.build_sidebar_html("")?;
-    let fab = FabComponent::new();
+    let fab = fab_component(&state);
     let actions = fab.generate_actions("");
     let fab_html = fab.generate_fab_html("", &actions);
-    let templates = TemplateComponent::new();
+    let templates = &state.templates;
     
     let page = templates.render_page_with_nav(&sidebar, &search_content, &fab_html, "Search")?;
     
@@ -301,59 +602,348 @@ pub async fn handle_search(
     Ok(Html(page).into_response())
 }
 
+/// Longest search query accepted, in bytes
+const MAX_QUERY_LEN: usize = 1000;
+
+/// Truncate very long queries to prevent issues, backing off to a char boundary
+fn truncate_query(query: &str) -> &str {
+    if query.len() <= MAX_QUERY_LEN {
+        return query;
+    }
+
+    log::warn!("Very long search query received ({} chars), truncating", query.len());
+    let mut end = MAX_QUERY_LEN;
+    while !query.is_char_boundary(end) {
+        end -= 1;
+    }
+    &query[..end]
+}
+
+/// Handle JSON search requests
+pub async fn handle_api_search(
+    State(state): State<AppState>,
+    RawQuery(raw): RawQuery,
+) -> Response<Body> {
+    let raw_query = raw.unwrap_or_default();
+    let query = parse_query_param(&raw_query, "q");
+    let query = truncate_query(&query);
+    log::info!("API search request received for query: '{}'", query);
+    state.metrics.record_search();
+
+    let file_service = wiki_file_service(&state);
+    let search_service = SearchService::new(file_service, state.search_index.clone(), state.search_config.clone());
+    let results = match search_service.search(query) {
+        Ok(results) => results,
+        Err(e) => {
+            log::error!("API search failed: {:?}", e);
+            return e.into_json_response();
+        }
+    };
+
+    let items: Vec<String> = results
+        .iter()
+        .map(|result| {
+            format!(
+                "{{\"title\":\"{}\",\"path\":\"{}\",\"excerpt\":\"{}\",\"excerpts\":[{}],\"relevance\":{}}}",
+                escape_json(&result.title),
+                escape_json(&result.path),
+                escape_json(&result.excerpt),
+                result
+                    .excerpts
+                    .iter()
+                    .map(|excerpt| format!("\"{}\"", escape_json(excerpt)))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                if result.relevance.is_finite() { result.relevance } else { 0.0 },
+            )
+        })
+        .collect();
+
+    json_response(format!("{{\"total\":{},\"results\":[{}]}}", results.len(), items.join(",")))
+}
+
+/// Handle JSON page requests, returning the rendered page without the HTML shell
+pub async fn handle_api_page(
+    State(state): State<AppState>,
+    path: Option<AxumPath<String>>,
+) -> Response<Body> {
+    let path = path.map(|AxumPath(path)| path).unwrap_or_default();
+    log::info!("API page request received: '{}'", path);
+
+    match render_api_page(&state, &path) {
+        Ok(json) => json_response(json),
+        Err(e) => {
+            log::warn!("API page request failed for '{}': {:?}", path, e);
+            e.into_json_response()
+        }
+    }
+}
+
+/// Render a page as a JSON object with title, html, toc and path
+fn render_api_page(state: &AppState, path: &str) -> Result<String, WikiError> {
+    ensure_path_len(state, path)?;
+    let normalized = normalize_path(path);
+    ensure_safe_path(&normalized)?;
+
+    let relative = resolve_markdown_page(&state.base_dir, &normalized)?;
+    let file_service = wiki_file_service(state);
+    let content = read_page(state, &file_service, &relative)?;
+    let result = markdown_service(state, &relative).render_with_toc(&content)?;
+    let title = result.title.as_deref().unwrap_or(&normalized);
+
+    Ok(format!(
+        "{{\"title\":\"{}\",\"html\":\"{}\",\"toc\":\"{}\",\"path\":\"{}\"}}",
+        escape_json(title),
+        escape_json(&result.html),
+        escape_json(&result.toc),
+        escape_json(&normalized),
+    ))
+}
+
+/// Paths of the app's own pages, which links may point at without a wiki file behind them
+const APP_ROUTES: &[&str] = &["/search", "/sitemap.xml", "/healthz", "/readyz", "/metrics"];
+
+/// Prefixes of routes whose targets do not live in the wiki directory
+const APP_ROUTE_PREFIXES: &[&str] = &["/static/", "/api/"];
+
+/// Handle link check requests, listing internal links that lead nowhere for each page
+pub async fn handle_api_linkcheck(State(state): State<AppState>) -> Response<Body> {
+    log::info!("Link check request received");
+    let report = match check_links(&state) {
+        Ok(report) => report,
+        Err(e) => {
+            log::error!("Link check failed: {:?}", e);
+            return e.into_json_response();
+        }
+    };
+
+    let total: usize = report.iter().map(|(_, broken)| broken.len()).sum();
+    let pages: Vec<String> = report
+        .iter()
+        .map(|(path, broken)| {
+            format!(
+                "{{\"path\":\"{}\",\"broken\":[{}]}}",
+                escape_json(path),
+                broken
+                    .iter()
+                    .map(|link| format!("\"{}\"", escape_json(link)))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+        })
+        .collect();
+    json_response(format!("{{\"total\":{},\"pages\":[{}]}}", total, pages.join(",")))
+}
+
+/// Broken internal link targets of every visible page, skipping pages without any
+///
+/// Pages that cannot be read or rendered are logged and left out.
+fn check_links(state: &AppState) -> Result<Vec<(String, Vec<String>)>, WikiError> {
+    let file_service = wiki_file_service(state);
+    let mut report = Vec::new();
+
+    for entry in file_service.walk(Path::new(""))? {
+        if entry.is_dir || !is_markdown(&entry.path) {
+            continue;
+        }
+        let content = match read_visible_page(state, &file_service, &entry.path) {
+            Ok(Some(content)) => content,
+            Ok(None) => continue,
+            Err(e) => {
+                log::warn!("Link check skipped {:?}: {:?}", entry.path, e);
+                continue;
+            }
+        };
+        let markdown_service = markdown_service(state, &entry.path);
+        let result = match markdown_service.render_with_toc(&content) {
+            Ok(result) => result,
+            Err(e) => {
+                log::warn!("Link check skipped {:?}: {:?}", entry.path, e);
+                continue;
+            }
+        };
+
+        let broken: Vec<String> = markdown_service
+            .internal_links(&result.html)
+            .into_iter()
+            .filter(|link| !link_resolves(&file_service, link))
+            .collect();
+        if !broken.is_empty() {
+            log::warn!("{} broken internal links in {:?}", broken.len(), entry.path);
+            report.push((url_path(&entry.path), broken));
+        }
+    }
+
+    Ok(report)
+}
+
+/// Whether an internal link leads to a wiki page, directory or file, or to one of the app's routes
+fn link_resolves(file_service: &FileService, link: &str) -> bool {
+    if let Some(raw) = link.strip_prefix("/raw/") {
+        return file_service.link_target_exists(raw);
+    }
+    if APP_ROUTES.contains(&link) || APP_ROUTE_PREFIXES.iter().any(|prefix| link.starts_with(prefix)) {
+        return true;
+    }
+    file_service.link_target_exists(link)
+}
+
+/// Find the markdown file backing a wiki path, relative to the base directory
+///
+/// Directories resolve to their index.md or README.md, other paths to the file
+/// itself or its `.md` variant.
+fn resolve_markdown_page(base_dir: &Path, normalized: &str) -> Result<PathBuf, WikiError> {
+    let relative = PathBuf::from(normalized);
+    let requested = base_dir.join(&relative);
+
+    let candidates = if requested.is_dir() {
+        vec![relative.join("index.md"), relative.join("README.md")]
+    } else if is_markdown(&requested) {
+        vec![relative]
+    } else {
+        vec![relative.with_extension("md")]
+    };
+
+    candidates
+        .into_iter()
+        .find(|candidate| base_dir.join(candidate).is_file())
+        .ok_or(WikiError::NotFound)
+}
+
+/// Build an `application/json` response
+fn json_response(body: String) -> Response<Body> {
+    let mut resp = Response::new(Body::from(body));
+    resp.headers_mut().insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/json"));
+    resp
+}
+
+/// Serve request counters and latencies in the Prometheus text format
+pub async fn handle_metrics(State(state): State<AppState>) -> Response<Body> {
+    let mut resp = Response::new(Body::from(state.metrics.render()));
+    resp.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+    );
+    resp
+}
+
+/// Liveness probe: answers as long as the server is running, without touching the disk
+pub async fn handle_healthz() -> Response<Body> {
+    json_response("{\"status\":\"ok\"}".to_string())
+}
+
+/// Readiness probe: 200 when the wiki directory can be read, 503 otherwise
+pub async fn handle_readyz(State(state): State<AppState>) -> Response<Body> {
+    match std::fs::read_dir(state.base_dir.as_ref()) {
+        Ok(_) => json_response("{\"status\":\"ready\"}".to_string()),
+        Err(e) => {
+            log::warn!("Readiness check failed for {:?}: {}", state.base_dir, e);
+            let mut resp = json_response("{\"status\":\"unavailable\"}".to_string());
+            *resp.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            resp
+        }
+    }
+}
+
+/// Handle sitemap requests, listing every markdown page with its modification time
+pub async fn handle_sitemap(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, WikiError> {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("localhost");
+    let origin = format!("http://{}", host);
+
+    let file_service = wiki_file_service(&state);
+    let mut pages = collect_sitemap_pages(&file_service, state.show_drafts)?;
+    pages.sort();
+    log::info!("Generated sitemap with {} pages", pages.len());
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for (url_path, lastmod) in &pages {
+        xml.push_str("  <url>\n");
+        xml.push_str(&format!("    <loc>{}{}</loc>\n", escape_html(&origin), escape_html(url_path)));
+        if let Some(lastmod) = lastmod {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", lastmod));
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+
+    let mut resp = Response::new(Body::from(xml));
+    resp.headers_mut().insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/xml"));
+    Ok(resp)
+}
+
+/// Collect `(url path, lastmod)` pairs for every markdown page
+///
+/// index.md and README.md map to their directory URL; README.md is skipped
+/// when an index.md takes precedence. Drafts are left out unless shown.
+fn collect_sitemap_pages(file_service: &FileService, show_drafts: bool) -> Result<Vec<(String, Option<String>)>, WikiError> {
+    let mut pages = Vec::new();
+
+    for entry in file_service.walk(Path::new(""))? {
+        if entry.is_dir || !is_markdown(&entry.path) {
+            continue;
+        }
+        if !show_drafts && is_draft(file_service, &entry.path) {
+            continue;
+        }
+
+        let dir = entry.path.parent().unwrap_or(Path::new(""));
+        if entry.name == "README.md" && file_service.file_exists(&dir.join("index.md")) {
+            continue;
+        }
+        let url_path = page_url(&entry.path);
+
+        let lastmod = file_service
+            .get_metadata(&entry.path)
+            .ok()
+            .and_then(|meta| meta.modified().ok())
+            .and_then(|mtime| mtime.duration_since(std::time::UNIX_EPOCH).ok())
+            .and_then(|dur| time::OffsetDateTime::from_unix_timestamp(dur.as_secs() as i64).ok())
+            .and_then(|dt| dt.format(&time::format_description::well_known::Rfc3339).ok());
+        pages.push((url_path, lastmod));
+    }
+
+    Ok(pages)
+}
+
 /// Handle raw markdown requests
 pub async fn handle_raw(
     State(state): State<AppState>,
     AxumPath(path): AxumPath<String>,
 ) -> Result<impl IntoResponse, WikiError> {
+    ensure_path_len(&state, &path)?;
     let normalized = normalize_path(&path);
+    ensure_safe_path(&normalized)?;
     let requested = state.base_dir.join(&normalized);
     
-    let file_service = FileService::new(state.base_dir.as_ref().clone());
-    let content: String;
-    let display_path: String;
+    let file_service = wiki_file_service(&state);
     
     if !requested.exists() {
-        // Check for .md variant
-        let md_variant = requested.with_extension("md");
-        if md_variant.is_file() {
-            let relative_path = md_variant.strip_prefix(&*state.base_dir)
-                .map_err(|_| WikiError::InvalidPath)?;
-            content = file_service.read_file(relative_path)?;
-            display_path = relative_path.to_string_lossy().to_string();
-        } else {
-            // Return HTML 404 page for consistency
-            let error_html = r#"<!doctype html>
-<html lang="en">
-<head>
-    <meta charset="utf-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1">
-    <title>404 - Not Found</title>
-    <link rel="stylesheet" href="/static/css/strata.css">
-</head>
-<body>
-    <div class="error-page">
-        <div class="error-container glass">
-            <div class="error-icon">404</div>
-            <h1 class="error-title">Page Not Found</h1>
-            <p class="error-message">The requested page could not be found.</p>
-            <div class="error-actions">
-                <a href="/" class="error-btn primary">Go Home</a>
-                <a href="/" class="error-btn secondary">Go Home</a>
-            </div>
-        </div>
-    </div>
-</body>
-</html>"#;
-            return Ok(Html(error_html.to_string()).into_response());
+        // Old extension-less raw links point at the .md file's canonical raw URL
+        if requested.with_extension("md").is_file() {
+            let location = format!("/raw/{}.md", normalized);
+            log::debug!("Redirecting raw request to '{}'", location);
+            return permanent_redirect(&location, "");
         }
-    } else {
-        let relative_path = requested.strip_prefix(&*state.base_dir)
-            .map_err(|_| WikiError::InvalidPath)?;
-        content = file_service.read_file(relative_path)?;
-        display_path = relative_path.to_string_lossy().to_string();
+        // Same styled 404 page as missing wiki paths
+        return Ok(not_found_response(&state, &format!("raw/{}", normalized)));
     }
     
+    let relative_path = requested.strip_prefix(&*state.base_dir)
+        .map_err(|_| WikiError::InvalidPath)?;
+    let content = if is_markdown(relative_path) {
+        read_page(&state, &file_service, relative_path)?
+    } else {
+        file_service.read_file(relative_path)?
+    };
+    let display_path = url_path(relative_path);
+    
     // Create the rendered path (remove .md extension for display)
     let rendered_path = if display_path.ends_with(".md") {
         display_path[..display_path.len()-3].to_string()
@@ -363,12 +953,12 @@ pub async fn handle_raw(
     
     // Return a proper HTML page with the raw markdown content
     let raw_html = format!(r#"<!doctype html>
-<html lang="en">
+<html lang="en" class="{}">
 <head>
     <meta charset="utf-8">
     <meta name="viewport" content="width=device-width, initial-scale=1">
-    <title>Raw: {}</title>
-    <link rel="stylesheet" href="/static/css/strata.css">
+    <title>Raw: {} — {}</title>
+    {}
 </head>
 <body>
     <div class="raw-viewer">
@@ -378,7 +968,7 @@ pub async fn handle_raw(
                 <p class="raw-path">/raw/{}</p>
             </div>
             <div class="raw-actions">
-                <a href="/{}" class="raw-btn primary">← Back to Rendered View</a>
+                <a href="{}" class="raw-btn primary">← Back to Rendered View</a>
                 <a href="/" class="raw-btn secondary">Go Home</a>
             </div>
         </div>
@@ -388,10 +978,13 @@ pub async fn handle_raw(
     </div>
 </body>
 </html>"#, 
-        display_path, 
-        display_path, 
-        display_path, 
-        rendered_path,
+        escape_attr(&state.templates.theme_class()),
+        escape_html(&display_path),
+        escape_html(&state.site_title),
+        state.templates.head_styles(),
+        escape_html(&display_path),
+        escape_html(&display_path),
+        escape_attr(&encode_url_path(&format!("/{}", rendered_path))),
         escape_html(&content)
     );
     
@@ -402,32 +995,302 @@ pub async fn handle_raw(
 pub async fn handle_static(
     State(state): State<AppState>,
     AxumPath(path): AxumPath<String>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, WikiError> {
+    ensure_path_len(&state, &path)?;
     let normalized = normalize_path(&path);
-    let requested = state.static_dir.join(&normalized);
+    ensure_safe_path(&normalized)?;
+    // Theme assets override the bundled static files of the same name
+    let root: &Path = match &state.template_dir {
+        Some(dir) if dir.join(&normalized).is_file() => dir,
+        _ => &state.static_dir,
+    };
+    let requested = root.join(&normalized);
     
     if !requested.exists() {
         return Err(WikiError::NotFound);
     }
+
+    // A precompressed sibling (`.br`, `.gz`) is sent in place of the file when the client accepts it
+    let precompressed = PRECOMPRESSED_SUFFIXES.iter().find_map(|&(encoding, suffix)| {
+        if !accepts_encoding(&headers, encoding) {
+            return None;
+        }
+        let mut name = requested.clone().into_os_string();
+        name.push(suffix);
+        let sibling = PathBuf::from(name);
+        sibling.is_file().then_some((encoding, sibling))
+    });
+    let (served, encoding) = match precompressed {
+        Some((encoding, sibling)) => (sibling, Some(encoding)),
+        None => (requested.clone(), None),
+    };
+
+    // The path itself is plain, but a symlink inside the root could still lead outside it
+    let canonical_root = std::fs::canonicalize(root)?;
+    for path in [&requested, &served] {
+        if !std::fs::canonicalize(path)?.starts_with(&canonical_root) {
+            log::warn!("Static path {:?} resolves outside {:?}", path, root);
+            return Err(WikiError::InvalidPath);
+        }
+    }
     
-    let bytes = std::fs::read(&requested)?;
-    let file_service = FileService::new(state.static_dir.as_ref().clone());
-    let content_type = file_service.content_type_for(&requested);
-    let mut resp = Response::new(Body::from(bytes));
-    resp.headers_mut().insert(header::CONTENT_TYPE, content_type.parse().unwrap_or_else(|_| header::HeaderValue::from_static("application/octet-stream")));
+    let metadata = std::fs::metadata(&served)?;
+    let modified = metadata.modified().ok();
+    let etag = static_etag(&metadata);
+    
+    let mut resp = if is_not_modified(&headers, &etag, modified) {
+        log::debug!("Static file unchanged, sending 304: '{}'", normalized);
+        let mut resp = Response::new(Body::empty());
+        *resp.status_mut() = StatusCode::NOT_MODIFIED;
+        resp
+    } else {
+        let len = metadata.len();
+        // Ranges would index into the compressed bytes, so precompressed files are sent whole
+        let range = headers
+            .get(header::RANGE)
+            .filter(|_| encoding.is_none())
+            .and_then(|value| value.to_str().ok())
+            .map(|value| parse_byte_range(value, len))
+            .unwrap_or(Ok(None));
+        
+        let mut resp = match range {
+            Ok(Some((start, end))) => {
+                log::debug!("Serving bytes {}-{}/{} of '{}'", start, end, len, normalized);
+                ensure_file_size(&state, &served, end - start + 1)?;
+                let mut file = std::fs::File::open(&served)?;
+                file.seek(SeekFrom::Start(start))?;
+                let mut bytes = Vec::new();
+                file.take(end - start + 1).read_to_end(&mut bytes)?;
+                let mut resp = Response::new(Body::from(bytes));
+                *resp.status_mut() = StatusCode::PARTIAL_CONTENT;
+                if let Ok(value) = header::HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, len)) {
+                    resp.headers_mut().insert(header::CONTENT_RANGE, value);
+                }
+                resp
+            }
+            Ok(None) => {
+                ensure_file_size(&state, &served, len)?;
+                Response::new(Body::from(std::fs::read(&served)?))
+            }
+            Err(()) => {
+                log::debug!("Unsatisfiable range requested for '{}'", normalized);
+                let mut resp = Response::new(Body::empty());
+                *resp.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+                if let Ok(value) = header::HeaderValue::from_str(&format!("bytes */{}", len)) {
+                    resp.headers_mut().insert(header::CONTENT_RANGE, value);
+                }
+                resp
+            }
+        };
+        
+        let file_service = FileService::new(state.static_dir.as_ref().clone());
+        let content_type = file_service.content_type_for(&requested);
+        resp.headers_mut().insert(header::CONTENT_TYPE, content_type.parse().unwrap_or_else(|_| header::HeaderValue::from_static("application/octet-stream")));
+        resp
+    };
+    
+    let resp_headers = resp.headers_mut();
+    if let Some(encoding) = encoding {
+        resp_headers.insert(header::CONTENT_ENCODING, header::HeaderValue::from_static(encoding));
+    }
+    // Responses differ by Accept-Encoding whenever a precompressed variant exists
+    if precompressed_exists(&requested) {
+        add_vary(resp_headers, "accept-encoding");
+    }
+    resp_headers.insert(header::ACCEPT_RANGES, header::HeaderValue::from_static("bytes"));
+    resp_headers.insert(header::CACHE_CONTROL, header::HeaderValue::from_static(STATIC_CACHE_CONTROL));
+    if let Ok(value) = header::HeaderValue::from_str(&etag) {
+        resp_headers.insert(header::ETAG, value);
+    }
+    if let Some(modified) = modified
+        && let Ok(value) = header::HeaderValue::from_str(&httpdate::fmt_http_date(modified))
+    {
+        resp_headers.insert(header::LAST_MODIFIED, value);
+    }
     Ok(resp)
 }
 
-/// Render search results HTML
-fn render_search_results(query: &str, results: &[crate::types::SearchResult]) -> String {
-    let mut content = String::new();
-    
-    if query.is_empty() {
-        content.push_str("<div class=\"search-results\">");
+/// Send a markdown page, answering conditional requests with 304 before rendering it
+///
+/// The validators come from what the page is built from: the source file's
+/// size and mtime, the sidebar tree and the template. Edits to other pages
+/// that change the sidebar, or new recent pages, change them too, so a 304
+/// never pays for a render. `Last-Modified` is the newest of those mtimes.
+/// Browsers are asked to revalidate on every visit.
+fn markdown_page_response(
+    state: &AppState,
+    navigation: &NavigationComponent,
+    headers: &HeaderMap,
+    source: &Path,
+    render: impl FnOnce() -> Result<String, WikiError>,
+) -> Result<Response<Body>, WikiError> {
+    let metadata = std::fs::metadata(source)?;
+    let source_modified = metadata.modified()?;
+    let (tree, tree_modified) = navigation.tree_version()?;
+    let (template, template_modified) = state.templates.version();
+
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    source_modified.hash(&mut hasher);
+    tree.hash(&mut hasher);
+    template.hash(&mut hasher);
+    let etag = format!("\"{:x}\"", hasher.finish());
+    let modified = [Some(source_modified), tree_modified, Some(template_modified)]
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap_or(source_modified);
+
+    let mut resp = if is_not_modified(headers, &etag, Some(modified)) {
+        log::debug!("Page unchanged, sending 304: {:?}", source);
+        let mut resp = Response::new(Body::empty());
+        *resp.status_mut() = StatusCode::NOT_MODIFIED;
+        resp
+    } else {
+        Html(render()?).into_response()
+    };
+
+    let resp_headers = resp.headers_mut();
+    resp_headers.insert(header::CACHE_CONTROL, header::HeaderValue::from_static("no-cache"));
+    if let Ok(value) = header::HeaderValue::from_str(&etag) {
+        resp_headers.insert(header::ETAG, value);
+    }
+    if let Ok(value) = header::HeaderValue::from_str(&httpdate::fmt_http_date(modified)) {
+        resp_headers.insert(header::LAST_MODIFIED, value);
+    }
+    Ok(resp)
+}
+
+/// Content codings of precompressed static files and their file suffixes, in order of preference
+const PRECOMPRESSED_SUFFIXES: &[(&str, &str)] = &[("br", ".br"), ("gzip", ".gz")];
+
+/// Check whether any precompressed sibling of a static file exists
+fn precompressed_exists(path: &Path) -> bool {
+    PRECOMPRESSED_SUFFIXES.iter().any(|(_, suffix)| {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(suffix);
+        Path::new(&name).is_file()
+    })
+}
+
+/// Parse a single `bytes=` range into inclusive offsets within a body of `len` bytes
+///
+/// Returns `Ok(None)` when the header should be ignored (other units or several
+/// ranges) and `Err(())` when the range cannot be satisfied.
+fn parse_byte_range(value: &str, len: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+
+    let (start, end) = spec.split_once('-').ok_or(())?;
+    let (start, end) = (start.trim(), end.trim());
+    let range = match (start.is_empty(), end.is_empty()) {
+        // bytes=-N: the last N bytes
+        (true, false) => {
+            let suffix: u64 = end.parse().map_err(|_| ())?;
+            if suffix == 0 {
+                return Err(());
+            }
+            (len.saturating_sub(suffix), len.checked_sub(1).ok_or(())?)
+        }
+        // bytes=N-: from N to the end
+        (false, true) => (start.parse().map_err(|_| ())?, len.checked_sub(1).ok_or(())?),
+        (false, false) => {
+            let start: u64 = start.parse().map_err(|_| ())?;
+            let end: u64 = end.parse().map_err(|_| ())?;
+            if end < start {
+                return Err(());
+            }
+            (start, end.min(len.saturating_sub(1)))
+        }
+        (true, true) => return Err(()),
+    };
+
+    if range.0 >= len {
+        return Err(());
+    }
+    Ok(Some(range))
+}
+
+/// Cache policy for static assets; clients revalidate with the ETag after an hour
+const STATIC_CACHE_CONTROL: &str = "public, max-age=3600";
+
+/// Entity tag derived from file size and modification time
+fn static_etag(metadata: &std::fs::Metadata) -> String {
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|mtime| mtime.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|dur| dur.as_nanos())
+        .unwrap_or(0);
+    format!("\"{:x}-{:x}\"", metadata.len(), mtime)
+}
+
+/// Check the request's conditional headers against the current validators
+///
+/// `If-None-Match` takes precedence; `If-Modified-Since` is only consulted
+/// when it is absent, as HTTP requires.
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: Option<std::time::SystemTime>) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+        let Ok(if_none_match) = if_none_match.to_str() else {
+            return false;
+        };
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+    }
+
+    match (headers.get(header::IF_MODIFIED_SINCE), modified) {
+        (Some(since), Some(modified)) => since
+            .to_str()
+            .ok()
+            .and_then(|since| httpdate::parse_http_date(since).ok())
+            // HTTP dates have whole-second precision
+            .is_some_and(|since| modified.duration_since(since).map(|d| d.as_secs() == 0).unwrap_or(true)),
+        _ => false,
+    }
+}
+
+/// Render the prompt for an empty or too short query, with suggested pages
+fn render_search_suggestions(query: &str, suggestions: &[RecentPage]) -> String {
+    let mut content = String::from("<div class=\"search-results\">");
+    if query.trim().is_empty() {
         content.push_str("<p class=\"no-query\">Enter a search query to find content.</p>");
+    } else {
+        content.push_str(&format!(
+            "<p class=\"no-query\">Enter at least {} characters to search.</p>",
+            MIN_QUERY_CHARS
+        ));
+    }
+
+    if !suggestions.is_empty() {
+        content.push_str("<div class=\"search-suggestions\">");
+        content.push_str("<h3>Recently updated</h3>");
+        content.push_str("<ul>");
+        for page in suggestions {
+            content.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>",
+                escape_attr(&page_url(&page.path)),
+                escape_html(&page.title)
+            ));
+        }
+        content.push_str("</ul>");
         content.push_str("</div>");
-        return content;
     }
+
+    content.push_str("</div>");
+    content
+}
+
+/// Render search results HTML
+fn render_search_results(query: &str, results: &[crate::types::SearchResult]) -> String {
+    let mut content = String::new();
     
     content.push_str("<div class=\"search-results\">");
     content.push_str(&format!("<h2 class=\"search-header\">Search Results for \"{}\"</h2>", escape_html(query)));
@@ -459,10 +1322,18 @@ fn render_search_results(query: &str, results: &[crate::types::SearchResult]) ->
                 "<p class=\"result-path\"><code>{}</code></p>",
                 escape_html(&path_display)
             ));
-            content.push_str(&format!(
-                "<p class=\"result-excerpt\">{}</p>",
-                escape_html(&result.excerpt)
-            ));
+            if result.excerpts.len() > 1 {
+                content.push_str("<ul class=\"result-excerpts\">");
+                for excerpt in &result.excerpts {
+                    content.push_str(&format!("<li class=\"result-excerpt\">{}</li>", escape_html(excerpt)));
+                }
+                content.push_str("</ul>");
+            } else {
+                content.push_str(&format!(
+                    "<p class=\"result-excerpt\">{}</p>",
+                    escape_html(&result.excerpt)
+                ));
+            }
             content.push_str(&format!(
                 "<div class=\"result-meta\">Relevance: {:.1}</div>",
                 result.relevance
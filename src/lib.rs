@@ -6,16 +6,21 @@
 pub mod components;
 pub mod config;
 pub mod errors;
+pub mod handlers;
+pub mod logger;
+pub mod metrics;
+pub mod middleware;
 pub mod services;
 pub mod types;
 pub mod utils;
 
 // Re-export commonly used items
-pub use config::Config;
+pub use config::{Config, ListenAddr, MarkdownConfig, SearchConfig};
 pub use errors::WikiError;
-pub use types::{AppState, DirEntry, SearchResult, MarkdownResult, TemplateContext};
-pub use services::{FileService, SearchService, MarkdownService};
-pub use components::{FabComponent, NavigationComponent, TemplateComponent};
+pub use metrics::Metrics;
+pub use types::{AppState, DirEntry, DirEntryMeta, RecentPage, SearchResult, MarkdownResult, TemplateContext};
+pub use services::{ContentCache, FileService, SearchService, MarkdownService};
+pub use components::{FabActionTemplate, FabComponent, NavigationComponent, SidebarCache, TemplateComponent};
 
 // Re-export utility functions
-pub use utils::{escape_html, escape_attr, last_modified_html, normalize_path, parse_query_param};
+pub use utils::{decode_url_path, ensure_safe_path, escape_html, escape_attr, escape_json, format_rfc3339, format_size, last_modified_html, normalize_path, page_url, parse_query_param, url_path, Frontmatter, PathFilter};
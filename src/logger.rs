@@ -1,23 +1,40 @@
 use log::{Level, LevelFilter, Log, Metadata, Record};
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use time::OffsetDateTime;
 
 pub enum LogOutput {
     Stdout,
     Stderr,
 }
 
+/// How an existing log file is treated when the logger opens it
+pub enum LogFileMode {
+    /// Keep earlier entries and add new ones at the end
+    Append,
+    /// Start from an empty file on every startup
+    Truncate,
+}
+
 pub struct Logger {
     pub write_to_file: bool,
     pub write_to_std: Option<LogOutput>,
     pub severity: Level,
     pub file: Option<Arc<Mutex<File>>>,
     pub enable_colors: bool,
+    /// Path of the log file, used when rotating
+    pub path: Option<PathBuf>,
+    /// Rotate the log file once it grows past this many bytes
+    pub max_bytes: Option<u64>,
+    /// Number of rotated files (`.1` to `.N`) to keep
+    pub max_files: usize,
 }
 
+/// Rotated files kept by default
+const DEFAULT_MAX_FILES: usize = 5;
+
 impl Logger {
     /// Create a new logger
     pub fn new(
@@ -25,6 +42,7 @@ impl Logger {
         severity: Option<Level>,
         write_to_std: Option<LogOutput>,
         write_to_file: bool,
+        file_mode: LogFileMode,
         enable_colors: bool,
     ) -> Self {
         let mut path = file_path;
@@ -50,16 +68,17 @@ impl Logger {
         let mut file = None;
 
         // Create log directory if it doesn't exist
-        if let Some(path_ref) = path.as_ref() {
-            if let Some(parent) = path_ref.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
+        if let Some(parent) = path.as_ref().and_then(|p| p.parent()) {
+            let _ = std::fs::create_dir_all(parent);
         }
 
-        if write_to_file {
-            if let Some(path_ref) = path.as_ref() {
-                file = File::create(path_ref).ok().map(|f| Arc::new(Mutex::new(f)));
-            }
+        if write_to_file && let Some(path_ref) = path.as_ref() {
+            let mut options = OpenOptions::new();
+            match file_mode {
+                LogFileMode::Append => options.create(true).append(true),
+                LogFileMode::Truncate => options.create(true).write(true).truncate(true),
+            };
+            file = options.open(path_ref).ok().map(|f| Arc::new(Mutex::new(f)));
         }
 
         Logger {
@@ -68,22 +87,58 @@ impl Logger {
             severity: severity.unwrap_or(Level::Info),
             file,
             enable_colors,
+            path,
+            max_bytes: None,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+
+    /// Rotate the log file when it exceeds `max_bytes`, keeping `max_files` old files
+    pub fn with_rotation(mut self, max_bytes: u64, max_files: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self.max_files = max_files.max(1);
+        self
+    }
+
+    /// Shift `log.N` files up by one, move the current file to `log.1` and reopen it
+    ///
+    /// Called with the file lock held so no line is written during the swap.
+    fn rotate(&self, file: &mut File) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let numbered = |n: usize| {
+            let mut name = path.clone().into_os_string();
+            name.push(format!(".{n}"));
+            PathBuf::from(name)
+        };
+
+        let _ = std::fs::remove_file(numbered(self.max_files));
+        for n in (1..self.max_files).rev() {
+            let _ = std::fs::rename(numbered(n), numbered(n + 1));
+        }
+        if std::fs::rename(path, numbered(1)).is_err() {
+            return;
+        }
+
+        match OpenOptions::new().create(true).write(true).truncate(true).open(path) {
+            Ok(fresh) => *file = fresh,
+            Err(e) => eprintln!("Failed to reopen log file {:?} after rotation: {}", path, e),
         }
     }
 
-    /// Get current timestamp as string
+    /// Get current UTC timestamp as string, e.g. `2024-05-01 13:45:07Z`
     fn get_timestamp() -> String {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default();
-        
-        let secs = now.as_secs();
-        let hours = (secs / 3600) % 24;
-        let minutes = (secs / 60) % 60;
-        let seconds = secs % 60;
-        
-        // Simple timestamp format: HH:MM:SS
-        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+        let now = OffsetDateTime::now_utc();
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}Z",
+            now.year(),
+            u8::from(now.month()),
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.second()
+        )
     }
 
     /// Get color code for log level
@@ -112,15 +167,24 @@ impl Logger {
 
         let write_to_std = Some(LogOutput::Stderr);
         let write_to_file = std::env::var("STRATA_LOG_FILE").is_ok();
+        let file_mode = if std::env::var("STRATA_LOG_TRUNCATE").is_ok() {
+            LogFileMode::Truncate
+        } else {
+            LogFileMode::Append
+        };
         let enable_colors = std::env::var("NO_COLOR").is_err();
 
-        let logger = Logger::new(
+        let mut logger = Logger::new(
             None,
             Some(severity),
             write_to_std,
             write_to_file,
+            file_mode,
             enable_colors,
         );
+        if let Some(max_bytes) = std::env::var("STRATA_LOG_MAX_BYTES").ok().and_then(|v| v.parse().ok()) {
+            logger = logger.with_rotation(max_bytes, DEFAULT_MAX_FILES);
+        }
         log::set_max_level(LevelFilter::Trace);
         log::set_logger(Box::leak(Box::new(logger)))?;
         Ok(())
@@ -162,12 +226,17 @@ impl Log for Logger {
         }
 
         // Write to file (without colors)
-        if self.write_to_file {
-            if let Some(file) = &self.file {
-                if let Ok(mut file_guard) = file.lock() {
-                    let file_message = format!("[{timestamp}] {level_str} {args}");
-                    let _ = writeln!(file_guard, "{file_message}");
-                }
+        if self.write_to_file
+            && let Some(file) = &self.file
+            && let Ok(mut file_guard) = file.lock()
+        {
+            let file_message = format!("[{timestamp}] {level_str} {args}");
+            let _ = writeln!(file_guard, "{file_message}");
+
+            if let Some(max_bytes) = self.max_bytes
+                && file_guard.metadata().map(|m| m.len() > max_bytes).unwrap_or(false)
+            {
+                self.rotate(&mut file_guard);
             }
         }
     }
@@ -176,4 +245,121 @@ impl Log for Logger {
         let _ = std::io::stdout().flush();
         let _ = std::io::stderr().flush();
     }
-}
\ No newline at end of file
+}
+
+/// Path of a log file receiving every record logged by this test binary
+///
+/// The first call installs the global logger, so tests that check log output share it.
+#[cfg(test)]
+pub(crate) fn captured_log() -> &'static std::path::Path {
+    static LOG: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+    LOG.get_or_init(|| {
+        let dir = Box::leak(Box::new(tempfile::tempdir().unwrap()));
+        let path = dir.path().join("strata.log");
+        let logger = Logger::new(Some(path.clone()), Some(Level::Info), None, true, LogFileMode::Append, false);
+        log::set_logger(Box::leak(Box::new(logger))).unwrap();
+        log::set_max_level(log::LevelFilter::Info);
+        path
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file-only logger without colors writing to `path`
+    fn file_logger(path: &std::path::Path, mode: LogFileMode) -> Logger {
+        Logger::new(Some(path.to_path_buf()), Some(Level::Info), None, true, mode, false)
+    }
+
+    fn write(logger: &Logger, message: &str) {
+        logger.log(&Record::builder().level(Level::Info).args(format_args!("{message}")).build());
+    }
+
+    #[test]
+    fn append_mode_keeps_earlier_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/strata.log");
+
+        write(&file_logger(&path, LogFileMode::Append), "first run");
+        write(&file_logger(&path, LogFileMode::Append), "second run");
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("INFO first run"));
+        assert!(lines[1].ends_with("INFO second run"));
+    }
+
+    #[test]
+    fn truncate_mode_starts_fresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("strata.log");
+
+        write(&file_logger(&path, LogFileMode::Append), "first run");
+        write(&file_logger(&path, LogFileMode::Truncate), "second run");
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(log.lines().count(), 1);
+        assert!(log.contains("second run"));
+    }
+
+    #[test]
+    fn timestamps_carry_the_utc_date() {
+        let before = OffsetDateTime::now_utc();
+        let timestamp = Logger::get_timestamp();
+        let after = OffsetDateTime::now_utc();
+
+        let (date, clock) = timestamp.strip_suffix('Z').unwrap().split_once(' ').unwrap();
+        let date: Vec<u32> = date.split('-').map(|part| part.parse().unwrap()).collect();
+        let clock: Vec<u8> = clock.split(':').map(|part| part.parse().unwrap()).collect();
+        let month = time::Month::try_from(date[1] as u8).unwrap();
+        let parsed = time::Date::from_calendar_date(date[0] as i32, month, date[2] as u8)
+            .unwrap()
+            .with_hms(clock[0], clock[1], clock[2])
+            .unwrap()
+            .assume_utc();
+        assert!(before.unix_timestamp() <= parsed.unix_timestamp());
+        assert!(parsed <= after);
+        assert_eq!(timestamp.len(), "2024-05-01 13:45:07Z".len());
+    }
+
+    #[test]
+    fn file_lines_start_with_the_timestamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("strata.log");
+        write(&file_logger(&path, LogFileMode::Append), "hello");
+
+        let line = std::fs::read_to_string(&path).unwrap();
+        let timestamp = line.strip_prefix('[').and_then(|rest| rest.split_once(']')).unwrap().0;
+        let (date, _) = timestamp.split_once(' ').unwrap();
+        let today = OffsetDateTime::now_utc().date();
+        assert_eq!(date, format!("{:04}-{:02}-{:02}", today.year(), u8::from(today.month()), today.day()));
+    }
+
+    #[test]
+    fn writing_past_the_limit_rotates_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("strata.log");
+        let logger = file_logger(&path, LogFileMode::Append).with_rotation(100, 2);
+
+        for n in 0..10 {
+            write(&logger, &format!("line {n} {}", "x".repeat(40)));
+        }
+
+        let rotated = |n: usize| std::fs::read_to_string(dir.path().join(format!("strata.log.{n}")));
+        let current = std::fs::read_to_string(&path).unwrap();
+        assert!(current.len() <= 100 + 80);
+        assert!(rotated(1).unwrap().len() > 100);
+        assert!(rotated(2).is_ok());
+        assert!(rotated(3).is_err(), "only max_files rotated files are kept");
+
+        // Nothing written since the last rotation is lost
+        let newest: Vec<String> = [rotated(2).unwrap(), rotated(1).unwrap(), current].concat().lines().map(str::to_string).collect();
+        let numbers: Vec<usize> = newest
+            .iter()
+            .map(|line| line.split_whitespace().nth(4).unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(numbers, ((10 - numbers.len())..10).collect::<Vec<_>>());
+    }
+}
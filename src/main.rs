@@ -1,20 +1,18 @@
-use axum::{routing::get, Router};
+use axum::Router;
 use tokio::net::TcpListener;
-use log::{info, error};
-
-use crate::config::Config;
-use crate::errors::WikiError;
-use crate::types::AppState;
-use crate::handlers::{handle_path, handle_root, handle_search, handle_raw, handle_static};
-
-mod components;
-mod config;
-mod errors;
-mod handlers;
-mod services;
-mod types;
-mod utils;
-mod logger;
+use log::{debug, info, error, warn};
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use strata::services::{FileService, SearchIndex, SearchService};
+use strata::config::{Config, ListenAddr};
+use strata::errors::WikiError;
+use strata::types::AppState;
+use strata::handlers::router;
+use strata::logger;
+use strata::metrics::Metrics;
 
 #[tokio::main]
 async fn main() -> Result<(), WikiError> {
@@ -26,7 +24,7 @@ async fn main() -> Result<(), WikiError> {
     
     info!("Starting Strata Wiki server...");
     
-    let config = Config::new();
+    let config = Config::load();
     info!("Configuration loaded successfully");
     
     // Validate directories exist
@@ -37,20 +35,43 @@ async fn main() -> Result<(), WikiError> {
     
     info!("Base directory validated: {:?}", config.base_dir);
 
-    let state = AppState { 
-        base_dir: config.base_dir.clone(), 
-        static_dir: config.static_dir.clone() 
-    };
+    let search_index = Arc::new(SearchIndex::new());
+    let state = AppState::from_config(&config, search_index.clone(), Arc::new(Metrics::new()));
+    let search_service = SearchService::new(
+        FileService::new(config.base_dir.as_ref().clone())
+            .with_exclude(state.exclude.clone())
+            .with_max_file_bytes(config.max_file_bytes),
+        search_index.clone(),
+        config.search.clone(),
+    )
+    .with_drafts(state.show_drafts);
+    if let Err(e) = search_service.rebuild_index() {
+        error!("Initial search index build failed: {:?}", e);
+    }
+    info!("Search index built with {} documents", search_index.len());
+
+    // Keep the index in step with edits made on disk
+    let reindex_interval = Duration::from_secs(config.search_reindex_secs.max(1));
+    tokio::spawn(async move {
+        let search_service = Arc::new(search_service);
+        let mut interval = tokio::time::interval(reindex_interval);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let service = search_service.clone();
+            let rebuilt = tokio::task::spawn_blocking(move || service.rebuild_index()).await;
+            if let Ok(Err(e)) = rebuilt {
+                error!("Background search index rebuild failed: {:?}", e);
+            }
+        }
+    });
 
-    let app = Router::new()
-        .route("/", get(handle_root))
-        .route("/search", get(handle_search))
-        .route("/raw/*path", get(handle_raw))
-        .route("/static/*path", get(handle_static))
-        .route("/*path", get(handle_path))
-        .with_state(state);
+    let app = router(state, Duration::from_millis(config.slow_request_ms));
 
-    let addr = config.socket_addr();
+    let addr = match config.listen_addr() {
+        ListenAddr::Tcp(addr) => addr,
+        ListenAddr::Unix(path) => return serve_unix(&path, app).await,
+    };
     info!("Wiki server starting on http://{}", addr);
     
     let listener = TcpListener::bind(addr).await?;
@@ -61,3 +82,62 @@ async fn main() -> Result<(), WikiError> {
         WikiError::from(e)
     })
 }
+
+/// Serve the app on a Unix domain socket until Ctrl-C, then remove the socket file
+#[cfg(unix)]
+async fn serve_unix(path: &Path, app: Router) -> Result<(), WikiError> {
+    use std::os::unix::fs::FileTypeExt;
+    use hyper_util::rt::TokioIo;
+    use hyper_util::service::TowerToHyperService;
+    use tokio::net::UnixListener;
+
+    // A socket left behind by an earlier run would make the bind fail
+    if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        info!("Removing stale socket {:?}", path);
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    info!("Server listening successfully on unix:{}", path.display());
+
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("Failed to accept connection: {}", e);
+                        continue;
+                    }
+                };
+                let service = TowerToHyperService::new(app.clone());
+                tokio::spawn(async move {
+                    if let Err(e) = hyper::server::conn::http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await
+                    {
+                        debug!("Connection error: {}", e);
+                    }
+                });
+            }
+            _ = &mut shutdown => {
+                info!("Shutting down");
+                break;
+            }
+        }
+    }
+
+    if let Err(e) = std::fs::remove_file(path) {
+        warn!("Failed to remove socket {:?}: {}", path, e);
+    }
+    Ok(())
+}
+
+/// Unix domain sockets are not available on this platform
+#[cfg(not(unix))]
+async fn serve_unix(path: &Path, _app: Router) -> Result<(), WikiError> {
+    error!("Cannot listen on unix:{}: Unix sockets are not supported on this platform", path.display());
+    Err(WikiError::InvalidPath)
+}
@@ -0,0 +1,128 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the request duration histogram buckets
+const DURATION_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Request counters and latency histogram, rendered in the Prometheus text format
+///
+/// All fields are atomics so handlers and middleware can update them through
+/// a shared reference without locking.
+#[derive(Default)]
+pub struct Metrics {
+    requests_total: AtomicU64,
+    /// Responses by status class, 1xx through 5xx
+    status_classes: [AtomicU64; 5],
+    search_queries: AtomicU64,
+    /// Non-cumulative counts per bucket; the last slot counts durations above every bound
+    duration_buckets: [AtomicU64; DURATION_BUCKETS.len() + 1],
+    duration_sum_micros: AtomicU64,
+}
+
+impl Metrics {
+    /// Create a registry with every counter at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a finished request by its status code and time taken
+    pub fn record_request(&self, status: u16, elapsed: Duration) {
+        self.requests_total.fetch_add(1, Ordering::Relaxed);
+        if let Some(class) = (status / 100).checked_sub(1).and_then(|idx| self.status_classes.get(idx as usize)) {
+            class.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let seconds = elapsed.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.duration_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.duration_sum_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Count a search query
+    pub fn record_search(&self) {
+        self.search_queries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP strata_requests_total Total HTTP requests served.\n");
+        out.push_str("# TYPE strata_requests_total counter\n");
+        let _ = writeln!(out, "strata_requests_total {}", self.requests_total.load(Ordering::Relaxed));
+
+        out.push_str("# HELP strata_responses_total HTTP responses by status class.\n");
+        out.push_str("# TYPE strata_responses_total counter\n");
+        for (idx, count) in self.status_classes.iter().enumerate() {
+            let _ = writeln!(out, "strata_responses_total{{class=\"{}xx\"}} {}", idx + 1, count.load(Ordering::Relaxed));
+        }
+
+        out.push_str("# HELP strata_search_queries_total Search queries handled.\n");
+        out.push_str("# TYPE strata_search_queries_total counter\n");
+        let _ = writeln!(out, "strata_search_queries_total {}", self.search_queries.load(Ordering::Relaxed));
+
+        out.push_str("# HELP strata_request_duration_seconds Time taken to handle requests.\n");
+        out.push_str("# TYPE strata_request_duration_seconds histogram\n");
+        let mut cumulative = 0;
+        for (idx, count) in self.duration_buckets.iter().enumerate() {
+            cumulative += count.load(Ordering::Relaxed);
+            match DURATION_BUCKETS.get(idx) {
+                Some(bound) => {
+                    let _ = writeln!(out, "strata_request_duration_seconds_bucket{{le=\"{}\"}} {}", bound, cumulative);
+                }
+                None => {
+                    let _ = writeln!(out, "strata_request_duration_seconds_bucket{{le=\"+Inf\"}} {}", cumulative);
+                }
+            }
+        }
+        let sum = self.duration_sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "strata_request_duration_seconds_sum {}", sum);
+        let _ = writeln!(out, "strata_request_duration_seconds_count {}", cumulative);
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_fill_cumulative_buckets() {
+        let metrics = Metrics::new();
+        metrics.record_request(200, Duration::from_millis(3));
+        metrics.record_request(301, Duration::from_millis(30));
+        metrics.record_request(503, Duration::from_secs(10));
+
+        let out = metrics.render();
+        for line in [
+            "strata_requests_total 3",
+            "strata_responses_total{class=\"2xx\"} 1",
+            "strata_responses_total{class=\"3xx\"} 1",
+            "strata_responses_total{class=\"5xx\"} 1",
+            "strata_request_duration_seconds_bucket{le=\"0.005\"} 1",
+            "strata_request_duration_seconds_bucket{le=\"0.025\"} 1",
+            "strata_request_duration_seconds_bucket{le=\"0.05\"} 2",
+            "strata_request_duration_seconds_bucket{le=\"5\"} 2",
+            "strata_request_duration_seconds_bucket{le=\"+Inf\"} 3",
+            "strata_request_duration_seconds_sum 10.033",
+            "strata_request_duration_seconds_count 3",
+        ] {
+            assert!(out.lines().any(|l| l == line), "missing {line:?} in\n{out}");
+        }
+    }
+
+    #[test]
+    fn out_of_range_statuses_count_only_in_the_total() {
+        let metrics = Metrics::new();
+        metrics.record_request(99, Duration::ZERO);
+        metrics.record_request(600, Duration::ZERO);
+        let out = metrics.render();
+        assert!(out.contains("strata_requests_total 2\n"));
+        assert!(!out.lines().any(|line| line.starts_with("strata_responses_total{") && !line.ends_with(" 0")), "{out}");
+    }
+}
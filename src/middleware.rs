@@ -0,0 +1,341 @@
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use flate2::{write::{GzEncoder, ZlibEncoder}, Compression};
+use log::{debug, info, warn};
+
+use crate::metrics::Metrics;
+
+/// Log method, path, status and elapsed time for every request
+///
+/// Requests slower than the threshold in the layer's state are logged at warn level.
+pub async fn log_requests(
+    State(slow_threshold): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    let elapsed = start.elapsed();
+    let status = response.status().as_u16();
+    if elapsed > slow_threshold {
+        warn!("{} {} {} {:.1}ms (slow)", method, path, status, elapsed.as_secs_f64() * 1000.0);
+    } else {
+        info!("{} {} {} {:.1}ms", method, path, status, elapsed.as_secs_f64() * 1000.0);
+    }
+    response
+}
+
+/// Count every request and its duration in the metrics registry
+pub async fn track_metrics(State(metrics): State<Arc<Metrics>>, request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let response = next.run(request).await;
+    metrics.record_request(response.status().as_u16(), start.elapsed());
+    response
+}
+
+/// Bodies smaller than this are sent as-is; compressing them saves little
+const MIN_COMPRESS_BYTES: usize = 1024;
+
+/// Largest body that will be buffered for compression
+const MAX_COMPRESS_BYTES: usize = 16 * 1024 * 1024;
+
+/// Content encodings the server can produce, in order of preference
+#[derive(Clone, Copy)]
+enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    fn header_value(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+}
+
+/// Compress text responses when the client accepts gzip or deflate
+///
+/// Bodies whose size is not known up front, or is above `MAX_COMPRESS_BYTES`,
+/// are passed through untouched rather than buffered.
+pub async fn compress_response(request: Request, next: Next) -> Response {
+    let encoding = negotiate_encoding(request.headers());
+    let response = next.run(request).await;
+
+    if !is_compressible(&response) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    add_vary(&mut parts.headers, "accept-encoding");
+    let Some(encoding) = encoding else {
+        return Response::from_parts(parts, body);
+    };
+
+    let bytes = match to_bytes(body, MAX_COMPRESS_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to buffer response for compression: {}", e);
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            return response;
+        }
+    };
+
+    if bytes.len() < MIN_COMPRESS_BYTES {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    match encode(encoding, &bytes) {
+        Ok(compressed) => {
+            debug!("Compressed response with {}: {} -> {} bytes", encoding.header_value(), bytes.len(), compressed.len());
+            parts.headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding.header_value()));
+            parts.headers.remove(header::CONTENT_LENGTH);
+            // The encoded bytes differ from the identity representation
+            if let Some(etag) = parts.headers.get(header::ETAG).and_then(|v| v.to_str().ok())
+                && !etag.starts_with("W/")
+                && let Ok(weak) = HeaderValue::from_str(&format!("W/{}", etag))
+            {
+                parts.headers.insert(header::ETAG, weak);
+            }
+            Response::from_parts(parts, Body::from(compressed))
+        }
+        Err(e) => {
+            warn!("Failed to compress response: {}", e);
+            Response::from_parts(parts, Body::from(bytes))
+        }
+    }
+}
+
+/// Check whether the `Accept-Encoding` header allows a content coding
+///
+/// A coding listed with `q=0` counts as refused.
+pub fn accepts_encoding(headers: &HeaderMap, name: &str) -> bool {
+    let Some(accept) = headers.get(header::ACCEPT_ENCODING).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    accept.split(',').any(|item| {
+        let mut params = item.split(';').map(str::trim);
+        let coding = params.next().unwrap_or("");
+        let refused = params.any(|param| {
+            param
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        });
+        coding.eq_ignore_ascii_case(name) && !refused
+    })
+}
+
+/// Pick the preferred encoding allowed by the `Accept-Encoding` header
+fn negotiate_encoding(headers: &HeaderMap) -> Option<Encoding> {
+    if accepts_encoding(headers, "gzip") {
+        Some(Encoding::Gzip)
+    } else if accepts_encoding(headers, "deflate") {
+        Some(Encoding::Deflate)
+    } else {
+        None
+    }
+}
+
+/// Only uncompressed, successful text-like responses are worth compressing
+fn is_compressible(response: &Response) -> bool {
+    if !response.status().is_success() || response.status() == StatusCode::NO_CONTENT {
+        return false;
+    }
+
+    let headers = response.headers();
+    if headers.contains_key(header::CONTENT_ENCODING) || headers.contains_key(header::CONTENT_RANGE) {
+        return false;
+    }
+
+    // Only bodies of a known, bounded size are buffered
+    let size = response.body().size_hint();
+    if size.upper().is_none_or(|len| len > MAX_COMPRESS_BYTES as u64) {
+        return false;
+    }
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/javascript" | "application/json" | "application/xml" | "image/svg+xml"
+        )
+}
+
+/// Add a field name to the `Vary` header, merging with any value already set
+pub fn add_vary(headers: &mut HeaderMap, name: &str) {
+    let existing: Vec<&str> = headers
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .collect();
+    if existing.iter().any(|field| *field == "*" || field.eq_ignore_ascii_case(name)) {
+        return;
+    }
+
+    let merged = existing.into_iter().chain([name]).collect::<Vec<_>>().join(", ");
+    if let Ok(value) = HeaderValue::from_str(&merged) {
+        headers.insert(header::VARY, value);
+    }
+}
+
+/// Compress a buffered body with the chosen encoding
+fn encode(encoding: Encoding, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+        Encoding::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn, routing::get, Router};
+    use std::io::Read;
+    use tower::ServiceExt;
+
+    /// Run one GET through a router whose only route returns `response`
+    async fn compress(response: fn() -> Response, accept: Option<&str>) -> (HeaderMap, Vec<u8>) {
+        let app = Router::new().route("/", get(move || async move { response() })).layer(from_fn(compress_response));
+        let mut request = Request::builder().uri("/");
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT_ENCODING, accept);
+        }
+        let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let headers = response.headers().clone();
+        (headers, to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec())
+    }
+
+    fn text(len: usize) -> Response {
+        Response::builder()
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from("x".repeat(len)))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn gzip_round_trips() {
+        let (headers, body) = compress(|| text(4096), Some("gzip")).await;
+        assert_eq!(headers.get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(body.as_slice()).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, "x".repeat(4096));
+    }
+
+    #[tokio::test]
+    async fn small_and_unaccepted_bodies_are_sent_as_is() {
+        let (headers, body) = compress(|| text(10), Some("gzip")).await;
+        assert!(headers.get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(body, b"xxxxxxxxxx");
+
+        let (headers, body) = compress(|| text(4096), None).await;
+        assert!(headers.get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(headers.get(header::VARY).unwrap(), "accept-encoding");
+        assert_eq!(body.len(), 4096);
+    }
+
+    #[tokio::test]
+    async fn oversized_and_unsized_bodies_pass_through() {
+        let (headers, body) = compress(|| text(MAX_COMPRESS_BYTES + 1), Some("gzip")).await;
+        assert!(headers.get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(body.len(), MAX_COMPRESS_BYTES + 1);
+
+        // A streamed body has no upper size bound
+        let stream = || {
+            let chunks = futures_util::stream::iter((0..4).map(|_| Ok::<_, std::io::Error>("x".repeat(1024))));
+            Response::builder()
+                .header(header::CONTENT_TYPE, "text/html")
+                .body(Body::from_stream(chunks))
+                .unwrap()
+        };
+        let (headers, body) = compress(stream, Some("gzip")).await;
+        assert!(headers.get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(body.len(), 4096);
+    }
+
+    #[tokio::test]
+    async fn images_are_not_compressed() {
+        let png = || {
+            Response::builder()
+                .header(header::CONTENT_TYPE, "image/png")
+                .body(Body::from(vec![0u8; 4096]))
+                .unwrap()
+        };
+        let (headers, body) = compress(png, Some("gzip, deflate")).await;
+        assert!(headers.get(header::CONTENT_ENCODING).is_none());
+        assert!(headers.get(header::VARY).is_none());
+        assert_eq!(body, vec![0u8; 4096]);
+    }
+
+    #[tokio::test]
+    async fn vary_is_merged_with_the_handler_value() {
+        let varied = || {
+            let mut response = text(4096);
+            response.headers_mut().insert(header::VARY, HeaderValue::from_static("cookie"));
+            response
+        };
+        let (headers, _) = compress(varied, Some("gzip")).await;
+        let vary: Vec<_> = headers.get_all(header::VARY).iter().collect();
+        assert_eq!(vary, ["cookie, accept-encoding"]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+        add_vary(&mut headers, "accept-encoding");
+        assert_eq!(headers.get_all(header::VARY).iter().collect::<Vec<_>>(), ["Accept-Encoding"]);
+    }
+
+    /// Send a request through `log_requests` to a handler answering with `status`
+    async fn logged_request(method: &str, uri: &str, status: StatusCode, slow_threshold: Duration) {
+        let app = Router::new()
+            .route("/*path", axum::routing::any(move || async move { status }))
+            .layer(axum::middleware::from_fn_with_state(slow_threshold, log_requests));
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn requests_are_logged_with_method_path_and_status() {
+        let log = crate::logger::captured_log();
+        logged_request("POST", "/logged/fast", StatusCode::CREATED, Duration::from_secs(60)).await;
+        logged_request("GET", "/logged/slow", StatusCode::NOT_FOUND, Duration::ZERO).await;
+
+        let log = std::fs::read_to_string(log).unwrap();
+        let line = |path: &str| log.lines().find(|line| line.contains(path)).unwrap_or_else(|| panic!("no line for {path} in {log}"));
+        let fast = line("/logged/fast");
+        assert!(fast.contains(" INFO POST /logged/fast 201 "), "{fast}");
+        assert!(fast.ends_with("ms"));
+        let slow = line("/logged/slow");
+        assert!(slow.contains(" WARN GET /logged/slow 404 "), "{slow}");
+        assert!(slow.ends_with("ms (slow)"));
+    }
+}
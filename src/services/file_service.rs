@@ -1,24 +1,179 @@
-use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 use log::{debug, info, warn, error};
 use crate::errors::WikiError;
-use crate::types::DirEntry;
+use crate::types::{DirEntry, DirEntryMeta};
+use crate::utils::{ensure_safe_path, normalize_path, PathFilter};
+
+/// Largest file `read_file` loads unless configured otherwise (16 MiB)
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Default byte budget of a `ContentCache` (64 MiB)
+pub const DEFAULT_CONTENT_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+/// File contents kept in memory between reads
+///
+/// Entries remember the modification time and size of the file they were
+/// read from, so an edited file is read again. When the cached contents
+/// exceed the byte budget, the least recently used files are evicted.
+#[derive(Default)]
+pub struct ContentCache {
+    state: Mutex<CacheState>,
+    max_bytes: usize,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<PathBuf, CachedContent>,
+    total_bytes: usize,
+    /// Incremented on every access, to order entries by last use
+    clock: u64,
+}
+
+struct CachedContent {
+    modified: SystemTime,
+    len: u64,
+    content: String,
+    last_used: u64,
+}
+
+impl ContentCache {
+    /// Create an empty cache holding at most `max_bytes` of file content
+    pub fn new(max_bytes: usize) -> Self {
+        Self { state: Mutex::default(), max_bytes }
+    }
+
+    /// Cached content of a file, if it was read at the given modification time and size
+    pub fn get(&self, path: &Path, modified: SystemTime, len: u64) -> Option<String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(path)?;
+        if entry.modified != modified || entry.len != len {
+            return None;
+        }
+        entry.last_used = clock;
+        Some(entry.content.clone())
+    }
+
+    /// Store a file's content, evicting older entries to stay within the budget
+    pub fn insert(&self, path: &Path, modified: SystemTime, len: u64, content: &str) {
+        if content.len() > self.max_bytes {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let last_used = state.clock;
+        let entry = CachedContent { modified, len, content: content.to_string(), last_used };
+        if let Some(old) = state.entries.insert(path.to_path_buf(), entry) {
+            state.total_bytes -= old.content.len();
+        }
+        state.total_bytes += content.len();
+
+        while state.total_bytes > self.max_bytes {
+            let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+            else {
+                break;
+            };
+            if let Some(evicted) = state.entries.remove(&oldest) {
+                debug!("Evicting {:?} from content cache", oldest);
+                state.total_bytes -= evicted.content.len();
+            }
+        }
+    }
+
+    /// Drop every cached file
+    pub fn invalidate(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.entries.clear();
+        state.total_bytes = 0;
+    }
+
+    /// Number of files currently cached
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).entries.len()
+    }
+
+    /// Whether no files are cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 
 /// Service for handling file system operations
 #[derive(Clone)]
 pub struct FileService {
     base_dir: PathBuf,
+    /// Entries hidden from directory listings
+    exclude: PathFilter,
+    /// Files larger than this are refused by `read_file`
+    max_file_bytes: u64,
+    /// Contents of files read earlier, reused while they are unchanged
+    cache: Option<Arc<ContentCache>>,
 }
 
 impl FileService {
     /// Create a new file service
     pub fn new(base_dir: PathBuf) -> Self {
         debug!("Creating FileService with base directory: {:?}", base_dir);
-        Self { base_dir }
+        Self { base_dir, exclude: PathFilter::default(), max_file_bytes: DEFAULT_MAX_FILE_BYTES, cache: None }
+    }
+
+    /// Hide entries matching the filter from directory listings
+    pub fn with_exclude(mut self, exclude: PathFilter) -> Self {
+        self.exclude = exclude;
+        self
+    }
+
+    /// Refuse to read files larger than `max_file_bytes`
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
+    }
+
+    /// Serve unchanged files from a shared content cache
+    pub fn with_cache(mut self, cache: Arc<ContentCache>) -> Self {
+        self.cache = Some(cache);
+        self
     }
 
     /// List directory contents
     pub fn list_directory(&self, path: &Path) -> Result<Vec<DirEntry>, WikiError> {
+        self.read_directory(path, |_, entry| entry)
+    }
+
+    /// List directory contents along with each entry's size and modification time
+    ///
+    /// Sizes and times come from one `metadata` call per entry and describe a
+    /// symlink itself rather than its target. An entry whose metadata cannot be
+    /// read is listed with a size of zero and the Unix epoch as its time.
+    pub fn list_directory_with_meta(&self, path: &Path) -> Result<Vec<DirEntryMeta>, WikiError> {
+        self.read_directory(path, |dir_entry, entry| match dir_entry.metadata() {
+            Ok(meta) => DirEntryMeta {
+                size: meta.len(),
+                modified: meta.modified().unwrap_or(UNIX_EPOCH),
+                entry,
+            },
+            Err(e) => {
+                warn!("Failed to read metadata for {:?}: {}", entry.path, e);
+                DirEntryMeta { entry, size: 0, modified: UNIX_EPOCH }
+            }
+        })
+    }
+
+    /// Read a directory, building one item per entry that is not excluded
+    fn read_directory<T>(
+        &self,
+        path: &Path,
+        mut build: impl FnMut(&fs::DirEntry, DirEntry) -> T,
+    ) -> Result<Vec<T>, WikiError> {
         let full_path = self.base_dir.join(path);
         debug!("Listing directory: {:?} (full path: {:?})", path, full_path);
         
@@ -52,13 +207,18 @@ impl FileService {
                         path.join(&name)
                     };
                     
+                    if self.exclude.is_excluded(&entry_path) {
+                        debug!("Skipping excluded entry: {:?}", entry_path);
+                        continue;
+                    }
+                    
                     debug!("Found entry: {} (is_dir: {})", name, is_dir);
                     
-                    result.push(DirEntry {
+                    result.push(build(&entry, DirEntry {
                         name,
                         is_dir,
                         path: entry_path,
-                    });
+                    }));
                 }
                 Err(e) => {
                     warn!("Failed to read directory entry: {}", e);
@@ -70,7 +230,49 @@ impl FileService {
         Ok(result)
     }
 
-    /// Read file content
+    /// Recursively list every entry below a directory, depth-first
+    ///
+    /// Dotfiles are skipped and each directory's entries are sorted by name.
+    /// Symlinked directories are followed, but a directory reached a second
+    /// time (such as through a symlink cycle) is not descended into again.
+    pub fn walk(&self, path: &Path) -> Result<Vec<DirEntry>, WikiError> {
+        let mut visited = HashSet::new();
+        let mut result = Vec::new();
+        self.walk_into(path, &mut visited, &mut result)?;
+        info!("Walked {:?}, found {} entries", path, result.len());
+        Ok(result)
+    }
+
+    fn walk_into(
+        &self,
+        path: &Path,
+        visited: &mut HashSet<PathBuf>,
+        result: &mut Vec<DirEntry>,
+    ) -> Result<(), WikiError> {
+        if !visited.insert(self.canonical_path(path)?) {
+            warn!("Skipping already visited directory: {:?}", path);
+            return Ok(());
+        }
+
+        let mut entries = self.list_directory(path)?;
+        entries.retain(|entry| !entry.name.starts_with('.'));
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for mut entry in entries {
+            // Directory entries report symlinks as files; follow them here
+            entry.is_dir = entry.is_dir || self.dir_exists(&entry.path);
+            let sub_path = entry.is_dir.then(|| entry.path.clone());
+            result.push(entry);
+            if let Some(sub_path) = sub_path
+                && let Err(e) = self.walk_into(&sub_path, visited, result)
+            {
+                warn!("Failed to walk directory {:?}: {:?}", sub_path, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read file content, refusing files over the size limit
     pub fn read_file(&self, path: &Path) -> Result<String, WikiError> {
         let full_path = self.base_dir.join(path);
         debug!("Reading file: {:?} (full path: {:?})", path, full_path);
@@ -84,12 +286,31 @@ impl FileService {
             warn!("Path is not a file: {:?}", full_path);
             return Err(WikiError::InvalidPath);
         }
+
+        let metadata = self.get_metadata(path)?;
+        let size = metadata.len();
+        if size > self.max_file_bytes {
+            warn!("File {:?} is {} bytes, over the {} byte limit", full_path, size, self.max_file_bytes);
+            return Err(WikiError::FileTooLarge(size));
+        }
+
+        let modified = metadata.modified().ok();
+        if let (Some(cache), Some(modified)) = (&self.cache, modified)
+            && let Some(content) = cache.get(path, modified, size)
+        {
+            debug!("Read file {:?} from content cache", path);
+            return Ok(content);
+        }
         
         let content = fs::read_to_string(&full_path)
             .map_err(|e| {
                 error!("Failed to read file {:?}: {}", full_path, e);
                 WikiError::Io(e)
             })?;
+
+        if let (Some(cache), Some(modified)) = (&self.cache, modified) {
+            cache.insert(path, modified, size, &content);
+        }
         
         info!("Read file {:?}, {} bytes", path, content.len());
         Ok(content)
@@ -103,6 +324,32 @@ impl FileService {
         exists
     }
 
+    /// Check if directory exists, following symlinks
+    pub fn dir_exists(&self, path: &Path) -> bool {
+        self.base_dir.join(path).is_dir()
+    }
+
+    /// Whether a server path such as `/guide/intro` names a page, directory or file in the wiki
+    ///
+    /// Pages may be linked with or without their `.md` extension. Paths that
+    /// climb out of the wiki or are excluded do not exist.
+    pub fn link_target_exists(&self, url_path: &str) -> bool {
+        let normalized = normalize_path(url_path);
+        if normalized.is_empty() {
+            return true;
+        }
+        let path = Path::new(&normalized);
+        if ensure_safe_path(&normalized).is_err() || self.exclude.is_excluded(path) {
+            return false;
+        }
+        self.dir_exists(path) || self.file_exists(path) || self.file_exists(Path::new(&format!("{}.md", normalized)))
+    }
+
+    /// Resolve a path below the base to its canonical absolute form, following symlinks
+    pub fn canonical_path(&self, path: &Path) -> Result<PathBuf, WikiError> {
+        Ok(fs::canonicalize(self.base_dir.join(path))?)
+    }
+
     /// Get file metadata
     pub fn get_metadata(&self, path: &Path) -> Result<fs::Metadata, WikiError> {
         let full_path = self.base_dir.join(path);
@@ -117,7 +364,96 @@ impl FileService {
         Ok(metadata)
     }
 
-    /// Determine content type for a file
+    /// Write file content atomically, creating parent directories as needed
+    pub fn write_file(&self, path: &Path, contents: &str) -> Result<(), WikiError> {
+        let full_path = self.confined_path(path)?;
+        debug!("Writing file: {:?} (full path: {:?})", path, full_path);
+
+        if full_path.is_dir() {
+            warn!("Refusing to write over directory: {:?}", full_path);
+            return Err(WikiError::InvalidPath);
+        }
+
+        let parent = full_path.parent().ok_or(WikiError::InvalidPath)?;
+        // Check the deepest existing ancestor before creating anything beneath it
+        if let Some(existing) = parent.ancestors().find(|dir| dir.exists()) {
+            self.ensure_within_base(existing)?;
+        }
+        fs::create_dir_all(parent).map_err(|e| {
+            error!("Failed to create directory {:?}: {}", parent, e);
+            WikiError::Io(e)
+        })?;
+        self.ensure_within_base(parent)?;
+
+        // Write to a sibling temp file, then rename over the target
+        let file_name = full_path.file_name().ok_or(WikiError::InvalidPath)?;
+        let temp_path = parent.join(format!(
+            ".{}.tmp-{}",
+            file_name.to_string_lossy(),
+            std::process::id()
+        ));
+        if let Err(e) = fs::write(&temp_path, contents).and_then(|_| fs::rename(&temp_path, &full_path)) {
+            error!("Failed to write file {:?}: {}", full_path, e);
+            let _ = fs::remove_file(&temp_path);
+            return Err(WikiError::Io(e));
+        }
+
+        info!("Wrote file {:?}, {} bytes", path, contents.len());
+        Ok(())
+    }
+
+    /// Delete a file
+    pub fn delete_file(&self, path: &Path) -> Result<(), WikiError> {
+        let full_path = self.confined_path(path)?;
+        debug!("Deleting file: {:?} (full path: {:?})", path, full_path);
+
+        if !full_path.exists() {
+            warn!("File does not exist: {:?}", full_path);
+            return Err(WikiError::NotFound);
+        }
+
+        if !full_path.is_file() {
+            warn!("Path is not a file: {:?}", full_path);
+            return Err(WikiError::InvalidPath);
+        }
+
+        if let Some(parent) = full_path.parent() {
+            self.ensure_within_base(parent)?;
+        }
+
+        fs::remove_file(&full_path).map_err(|e| {
+            error!("Failed to delete file {:?}: {}", full_path, e);
+            WikiError::Io(e)
+        })?;
+
+        info!("Deleted file {:?}", path);
+        Ok(())
+    }
+
+    /// Join a relative path onto the base, rejecting anything that could leave it
+    fn confined_path(&self, path: &Path) -> Result<PathBuf, WikiError> {
+        let is_plain = path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if !is_plain || path.file_name().is_none() {
+            warn!("Rejected path outside base directory: {:?}", path);
+            return Err(WikiError::InvalidPath);
+        }
+        Ok(self.base_dir.join(path))
+    }
+
+    /// Check that an existing directory resolves inside the base, following symlinks
+    fn ensure_within_base(&self, dir: &Path) -> Result<(), WikiError> {
+        let base = fs::canonicalize(&self.base_dir)?;
+        let resolved = fs::canonicalize(dir)?;
+        if !resolved.starts_with(&base) {
+            warn!("Directory {:?} resolves outside base directory", dir);
+            return Err(WikiError::InvalidPath);
+        }
+        Ok(())
+    }
+
+    /// Determine content type for a file from its extension, ignoring case
+    ///
+    /// Text types declare UTF-8; unknown extensions are `application/octet-stream`.
     pub fn content_type_for(&self, path: &Path) -> String {
         let extension = path.extension()
             .and_then(|ext| ext.to_str())
@@ -125,17 +461,29 @@ impl FileService {
             .to_lowercase();
         
         let content_type = match extension.as_str() {
-            "html" | "htm" => "text/html",
-            "css" => "text/css",
+            "html" | "htm" => "text/html; charset=utf-8",
+            "css" => "text/css; charset=utf-8",
+            "txt" => "text/plain; charset=utf-8",
+            "md" => "text/markdown; charset=utf-8",
+            "csv" => "text/csv; charset=utf-8",
             "js" => "application/javascript",
             "json" => "application/json",
+            "xml" => "application/xml",
+            "pdf" => "application/pdf",
+            "wasm" => "application/wasm",
             "png" => "image/png",
             "jpg" | "jpeg" => "image/jpeg",
             "gif" => "image/gif",
             "svg" => "image/svg+xml",
             "ico" => "image/x-icon",
-            "txt" => "text/plain",
-            "md" => "text/markdown",
+            "webp" => "image/webp",
+            "avif" => "image/avif",
+            "woff" => "font/woff",
+            "woff2" => "font/woff2",
+            "ttf" => "font/ttf",
+            "mp4" => "video/mp4",
+            "webm" => "video/webm",
+            "mp3" => "audio/mpeg",
             _ => "application/octet-stream",
         };
         
@@ -143,3 +491,228 @@ impl FileService {
         content_type.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn service() -> (TempDir, FileService) {
+        let dir = tempfile::tempdir().unwrap();
+        let service = FileService::new(dir.path().to_path_buf());
+        (dir, service)
+    }
+
+    #[test]
+    fn write_creates_parents_and_overwrites() {
+        let (dir, service) = service();
+        service.write_file(Path::new("guide/new.md"), "first").unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("guide/new.md")).unwrap(), "first");
+
+        service.write_file(Path::new("guide/new.md"), "second").unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("guide/new.md")).unwrap(), "second");
+        // The temporary file is renamed away
+        assert_eq!(fs::read_dir(dir.path().join("guide")).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn delete_removes_files_only() {
+        let (dir, service) = service();
+        service.write_file(Path::new("guide/old.md"), "old").unwrap();
+        service.delete_file(Path::new("guide/old.md")).unwrap();
+        assert!(!dir.path().join("guide/old.md").exists());
+
+        assert!(matches!(service.delete_file(Path::new("guide/old.md")), Err(WikiError::NotFound)));
+        assert!(matches!(service.delete_file(Path::new("guide")), Err(WikiError::InvalidPath)));
+        assert!(matches!(service.write_file(Path::new("guide"), "x"), Err(WikiError::InvalidPath)));
+    }
+
+    #[test]
+    fn writes_and_deletes_outside_the_base_are_rejected() {
+        let (dir, service) = service();
+        let outside = dir.path().parent().unwrap().join("strata-escape.md");
+        for path in ["../strata-escape.md", "guide/../../strata-escape.md", outside.to_str().unwrap(), ""] {
+            assert!(matches!(service.write_file(Path::new(path), "x"), Err(WikiError::InvalidPath)), "{path}");
+            assert!(matches!(service.delete_file(Path::new(path)), Err(WikiError::InvalidPath)), "{path}");
+        }
+        assert!(!outside.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directories_cannot_lead_outside_the_base() {
+        let (dir, service) = service();
+        let target = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(target.path(), dir.path().join("link")).unwrap();
+
+        assert!(matches!(service.write_file(Path::new("link/escape.md"), "x"), Err(WikiError::InvalidPath)));
+        assert!(!target.path().join("escape.md").exists());
+    }
+
+    /// Paths of the walked entries, directories marked with a trailing slash
+    fn walked(service: &FileService, path: &str) -> Vec<String> {
+        service
+            .walk(Path::new(path))
+            .unwrap()
+            .into_iter()
+            .map(|entry| {
+                let path = entry.path.to_string_lossy().replace('\\', "/");
+                if entry.is_dir { format!("{path}/") } else { path }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn walk_visits_every_entry_once_depth_first() {
+        let (dir, service) = service();
+        for file in ["b.md", "a/z.md", "a/deep/x.md", "a/deep/deeper/y.md", "c/notes.txt", ".hidden/secret.md", "a/.draft.md"] {
+            service.write_file(Path::new(file), "text").unwrap();
+        }
+        fs::create_dir(dir.path().join("empty")).unwrap();
+
+        assert_eq!(
+            walked(&service, ""),
+            ["a/", "a/deep/", "a/deep/deeper/", "a/deep/deeper/y.md", "a/deep/x.md", "a/z.md", "b.md", "c/", "c/notes.txt", "empty/"]
+        );
+        assert_eq!(walked(&service, "a/deep"), ["a/deep/deeper/", "a/deep/deeper/y.md", "a/deep/x.md"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_does_not_loop_through_symlink_cycles() {
+        let (dir, service) = service();
+        service.write_file(Path::new("guide/intro.md"), "text").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("guide"), dir.path().join("guide/again")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("guide/root")).unwrap();
+
+        assert_eq!(walked(&service, ""), ["guide/", "guide/again/", "guide/intro.md", "guide/root/"]);
+    }
+
+    /// Overwrite a file, keeping its modification time
+    fn rewrite_keeping_mtime(path: &Path, content: &str) {
+        let modified = fs::metadata(path).unwrap().modified().unwrap();
+        fs::write(path, content).unwrap();
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn unchanged_files_are_read_from_the_cache() {
+        let (dir, service) = service();
+        let service = service.with_cache(Arc::new(ContentCache::new(1024)));
+        let path = dir.path().join("page.md");
+        fs::write(&path, "cached").unwrap();
+        assert_eq!(service.read_file(Path::new("page.md")).unwrap(), "cached");
+
+        // Same size and mtime: the disk is not read again
+        rewrite_keeping_mtime(&path, "ondisk");
+        assert_eq!(service.read_file(Path::new("page.md")).unwrap(), "cached");
+
+        // A new mtime busts the entry
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(service.read_file(Path::new("page.md")).unwrap(), "ondisk");
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used_entries_to_stay_in_budget() {
+        let cache = ContentCache::new(10);
+        let now = SystemTime::now();
+        cache.insert(Path::new("a"), now, 4, "aaaa");
+        cache.insert(Path::new("b"), now, 4, "bbbb");
+        assert!(cache.get(Path::new("a"), now, 4).is_some());
+        cache.insert(Path::new("c"), now, 4, "cccc");
+
+        assert_eq!(cache.get(Path::new("a"), now, 4).as_deref(), Some("aaaa"));
+        assert_eq!(cache.get(Path::new("b"), now, 4), None);
+        assert_eq!(cache.get(Path::new("c"), now, 4).as_deref(), Some("cccc"));
+        // Content larger than the whole budget is never stored
+        cache.insert(Path::new("d"), now, 11, "ddddddddddd");
+        assert_eq!(cache.get(Path::new("d"), now, 11), None);
+    }
+
+    #[test]
+    fn reads_without_a_cache_always_hit_the_disk() {
+        let (dir, service) = service();
+        let path = dir.path().join("page.md");
+        fs::write(&path, "first!").unwrap();
+        assert_eq!(service.read_file(Path::new("page.md")).unwrap(), "first!");
+        rewrite_keeping_mtime(&path, "second");
+        assert_eq!(service.read_file(Path::new("page.md")).unwrap(), "second");
+    }
+
+    #[test]
+    fn content_types_by_extension() {
+        let (_dir, service) = service();
+        let cases = [
+            ("photo.webp", "image/webp"),
+            ("PHOTO.WEBP", "image/webp"),
+            ("photo.avif", "image/avif"),
+            ("font.woff2", "font/woff2"),
+            ("font.TTF", "font/ttf"),
+            ("doc.pdf", "application/pdf"),
+            ("clip.mp4", "video/mp4"),
+            ("song.mp3", "audio/mpeg"),
+            ("module.wasm", "application/wasm"),
+            ("feed.xml", "application/xml"),
+            ("data.csv", "text/csv; charset=utf-8"),
+            ("page.md", "text/markdown; charset=utf-8"),
+            ("archive.tar.gz", "application/octet-stream"),
+            ("Makefile", "application/octet-stream"),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(service.content_type_for(Path::new(name)), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn listing_with_meta_reports_sizes_and_mtimes() {
+        let (dir, service) = service();
+        service.write_file(Path::new("docs/short.md"), "abc").unwrap();
+        service.write_file(Path::new("docs/long.md"), &"x".repeat(2048)).unwrap();
+        service.write_file(Path::new("docs/sub/inner.md"), "inner").unwrap();
+        let stamp = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        fs::File::options().write(true).open(dir.path().join("docs/short.md")).unwrap().set_modified(stamp).unwrap();
+
+        let mut entries = service.list_directory_with_meta(Path::new("docs")).unwrap();
+        entries.sort_by(|a, b| a.entry.name.cmp(&b.entry.name));
+        let summary: Vec<(&str, bool, u64)> = entries.iter().map(|meta| (meta.entry.name.as_str(), meta.entry.is_dir, meta.size)).collect();
+        assert_eq!(summary[..2], [("long.md", false, 2048), ("short.md", false, 3)]);
+        assert_eq!(summary[2].0, "sub");
+        assert!(summary[2].1);
+        assert_eq!(entries[1].modified, stamp);
+        assert!(entries[0].modified > stamp);
+        assert_eq!(entries[0].entry.path, Path::new("docs/long.md"));
+    }
+
+    #[test]
+    fn listing_with_meta_matches_the_plain_listing() {
+        let (_dir, service) = service();
+        for file in ["a.md", "b/c.md", "d.txt"] {
+            service.write_file(Path::new(file), "text").unwrap();
+        }
+        let mut plain: Vec<String> = service.list_directory(Path::new("")).unwrap().into_iter().map(|entry| entry.name).collect();
+        let mut with_meta: Vec<String> =
+            service.list_directory_with_meta(Path::new("")).unwrap().into_iter().map(|meta| meta.entry.name).collect();
+        plain.sort();
+        with_meta.sort();
+        assert_eq!(plain, with_meta);
+        assert!(matches!(service.list_directory_with_meta(Path::new("missing")), Err(WikiError::NotFound)));
+    }
+
+    #[test]
+    fn link_targets_are_pages_directories_or_files() {
+        let (_dir, service) = service();
+        service.write_file(Path::new("guide/intro.md"), "# Intro").unwrap();
+        service.write_file(Path::new("guide/diagram.svg"), "<svg/>").unwrap();
+        for target in ["/", "/guide", "/guide/", "/guide/intro", "/guide/intro.md", "/guide/diagram.svg"] {
+            assert!(service.link_target_exists(target), "{target}");
+        }
+        for target in ["/guide/missing", "/guide/diagram", "/../etc/passwd", "/other/"] {
+            assert!(!service.link_target_exists(target), "{target}");
+        }
+    }
+}
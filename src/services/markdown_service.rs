@@ -1,15 +1,212 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path};
 use log::{debug, info};
+use crate::config::MarkdownConfig;
 use crate::errors::WikiError;
 use crate::types::MarkdownResult;
+use crate::utils::{decode_url_path, escape_attr, escape_html, Frontmatter};
+
+/// Elements whose tags are passed through as raw HTML blocks when enabled
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "details", "dialog", "dd", "div", "dl", "dt",
+    "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6",
+    "header", "hr", "li", "main", "nav", "ol", "p", "pre", "section", "summary", "table",
+    "tbody", "td", "tfoot", "th", "thead", "tr", "ul",
+];
+
+/// Longest description, in characters, taken from a page's first paragraph
+const DESCRIPTION_MAX_CHARS: usize = 160;
+
+/// Emoji shortcodes understood when `emoji` is enabled, sorted by name for binary search
+const EMOJI_SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("alarm_clock", "⏰"),
+    ("arrow_down", "⬇️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("art", "🎨"),
+    ("beer", "🍺"),
+    ("bell", "🔔"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("calendar", "📆"),
+    ("chart_with_upwards_trend", "📈"),
+    ("check", "✔️"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("construction", "🚧"),
+    ("crab", "🦀"),
+    ("cry", "😢"),
+    ("email", "📧"),
+    ("exclamation", "❗"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("floppy_disk", "💾"),
+    ("gear", "⚙️"),
+    ("ghost", "👻"),
+    ("globe_with_meridians", "🌐"),
+    ("green_heart", "💚"),
+    ("grey_question", "❔"),
+    ("hammer", "🔨"),
+    ("heart", "❤️"),
+    ("heavy_check_mark", "✔️"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_plus_sign", "➕"),
+    ("hourglass", "⌛"),
+    ("house", "🏠"),
+    ("information_source", "ℹ️"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("laughing", "😆"),
+    ("lightbulb", "💡"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("memo", "📝"),
+    ("muscle", "💪"),
+    ("no_entry", "⛔"),
+    ("octocat", "🐙"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("pencil", "📝"),
+    ("pencil2", "✏️"),
+    ("penguin", "🐧"),
+    ("phone", "📱"),
+    ("pin", "📌"),
+    ("pray", "🙏"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("recycle", "♻️"),
+    ("robot", "🤖"),
+    ("rocket", "🚀"),
+    ("see_no_evil", "🙈"),
+    ("shield", "🛡️"),
+    ("skull", "💀"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("snake", "🐍"),
+    ("snowflake", "❄️"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("stop_sign", "🛑"),
+    ("sunny", "☀️"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("test_tube", "🧪"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("triangular_flag_on_post", "🚩"),
+    ("trophy", "🏆"),
+    ("umbrella", "☂️"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("whale", "🐳"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+];
+
+/// Kind of list opened by a list item marker
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ListKind {
+    Unordered,
+    Ordered,
+}
+
+/// Heading collected while rendering, used to build the TOC
+struct Heading {
+    level: usize,
+    text: String,
+    anchor: String,
+}
+
+/// Destination of a link or image, with its optional tooltip title
+#[derive(Clone)]
+struct LinkTarget {
+    url: String,
+    title: Option<String>,
+}
+
+/// Footnote references seen so far in the document being rendered
+#[derive(Clone, Default)]
+struct FootnoteRefs {
+    /// Normalized ids in order of first reference; a footnote's number is its position + 1
+    order: Vec<String>,
+    /// How many times each id has been referenced
+    counts: HashMap<String, usize>,
+}
 
 /// Service for handling markdown rendering
-pub struct MarkdownService;
+#[derive(Clone)]
+pub struct MarkdownService {
+    /// Link reference definitions of the document being rendered, keyed by normalized id
+    link_refs: HashMap<String, LinkTarget>,
+    /// Footnote definitions of the document being rendered, keyed by normalized id
+    footnote_defs: HashMap<String, String>,
+    /// Footnotes referenced while rendering, numbered as they are first seen
+    footnote_refs: RefCell<FootnoteRefs>,
+    /// Abbreviations of the document being rendered as term and expansion, longest term first
+    abbreviations: Vec<(String, String)>,
+    /// Abbreviations already wrapped while rendering; only the first use of each is
+    abbreviations_used: RefCell<HashSet<String>>,
+    config: MarkdownConfig,
+    /// Directory of the page being rendered, relative to the wiki root, for resolving relative URLs
+    page_dir: Option<String>,
+}
 
 impl MarkdownService {
     /// Create a new markdown service
     pub fn new() -> Self {
         debug!("Creating new MarkdownService");
-        Self
+        Self {
+            link_refs: HashMap::new(),
+            footnote_defs: HashMap::new(),
+            footnote_refs: RefCell::new(FootnoteRefs::default()),
+            abbreviations: Vec::new(),
+            abbreviations_used: RefCell::new(HashSet::new()),
+            config: MarkdownConfig::new(),
+            page_dir: None,
+        }
+    }
+
+    /// Resolve relative link and image URLs against the directory of this page
+    ///
+    /// `path` is the page's file path relative to the wiki root, e.g. `guide/intro.md`.
+    pub fn with_page_path(mut self, path: &Path) -> Self {
+        let dir: Vec<String> = path
+            .parent()
+            .map(|parent| {
+                parent
+                    .components()
+                    .filter_map(|component| match component {
+                        Component::Normal(segment) => Some(segment.to_string_lossy().to_string()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.page_dir = Some(dir.join("/"));
+        self
+    }
+
+    /// Use the given rendering options
+    pub fn with_config(mut self, config: MarkdownConfig) -> Self {
+        self.config = config;
+        self
     }
 
     /// Render markdown with table of contents
@@ -17,8 +214,23 @@ impl MarkdownService {
         debug!("Starting markdown rendering with TOC, content length: {} chars", content.len());
         let start_time = std::time::Instant::now();
         
-        let html = self.basic_markdown_to_html(content)?;
-        let toc = self.generate_toc(content)?;
+        // Reference definitions are document-scoped, so render with a copy that holds them
+        let (body, link_refs) = self.collect_link_references(content);
+        let (body, footnote_defs) = self.collect_footnotes(&body);
+        let mut document = self.clone();
+        document.link_refs = link_refs;
+        document.footnote_defs = footnote_defs;
+        document.footnote_refs = RefCell::new(FootnoteRefs::default());
+        document.abbreviations = self.collect_abbreviations(content);
+        document.abbreviations_used = RefCell::new(HashSet::new());
+        
+        let mut headings = Vec::new();
+        let mut html = document.basic_markdown_to_html(&body, &mut headings)?;
+        html.push_str(&document.render_footnotes());
+        let toc = document.generate_toc(&headings)?;
+        // After rendering, so the summary cannot claim footnote numbers or first abbreviation uses
+        let description = document.extract_description(&body);
+        let word_count = self.word_count(content);
         
         let duration = start_time.elapsed();
         info!("Markdown rendering completed in {:?}ms", duration.as_millis());
@@ -27,11 +239,232 @@ impl MarkdownService {
             html,
             toc,
             title: self.extract_title(content),
+            description,
+            word_count,
+            reading_minutes: self.reading_minutes(word_count),
         })
     }
 
-    /// Extract title from markdown content
-    fn extract_title(&self, content: &str) -> Option<String> {
+    /// Abbreviations from the config and the frontmatter `abbreviations` list
+    ///
+    /// Entries are `TERM=expansion` or `TERM: expansion`; a page's own entries
+    /// override configured ones for the same term.
+    fn collect_abbreviations(&self, content: &str) -> Vec<(String, String)> {
+        let mut abbreviations: Vec<(String, String)> = Vec::new();
+        let from_page = Frontmatter::parse(content).list("abbreviations");
+        for spec in self.config.abbreviations.iter().chain(&from_page) {
+            let Some(split) = spec.find(['=', ':']) else {
+                continue;
+            };
+            let term = spec[..split].trim();
+            let expansion = spec[split + 1..].trim();
+            if term.is_empty() || expansion.is_empty() {
+                continue;
+            }
+            abbreviations.retain(|(existing, _)| existing != term);
+            abbreviations.push((term.to_string(), expansion.to_string()));
+        }
+        abbreviations.sort_by_key(|(term, _)| std::cmp::Reverse(term.chars().count()));
+        abbreviations
+    }
+
+    /// Collect `[id]: url` link reference definitions and strip them from the body
+    fn collect_link_references(&self, content: &str) -> (String, HashMap<String, LinkTarget>) {
+        let mut refs = HashMap::new();
+        let mut body = String::with_capacity(content.len());
+        let mut in_code_block = false;
+        
+        for line in content.lines() {
+            if line.starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            if !in_code_block
+                && let Some((id, target)) = self.parse_link_reference(line)
+            {
+                debug!("Found link reference definition: '{}' -> '{}'", id, target.url);
+                // The first definition of an id wins
+                refs.entry(id).or_insert(target);
+                continue;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+        
+        (body, refs)
+    }
+
+    /// Collect `[^id]: text` footnote definitions and strip them from the body
+    ///
+    /// Lines indented by four spaces or a tab after a definition continue it.
+    fn collect_footnotes(&self, content: &str) -> (String, HashMap<String, String>) {
+        let mut defs: HashMap<String, String> = HashMap::new();
+        let mut body = String::with_capacity(content.len());
+        let mut in_code_block = false;
+        let mut current: Option<String> = None;
+        
+        for line in content.lines() {
+            if line.starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            if in_code_block {
+                current = None;
+            } else if let Some((id, text)) = self.parse_footnote_definition(line) {
+                debug!("Found footnote definition: '{}'", id);
+                // The first definition of an id wins
+                if !defs.contains_key(&id) {
+                    defs.insert(id.clone(), text.to_string());
+                    current = Some(id);
+                } else {
+                    current = None;
+                }
+                continue;
+            } else if let Some(id) = &current
+                && (line.starts_with("    ") || line.starts_with('\t'))
+                && let Some(text) = defs.get_mut(id)
+            {
+                text.push(' ');
+                text.push_str(line.trim());
+                continue;
+            } else {
+                current = None;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+        
+        (body, defs)
+    }
+
+    /// Parse a single `[^id]: text` footnote definition line
+    fn parse_footnote_definition<'a>(&self, line: &'a str) -> Option<(String, &'a str)> {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if indent > 3 {
+            return None;
+        }
+        let rest = line.trim_start().strip_prefix("[^")?;
+        let (id, text) = rest.split_once("]:")?;
+        if id.trim().is_empty() || id.contains(']') {
+            return None;
+        }
+        Some((self.normalize_reference_id(id), text.trim()))
+    }
+
+    /// Parse a footnote reference `[^id]` starting at `i`, if the footnote is defined
+    fn parse_footnote_ref(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        if chars.get(i + 1) != Some(&'^') {
+            return None;
+        }
+        let j = self.find_char(chars, i + 2, ']')?;
+        let id: String = chars[i + 2..j].iter().collect();
+        let id = self.normalize_reference_id(&id);
+        if !self.footnote_defs.contains_key(&id) {
+            return None;
+        }
+
+        let mut refs = self.footnote_refs.borrow_mut();
+        let number = match refs.order.iter().position(|seen| *seen == id) {
+            Some(pos) => pos + 1,
+            None => {
+                refs.order.push(id.clone());
+                refs.order.len()
+            }
+        };
+        let count = refs.counts.entry(id).or_insert(0);
+        *count += 1;
+        let ref_id = if *count == 1 { format!("fnref-{}", number) } else { format!("fnref-{}-{}", number, count) };
+        let html = format!(
+            "<sup class=\"footnote-ref\"><a href=\"#fn-{}\" id=\"{}\">{}</a></sup>",
+            number, ref_id, number
+        );
+        Some((html, j + 1))
+    }
+
+    /// Render the footnotes section for every referenced footnote, with back-links
+    fn render_footnotes(&self) -> String {
+        if self.footnote_refs.borrow().order.is_empty() {
+            return String::new();
+        }
+
+        let mut html = String::from("<section class=\"footnotes\">\n<hr>\n<ol>\n");
+        // Footnote text may reference further footnotes, which extend the list
+        let mut index = 0;
+        loop {
+            let next = self.footnote_refs.borrow().order.get(index).cloned();
+            let Some(id) = next else {
+                break;
+            };
+            let number = index + 1;
+            let text = self.footnote_defs.get(&id).map(String::as_str).unwrap_or("");
+            let content = self.process_inline_markdown(text);
+            let count = self.footnote_refs.borrow().counts.get(&id).copied().unwrap_or(1);
+            let backrefs: Vec<String> = (1..=count)
+                .map(|n| {
+                    let ref_id = if n == 1 { format!("fnref-{}", number) } else { format!("fnref-{}-{}", number, n) };
+                    format!("<a href=\"#{}\" class=\"footnote-backref\">↩</a>", ref_id)
+                })
+                .collect();
+            html.push_str(&format!("<li id=\"fn-{}\">{} {}</li>\n", number, content, backrefs.join(" ")));
+            index += 1;
+        }
+        html.push_str("</ol>\n</section>\n");
+        html
+    }
+
+    /// Parse a single `[id]: url "optional title"` definition line
+    fn parse_link_reference(&self, line: &str) -> Option<(String, LinkTarget)> {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if indent > 3 {
+            return None;
+        }
+        let rest = line.trim().strip_prefix('[')?;
+        let (label, rest) = rest.split_once("]:")?;
+        if label.trim().is_empty() || label.starts_with('^') {
+            return None;
+        }
+        let target = self.parse_link_target(rest);
+        if target.url.is_empty() {
+            return None;
+        }
+        Some((self.normalize_reference_id(label), target))
+    }
+
+    /// Split `url "title"`, `url 'title'` or `<url with spaces> "title"` into its parts
+    fn parse_link_target(&self, raw: &str) -> LinkTarget {
+        let raw = raw.trim();
+        let (url, rest) = if let Some(inner) = raw.strip_prefix('<')
+            && let Some((url, rest)) = inner.split_once('>')
+        {
+            (url, rest)
+        } else {
+            match raw.find(char::is_whitespace) {
+                Some(pos) => (&raw[..pos], &raw[pos..]),
+                None => (raw, ""),
+            }
+        };
+        
+        let rest = rest.trim();
+        let title = ['"', '\'']
+            .iter()
+            .find_map(|&quote| rest.strip_prefix(quote).and_then(|r| r.strip_suffix(quote)))
+            .map(|t| t.to_string());
+        
+        LinkTarget {
+            // Spaces are only possible inside `<...>`; encode them so the href stays one token
+            url: url.replace(' ', "%20"),
+            title,
+        }
+    }
+
+    /// Reference ids match case-insensitively and ignore whitespace differences
+    fn normalize_reference_id(&self, id: &str) -> String {
+        id.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    }
+
+    /// Extract title from markdown content, preferring frontmatter over the first heading
+    pub fn extract_title(&self, content: &str) -> Option<String> {
         debug!("Extracting title from markdown content");
         
         // Look for frontmatter title first
@@ -65,25 +498,221 @@ impl MarkdownService {
         None
     }
 
-    /// Convert basic markdown to HTML
-    fn basic_markdown_to_html(&self, content: &str) -> Result<String, WikiError> {
+    /// Short plain-text summary of a page for link previews
+    ///
+    /// A frontmatter `description` is used as is. Otherwise the first paragraph
+    /// of the body is stripped of markup and footnote references and cut at a
+    /// word boundary. Reference links resolve against the definitions this
+    /// service holds, so pages are described as part of `render_with_toc`.
+    pub fn extract_description(&self, content: &str) -> Option<String> {
+        if let Some(description) = Frontmatter::parse(content).get("description").map(str::trim)
+            && !description.is_empty()
+        {
+            return Some(description.to_string());
+        }
+
+        let mut paragraph: Vec<&str> = Vec::new();
+        let mut fence: Option<&str> = None;
+        let mut in_math = false;
+        for line in Frontmatter::body(content).lines() {
+            let trimmed = line.trim();
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                continue;
+            }
+            if in_math {
+                in_math = !trimmed.ends_with("$$");
+                continue;
+            }
+            let is_fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+            let is_math = self.config.math && trimmed.starts_with("$$");
+            let is_block = trimmed.is_empty()
+                || is_fence
+                || is_math
+                || trimmed.starts_with('#')
+                || trimmed.starts_with('>')
+                || trimmed.starts_with('|')
+                || self.is_html_block_line(line)
+                || self.is_thematic_break(line)
+                || self.list_marker(line, self.line_indent(line).0).is_some();
+            if !is_block {
+                paragraph.push(line);
+            } else if !paragraph.is_empty() {
+                break;
+            } else if is_fence {
+                fence = Some(&trimmed[..3]);
+            } else if is_math {
+                // Same as rendering: a block closes on the line ending in `$$`, which may be its first
+                in_math = !trimmed[2..].trim().ends_with("$$");
+            }
+        }
+        if paragraph.is_empty() {
+            return None;
+        }
+
+        let paragraph: Vec<String> = paragraph.iter().map(|line| self.strip_footnote_refs(line)).collect();
+        let paragraph: Vec<&str> = paragraph.iter().map(String::as_str).collect();
+        let text = self.plain_text(&self.paragraph_inline(&paragraph));
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.chars().count() <= DESCRIPTION_MAX_CHARS {
+            return Some(text).filter(|text| !text.is_empty());
+        }
+        let cut: String = text.chars().take(DESCRIPTION_MAX_CHARS).collect();
+        let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+        Some(format!("{}…", cut.trim_end()))
+    }
+
+    /// Remove `[^id]` references to defined footnotes from a line of text
+    fn strip_footnote_refs(&self, line: &str) -> String {
+        let mut text = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find("[^") {
+            let Some(len) = rest[start..].find(']') else {
+                break;
+            };
+            let id = self.normalize_reference_id(&rest[start + 2..start + len]);
+            let end = start + len + 1;
+            if !self.footnote_defs.contains_key(&id) {
+                text.push_str(&rest[..end]);
+            } else {
+                text.push_str(&rest[..start]);
+            }
+            rest = &rest[end..];
+        }
+        text.push_str(rest);
+        text
+    }
+
+    /// Number of words in the body, skipping frontmatter and fenced code blocks
+    ///
+    /// Runs of punctuation such as list markers or table pipes are not words.
+    pub fn word_count(&self, content: &str) -> usize {
+        let mut fence: Option<&str> = None;
+        let mut count = 0;
+        for line in Frontmatter::body(content).lines() {
+            let trimmed = line.trim();
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                continue;
+            }
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                fence = Some(&trimmed[..3]);
+                continue;
+            }
+            count += trimmed
+                .split_whitespace()
+                .filter(|word| word.chars().any(char::is_alphanumeric))
+                .count();
+        }
+        count
+    }
+
+    /// Minutes needed to read `word_count` words, rounded to the nearest minute
+    pub fn reading_minutes(&self, word_count: usize) -> usize {
+        let words_per_minute = self.config.words_per_minute.max(1);
+        (word_count + words_per_minute / 2) / words_per_minute
+    }
+
+    /// Text of rendered inline HTML with tags removed and entities decoded
+    fn plain_text(&self, html: &str) -> String {
+        let mut text = String::with_capacity(html.len());
+        let mut in_tag = false;
+        for ch in html.chars() {
+            match ch {
+                '<' => in_tag = true,
+                '>' if in_tag => in_tag = false,
+                _ if !in_tag => text.push(ch),
+                _ => {}
+            }
+        }
+        self.unescape_html(&text)
+    }
+
+    /// Undo `escape_html`
+    fn unescape_html(&self, text: &str) -> String {
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .replace("&amp;", "&")
+    }
+
+    /// Internal link targets in rendered HTML, as decoded server paths without query or fragment
+    ///
+    /// Only links starting with a single `/` count; relative links are in that
+    /// form once resolved against the page path. External, protocol-relative
+    /// and fragment-only links are skipped. Each target is listed once.
+    pub fn internal_links(&self, html: &str) -> Vec<String> {
+        let mut links = Vec::new();
+        let mut rest = html;
+        while let Some(start) = rest.find("href=\"") {
+            let after = &rest[start + 6..];
+            let Some(end) = after.find('"') else {
+                break;
+            };
+            let href = self.unescape_html(&after[..end]);
+            rest = &after[end..];
+
+            if !href.starts_with('/') || href.starts_with("//") {
+                continue;
+            }
+            let path_end = href.find(['?', '#']).unwrap_or(href.len());
+            let path = decode_url_path(&href[..path_end]);
+            if !links.contains(&path) {
+                links.push(path);
+            }
+        }
+        links
+    }
+
+    /// Convert basic markdown to HTML, recording headings for the TOC
+    fn basic_markdown_to_html(&self, content: &str, headings: &mut Vec<Heading>) -> Result<String, WikiError> {
         debug!("Converting markdown to HTML");
         
         let mut html = String::new();
         let lines: Vec<&str> = content.lines().collect();
         let mut i = 0;
         let mut in_code_block = false;
+        // Mermaid fences become diagram containers instead of code
+        let mut in_mermaid = false;
+        // Consecutive text lines waiting to be emitted as one paragraph
+        let mut paragraph: Vec<&str> = Vec::new();
+        // Slugs already handed out in this document
+        let mut used_anchors: HashMap<String, usize> = HashMap::new();
 
         // Track nested lists using a stack
-        #[derive(Clone, Copy, PartialEq, Eq)]
-        enum ListKind { Unordered, Ordered }
-        struct ListFrame { kind: ListKind, indent_level: usize }
+        // `content_col` is the column the text of the list's last item starts at; lines reaching it nest inside the item;
+        // `item_open` is set while the list's last <li> is still open, so nested lists go inside it;
+        // `lines` holds the text of the item's current paragraph, wrapped in <p> when the list is loose
+        struct ListFrame<'a> { kind: ListKind, content_col: usize, item_open: bool, loose: bool, lines: Vec<&'a str> }
         let mut list_stack: Vec<ListFrame> = Vec::new();
+        // Set by a blank line inside a list; the next line decides whether the list goes on
+        let mut after_blank = false;
 
+        // Helper to emit the buffered text of a frame's open item
+        let flush_item = |frame: &mut ListFrame<'_>, out: &mut String| {
+            if frame.lines.is_empty() {
+                return;
+            }
+            if frame.loose {
+                self.flush_paragraph(&mut frame.lines, out);
+            } else {
+                out.push_str(&self.paragraph_inline(&frame.lines));
+                frame.lines.clear();
+            }
+        };
         // Helper to close N list levels
         let close_list_levels = |levels: usize, out: &mut String, stack: &mut Vec<ListFrame>| {
             for _ in 0..levels {
-                if let Some(frame) = stack.pop() {
+                if let Some(mut frame) = stack.pop() {
+                    flush_item(&mut frame, out);
+                    if frame.item_open {
+                        out.push_str("</li>\n");
+                    }
                     match frame.kind {
                         ListKind::Unordered => out.push_str("</ul>\n"),
                         ListKind::Ordered => out.push_str("</ol>\n"),
@@ -92,21 +721,22 @@ impl MarkdownService {
             }
         };
         // Helper to open a list of kind at given level
-        let open_list = |kind: ListKind, out: &mut String, stack: &mut Vec<ListFrame>, indent_level: usize| {
+        let open_list = |kind: ListKind, loose: bool, out: &mut String, stack: &mut Vec<ListFrame>, content_col: usize| {
             match kind {
                 ListKind::Unordered => out.push_str("<ul>\n"),
                 ListKind::Ordered => out.push_str("<ol>\n"),
             }
-            stack.push(ListFrame { kind, indent_level });
+            stack.push(ListFrame { kind, content_col, item_open: false, loose, lines: Vec::new() });
         };
         
         while i < lines.len() {
             let line = lines[i];
             
-            if line.starts_with("---") {
-                // Skip frontmatter
+            if i == 0 && line.trim_end() == "---" && lines[1..].iter().any(|line| line.trim_end() == "---") {
+                // Skip frontmatter, only recognized at the very top of the document;
+                // an unclosed `---` is a rule
                 i += 1;
-                while i < lines.len() && !lines[i].starts_with("---") {
+                while i < lines.len() && lines[i].trim_end() != "---" {
                     i += 1;
                 }
                 i += 1;
@@ -115,6 +745,7 @@ impl MarkdownService {
             
             // Code blocks: triple backticks start/end
             if line.starts_with("```") {
+                self.flush_paragraph(&mut paragraph, &mut html);
                 // If we are inside any open lists, close them before code blocks
                 if !list_stack.is_empty() {
                     let levels = list_stack.len();
@@ -123,8 +754,17 @@ impl MarkdownService {
 
                 in_code_block = !in_code_block;
                 if in_code_block {
-                    let lang = line.trim_start_matches("```").trim();
-                    html.push_str(&format!("<pre><code class=\"language-{}\">", lang));
+                    match self.code_language(line.trim_start_matches("```")) {
+                        Some(lang) if lang == "mermaid" => {
+                            in_mermaid = true;
+                            html.push_str("<div class=\"mermaid\">\n");
+                        }
+                        Some(lang) => html.push_str(&format!("<pre><code class=\"language-{}\">", lang)),
+                        None => html.push_str("<pre><code>"),
+                    }
+                } else if in_mermaid {
+                    in_mermaid = false;
+                    html.push_str("</div>\n");
                 } else {
                     html.push_str("</code></pre>\n");
                 }
@@ -133,78 +773,109 @@ impl MarkdownService {
             }
 
             if in_code_block {
+                // Diagram source is escaped too; the client-side renderer reads the decoded text
                 html.push_str(&format!("{}\n", escape_html(line)));
                 i += 1;
                 continue;
             }
 
-            if line.starts_with('#') {
-                // Close any open lists before headers
+            // Block-level HTML is passed through only when enabled, otherwise shown as text
+            if self.is_html_block_line(line) {
+                self.flush_paragraph(&mut paragraph, &mut html);
                 if !list_stack.is_empty() {
                     let levels = list_stack.len();
                     close_list_levels(levels, &mut html, &mut list_stack);
                 }
-                let level = line.chars().take_while(|&c| c == '#').count();
-                let text = line.trim_start_matches('#').trim();
-                if !text.is_empty() {
-                    let anchor = text.to_lowercase()
-                        .chars()
-                        .map(|c| if c.is_alphanumeric() || c == ' ' { c } else { '-' })
-                        .collect::<String>()
-                        .replace(" ", "-");
-                    let processed_text = self.process_inline_markdown(text);
-                    html.push_str(&format!("<h{} id=\"{}\">{}</h{}>\n", level, anchor, processed_text, level));
+                if self.config.allow_raw_html {
+                    html.push_str(&format!("{}\n", line.trim()));
+                } else {
+                    html.push_str(&format!("<p>{}</p>\n", escape_html(line.trim())));
                 }
-            } else {
-                // Compute indentation (tabs count as 1, every 4 spaces as 1)
-                let mut pos = 0usize;
-                let mut tab_count = 0usize;
-                let mut space_count = 0usize;
-                for ch in line.chars() {
-                    match ch {
-                        '\t' => { tab_count += 1; pos += 1; },
-                        ' ' => { space_count += 1; pos += 1; },
-                        _ => break,
-                    }
+                i += 1;
+                continue;
+            }
+
+            if self.is_thematic_break(line) {
+                self.flush_paragraph(&mut paragraph, &mut html);
+                if !list_stack.is_empty() {
+                    let levels = list_stack.len();
+                    close_list_levels(levels, &mut html, &mut list_stack);
+                }
+                html.push_str("<hr>\n");
+                i += 1;
+                continue;
+            }
+
+            // Display math: `$$ ... $$` on one line, or fenced by lines starting and ending with `$$`
+            if self.config.math && line.trim_start().starts_with("$$") {
+                self.flush_paragraph(&mut paragraph, &mut html);
+                if !list_stack.is_empty() {
+                    let levels = list_stack.len();
+                    close_list_levels(levels, &mut html, &mut list_stack);
                 }
-                let indent_level = tab_count + (space_count / 4);
-
-                // Determine if this is a list item
-                let rest = &line[pos..];
-                let mut is_list_item = false;
-                let mut kind: Option<ListKind> = None;
-                let mut content_start = pos;
-
-                // Unordered markers: -, *, + followed by space
-                if rest.starts_with("- ") || rest.starts_with("* ") || rest.starts_with("+ ") {
-                    is_list_item = true;
-                    kind = Some(ListKind::Unordered);
-                    content_start = pos + 2;
+                let first = line.trim()[2..].trim();
+                let mut body: Vec<&str> = Vec::new();
+                if let Some(single) = first.strip_suffix("$$") {
+                    body.push(single.trim());
                 } else {
-                    // Ordered marker: digits + '. '
-                    let mut j = pos;
-                    while j < line.len() {
-                        if let Some(ch) = line[j..].chars().next() {
-                            if ch.is_ascii_digit() { j += ch.len_utf8(); } else { break; }
-                        } else { break; }
+                    if !first.is_empty() {
+                        body.push(first);
                     }
-                    // Need at least one digit, then '.' and space
-                    if j > pos {
-                        let after_digits = &line[j..];
-                        if after_digits.starts_with(". ") {
-                            is_list_item = true;
-                            kind = Some(ListKind::Ordered);
-                            content_start = j + 2;
+                    i += 1;
+                    while i < lines.len() {
+                        let trimmed = lines[i].trim();
+                        if let Some(last) = trimmed.strip_suffix("$$") {
+                            if !last.trim().is_empty() {
+                                body.push(last.trim());
+                            }
+                            break;
                         }
+                        body.push(lines[i]);
+                        i += 1;
                     }
                 }
+                html.push_str(&format!("<div class=\"math block\">{}</div>\n", escape_html(&body.join("\n"))));
+                i += 1;
+                continue;
+            }
+
+            let heading_level = line.chars().take_while(|&c| c == '#').count();
+            if (1..=6).contains(&heading_level) {
+                self.flush_paragraph(&mut paragraph, &mut html);
+                // Close any open lists before headers
+                if !list_stack.is_empty() {
+                    let levels = list_stack.len();
+                    close_list_levels(levels, &mut html, &mut list_stack);
+                }
+                let level = heading_level;
+                let text = line.trim_start_matches('#').trim();
+                if !text.is_empty() {
+                    let anchor = self.unique_anchor(&self.slugify(text), &mut used_anchors);
+                    let processed_text = self.process_inline_markdown(text);
+                    // Permalink so readers can copy a link to the section
+                    let hlink = format!(
+                        "<a class=\"hlink\" href=\"#{}\" aria-label=\"Link to section: {}\">#</a>",
+                        anchor, escape_attr(text)
+                    );
+                    html.push_str(&format!("<h{} id=\"{}\">{} {}</h{}>\n", level, anchor, processed_text, hlink, level));
+                    headings.push(Heading {
+                        level,
+                        text: text.to_string(),
+                        anchor,
+                    });
+                }
+            } else {
+                let (pos, column) = self.line_indent(line);
+                let content_cols: Vec<usize> = list_stack.iter().map(|frame| frame.content_col).collect();
 
-                if is_list_item {
-                    let this_kind = kind.unwrap_or(ListKind::Unordered);
+                if let Some((this_kind, content_start)) = self.list_marker(line, pos) {
+                    self.flush_paragraph(&mut paragraph, &mut html);
+                    after_blank = false;
+                    let content_col = column + content_start - pos;
 
-                    // Adjust stack according to indent level and kind
+                    // Adjust stack according to indentation and kind
                     let current_depth = list_stack.len();
-                    let target_depth = indent_level + 1; // root list has depth 1
+                    let target_depth = self.list_depth(&content_cols, column) + 1; // root list has depth 1
 
                     if target_depth < current_depth {
                         // Close extra levels
@@ -212,22 +883,42 @@ impl MarkdownService {
                         close_list_levels(levels, &mut html, &mut list_stack);
                     }
                     // If same level but kind changed, close one and reopen
-                    if let Some(top) = list_stack.last() {
-                        if top.indent_level + 1 == target_depth && top.kind != this_kind {
-                            close_list_levels(1, &mut html, &mut list_stack);
-                        }
+                    if let Some(top) = list_stack.last()
+                        && list_stack.len() == target_depth
+                        && top.kind != this_kind
+                    {
+                        close_list_levels(1, &mut html, &mut list_stack);
                     }
-                    // Open lists until reaching target depth
+                    // Open lists until reaching target depth, each nested inside an item of its parent
                     while list_stack.len() < target_depth {
-                        let current_len = list_stack.len();
-                        open_list(this_kind, &mut html, &mut list_stack, current_len);
+                        if let Some(parent) = list_stack.last_mut() {
+                            flush_item(parent, &mut html);
+                            if !parent.item_open {
+                                html.push_str("<li>");
+                                parent.item_open = true;
+                            }
+                            if !html.ends_with('\n') {
+                                html.push('\n');
+                            }
+                        }
+                        let outer: Vec<usize> = list_stack.iter().map(|frame| frame.content_col).collect();
+                        let loose = self.list_is_loose(&lines, i, &outer);
+                        open_list(this_kind, loose, &mut html, &mut list_stack, content_col);
                     }
 
-                    // Now add list item
-                    let item_text = &line[content_start..].trim_end();
-                    let processed = self.process_inline_markdown(item_text.trim());
-                    html.push_str(&format!("<li>{}</li>\n", processed));
+                    // Now add list item, left open for continuation lines and nested lists
+                    if let Some(frame) = list_stack.last_mut() {
+                        flush_item(frame, &mut html);
+                        if frame.item_open {
+                            html.push_str("</li>\n");
+                        }
+                        frame.item_open = true;
+                        frame.content_col = content_col;
+                        html.push_str("<li>");
+                        frame.lines.push(&line[content_start..]);
+                    }
                 } else if line.matches('|').count() > 1 {
+                    self.flush_paragraph(&mut paragraph, &mut html);
                     // Close lists before tables
                     if !list_stack.is_empty() {
                         let levels = list_stack.len();
@@ -242,29 +933,45 @@ impl MarkdownService {
                     }
                     continue;
                 } else if line.trim().is_empty() {
-                    // On blank line, close any open lists
-                    if !list_stack.is_empty() {
-                        let levels = list_stack.len();
-                        close_list_levels(levels, &mut html, &mut list_stack);
+                    // Blank line ends the current paragraph, including one inside a list item
+                    self.flush_paragraph(&mut paragraph, &mut html);
+                    if let Some(frame) = list_stack.last_mut() {
+                        flush_item(frame, &mut html);
+                        after_blank = true;
+                    }
+                } else if let Some(frame) = list_stack.last_mut()
+                    && !after_blank
+                {
+                    // Wrapped line continuing the current item
+                    frame.lines.push(line);
+                } else if self.list_depth(&content_cols, column) > 0 {
+                    // Indented paragraph after a blank line belongs to the deepest item whose text it lines up with
+                    let levels = list_stack.len() - self.list_depth(&content_cols, column);
+                    close_list_levels(levels, &mut html, &mut list_stack);
+                    if let Some(frame) = list_stack.last_mut() {
+                        frame.lines.push(line);
                     }
-                    html.push_str("<br>\n");
+                    after_blank = false;
                 } else {
                     // Non-list paragraph; close any open lists first
                     if !list_stack.is_empty() {
                         let levels = list_stack.len();
                         close_list_levels(levels, &mut html, &mut list_stack);
                     }
-                    // Regular paragraph
-                    let processed = self.process_inline_markdown(line);
-                    if !processed.trim().is_empty() {
-                        html.push_str(&format!("<p>{}</p>\n", processed));
-                    }
+                    paragraph.push(line);
                 }
             }
             
             i += 1;
         }
         
+        self.flush_paragraph(&mut paragraph, &mut html);
+
+        // A fence left open at the end of the document still gets closed
+        if in_code_block {
+            html.push_str(if in_mermaid { "</div>\n" } else { "</code></pre>\n" });
+        }
+
         // Close any remaining open lists
         if !list_stack.is_empty() {
             let levels = list_stack.len();
@@ -275,224 +982,672 @@ impl MarkdownService {
         Ok(html)
     }
 
-    /// Process inline markdown elements like links and code
-    fn process_inline_markdown(&self, text: &str) -> String {
-        let mut result = text.to_string();
-        
-        // Process images ![alt](url) first
-        result = self.process_images(&result);
-        
-        // Process links [text](url)
-        result = self.process_links(&result);
-        
-        // Process inline code `code` - handle backticks properly
-        result = self.process_inline_code(&result);
-        
-        // Process strikethrough ~~text~~
-        result = self.replace_emphasis(&result, "~~", "<del>", "</del>");
-        
-        // Process bold italic ***text*** first (before **text**)
-        result = self.replace_emphasis(&result, "***", "<strong><em>", "</em></strong>");
-        
-        // Process bold **text**
-        result = self.replace_emphasis(&result, "**", "<strong>", "</strong>");
-        
-        // Process italic *text* last (after **text**)
-        result = self.replace_emphasis(&result, "*", "<em>", "</em>");
-        
-        result
+    /// Language name for a fence's `language-*` class, as highlight.js expects
+    ///
+    /// Only the first word of the info string is used, and only if it consists
+    /// of ASCII alphanumerics and `-`.
+    fn code_language(&self, info: &str) -> Option<String> {
+        let lang = info.split_whitespace().next()?;
+        if lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            Some(lang.to_string())
+        } else {
+            None
+        }
     }
 
-    /// Helper function to replace emphasis markers
-    fn replace_emphasis(&self, text: &str, marker: &str, open_tag: &str, close_tag: &str) -> String {
-        let mut result = String::new();
-        let mut i = 0;
-        let chars: Vec<char> = text.chars().collect();
-        let marker_chars: Vec<char> = marker.chars().collect();
-        let marker_len = marker_chars.len();
-        
-        while i < chars.len() {
-            if i + marker_len <= chars.len() {
-                let mut is_marker = true;
-                for (k, &marker_char) in marker_chars.iter().enumerate() {
-                    if chars[i + k] != marker_char {
-                        is_marker = false;
-                        break;
-                    }
-                }
-                
-                if is_marker {
-                    // Look for closing marker
-                    let mut j = i + marker_len;
-                    let mut found = false;
-                    while j + marker_len <= chars.len() {
-                        let mut is_closing = true;
-                        for (k, &marker_char) in marker_chars.iter().enumerate() {
-                            if chars[j + k] != marker_char {
-                                is_closing = false;
-                                break;
-                            }
-                        }
-                        
-                        if is_closing {
-                            let content: String = chars[i + marker_len..j].iter().collect();
-                            // Skip if content is empty or contains only whitespace
-                            if !content.trim().is_empty() {
-                                result.push_str(open_tag);
-                                result.push_str(&content);
-                                result.push_str(close_tag);
-                                i = j + marker_len;
-                                found = true;
-                                break;
-                            }
-                        }
-                        j += 1;
-                    }
-                    
-                    if !found {
-                        // No closing marker found, add as regular text
-                        result.push(chars[i]);
-                        i += 1;
-                        continue;
-                    }
-                    continue;
-                }
+    /// Indentation of a line as (byte offset of first non-blank, column)
+    ///
+    /// Tabs advance to the next multiple of four columns.
+    fn line_indent(&self, line: &str) -> (usize, usize) {
+        let mut pos = 0usize;
+        let mut column = 0usize;
+        for ch in line.chars() {
+            match ch {
+                '\t' => column += 4 - column % 4,
+                ' ' => column += 1,
+                _ => break,
             }
-            
-            result.push(chars[i]);
-            i += 1;
+            pos += 1;
         }
-        
-        result
+        (pos, column)
     }
 
-    /// Process images ![alt](url)
-    fn process_images(&self, text: &str) -> String {
-        let mut result = String::new();
-        let mut i = 0;
-        let chars: Vec<char> = text.chars().collect();
-        
-        while i < chars.len() {
-            if i + 1 < chars.len() && chars[i] == '!' && chars[i + 1] == '[' {
-                // Find closing bracket
-                let mut j = i + 2;
-                while j < chars.len() && chars[j] != ']' {
-                    j += 1;
-                }
-                
-                if j < chars.len() && j + 1 < chars.len() && chars[j + 1] == '(' {
-                    let alt_text: String = chars[i + 2..j].iter().collect();
-                    let mut k = j + 2;
-                    while k < chars.len() && chars[k] != ')' {
-                        k += 1;
-                    }
-                    
-                    if k < chars.len() {
-                        let url: String = chars[j + 2..k].iter().collect();
-                        result.push_str(&format!("<img src=\"{}\" alt=\"{}\">", 
-                            escape_attr(&url), escape_attr(&alt_text)));
-                        i = k + 1;
-                        continue;
-                    }
-                }
-            }
-            
-            result.push(chars[i]);
-            i += 1;
-        }
-        
-        result
+    /// Number of open list items a line at `column` is nested in
+    ///
+    /// `content_cols` are the columns the text of the open items starts at,
+    /// outermost first; a line is inside each item whose text it lines up with.
+    fn list_depth(&self, content_cols: &[usize], column: usize) -> usize {
+        content_cols.iter().take_while(|&&col| column >= col).count()
     }
 
-    /// Process links [text](url)
-    fn process_links(&self, text: &str) -> String {
-        let mut result = String::new();
-        let mut i = 0;
-        let chars: Vec<char> = text.chars().collect();
-        
-        while i < chars.len() {
-            if i < chars.len() && chars[i] == '[' {
-                // Find closing bracket
-                let mut j = i + 1;
-                while j < chars.len() && chars[j] != ']' {
-                    j += 1;
-                }
-                
-                if j < chars.len() && j + 1 < chars.len() && chars[j + 1] == '(' {
-                    let link_text: String = chars[i + 1..j].iter().collect();
-                    let mut k = j + 2;
-                    while k < chars.len() && chars[k] != ')' {
-                        k += 1;
-                    }
-                    
-                    if k < chars.len() {
-                        let mut url: String = chars[j + 2..k].iter().collect();
-                        
-                        // Strip .md extension for internal links
-                        if url.ends_with(".md") && !url.starts_with("http") {
-                            url = url[..url.len() - 3].to_string();
-                        }
-                        
-                        result.push_str(&format!("<a href=\"{}\">{}</a>", 
-                            escape_attr(&url), escape_html(&link_text)));
-                        i = k + 1;
-                        continue;
-                    }
-                }
-            }
-            
-            result.push(chars[i]);
-            i += 1;
+    /// Parse a list item marker at `pos`, returning its kind and where the item text starts
+    fn list_marker(&self, line: &str, pos: usize) -> Option<(ListKind, usize)> {
+        let rest = &line[pos..];
+
+        // Unordered markers: -, *, + followed by space
+        if rest.starts_with("- ") || rest.starts_with("* ") || rest.starts_with("+ ") {
+            return Some((ListKind::Unordered, pos + 2));
         }
-        
-        result
+
+        // Ordered marker: at least one digit, then '.' and space
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 && rest[digits..].starts_with(". ") {
+            return Some((ListKind::Ordered, pos + digits + 2));
+        }
+        None
     }
 
-    /// Process inline code `code`
-    fn process_inline_code(&self, text: &str) -> String {
-        let mut result = String::new();
-        let mut i = 0;
-        let chars: Vec<char> = text.chars().collect();
-        
-        while i < chars.len() {
-            if i < chars.len() && chars[i] == '`' {
-                // Find closing backtick
-                let mut j = i + 1;
-                while j < chars.len() && chars[j] != '`' {
-                    j += 1;
-                }
-                
-                if j < chars.len() {
-                    let code_content: String = chars[i + 1..j].iter().collect();
-                    // Skip if content is empty or contains only whitespace
-                    if !code_content.trim().is_empty() {
-                        result.push_str(&format!("<code>{}</code>", escape_html(&code_content)));
-                        i = j + 1;
-                        continue;
-                    }
+    /// Check whether a line starts a block that ends any open list
+    fn is_list_breaking_block(&self, line: &str) -> bool {
+        let heading_level = line.chars().take_while(|&c| c == '#').count();
+        line.starts_with("```")
+            || self.is_html_block_line(line)
+            || self.is_thematic_break(line)
+            || (self.config.math && line.trim_start().starts_with("$$"))
+            || (1..=6).contains(&heading_level)
+    }
+
+    /// Check whether the list whose first item is on line `start` is loose
+    ///
+    /// `outer` holds the content columns of the items the list is nested in,
+    /// outermost first. A list is loose when a blank line separates two of its
+    /// items, or an item from an indented paragraph continuing it; a blank line
+    /// before a list of the other kind does not count. Items of a loose list
+    /// wrap their text in `<p>`.
+    fn list_is_loose(&self, lines: &[&str], start: usize, outer: &[usize]) -> bool {
+        let level = outer.len();
+        let mut content_cols = outer.to_vec();
+        let (pos, column) = self.line_indent(lines[start]);
+        let Some((kind, content_start)) = self.list_marker(lines[start], pos) else {
+            return false;
+        };
+        content_cols.push(column + content_start - pos);
+        let mut saw_blank = false;
+        for line in &lines[start + 1..] {
+            if line.trim().is_empty() {
+                saw_blank = true;
+                continue;
+            }
+            if self.is_list_breaking_block(line) {
+                return false;
+            }
+            let (pos, column) = self.line_indent(line);
+            let marker = self.list_marker(line, pos);
+            if marker.is_none() && line.matches('|').count() > 1 {
+                return false;
+            }
+            if marker.is_none() && !saw_blank {
+                // Wrapped line of the previous item
+                continue;
+            }
+            // Level of the list this line belongs to: an item starts or continues
+            // the list inside the items it reaches, other lines continue the deepest of them
+            let depth = self.list_depth(&content_cols, column);
+            let owner = match marker {
+                Some(_) => depth,
+                None if depth > 0 => depth - 1,
+                None => return false,
+            };
+            // An item of the other kind starts a new list
+            if owner < level || (owner == level && marker.is_some_and(|(other, _)| other != kind)) {
+                return false;
+            }
+            if owner == level && saw_blank {
+                return true;
+            }
+            match marker {
+                Some((_, content_start)) => {
+                    content_cols.truncate(owner);
+                    content_cols.push(column + content_start - pos);
                 }
+                None => content_cols.truncate(owner + 1),
+            }
+            saw_blank = false;
+        }
+        false
+    }
+
+    /// Emit buffered text lines as a single paragraph
+    fn flush_paragraph(&self, lines: &mut Vec<&str>, html: &mut String) {
+        if lines.is_empty() {
+            return;
+        }
+
+        let processed = self.paragraph_inline(lines);
+        lines.clear();
+
+        if !processed.trim().is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", processed));
+        }
+    }
+
+    /// Render the inline content of a paragraph's lines
+    ///
+    /// Lines are joined with a space, except after a line ending in two or
+    /// more spaces, which becomes an explicit `<br>`.
+    fn paragraph_inline(&self, lines: &[&str]) -> String {
+        let mut segments: Vec<String> = Vec::new();
+        let mut current = String::new();
+        for (idx, line) in lines.iter().enumerate() {
+            if !current.is_empty() {
+                current.push(' ');
             }
+            current.push_str(line.trim());
+            let is_last = idx + 1 == lines.len();
+            if !is_last && line.ends_with("  ") {
+                segments.push(self.process_inline_markdown(&current));
+                current.clear();
+            }
+        }
+        segments.push(self.process_inline_markdown(&current));
+        segments.join("<br>\n")
+    }
+
+    /// Check whether a line opens or closes a block-level HTML element, or is a comment
+    fn is_html_block_line(&self, line: &str) -> bool {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("<!--") {
+            return true;
+        }
+        let Some(tag) = trimmed.strip_prefix("</").or_else(|| trimmed.strip_prefix('<')) else {
+            return false;
+        };
+        let name: String = tag
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let terminated = matches!(tag[name.len()..].chars().next(), Some(' ' | '>' | '/' | '\t') | None);
+        terminated && HTML_BLOCK_TAGS.contains(&name.as_str())
+    }
+
+    /// Check whether a line is a thematic break (`---`, `***`, `___`)
+    fn is_thematic_break(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        let Some(marker) = trimmed.chars().next() else {
+            return false;
+        };
+        if !matches!(marker, '-' | '*' | '_') {
+            return false;
+        }
+        let mut count = 0;
+        for ch in trimmed.chars() {
+            if ch == marker {
+                count += 1;
+            } else if ch != ' ' && ch != '\t' {
+                return false;
+            }
+        }
+        count >= 3
+    }
+
+    /// Process inline markdown elements like links, code and emphasis
+    ///
+    /// Runs a single left-to-right scan: each construct is recognized at its
+    /// opening marker and emitted once, so generated tags are never rescanned.
+    fn process_inline_markdown(&self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        self.render_inline(&chars, false)
+    }
+
+    /// Render a run of inline content
+    ///
+    /// Inside link text (`in_link`) nested links and images are not recognized
+    /// and plain text is escaped. Elsewhere `<` and `>` are escaped unless raw
+    /// HTML is allowed.
+    fn render_inline(&self, chars: &[char], in_link: bool) -> String {
+        let mut result = String::with_capacity(chars.len());
+        let mut i = 0;
+        // Inside an inline HTML tag, where quotes delimit attribute values
+        let mut in_tag = false;
+        
+        while i < chars.len() {
+            let parsed = match chars[i] {
+                '`' => self.parse_code_span(chars, i),
+                '\\' if self.config.math && chars.get(i + 1) == Some(&'$') => Some(("$".to_string(), i + 2)),
+                '$' if self.config.math => self.parse_math(chars, i),
+                '!' if !in_link => self.parse_image(chars, i),
+                '[' if !in_link => self.parse_footnote_ref(chars, i)
+                    .or_else(|| self.parse_wiki_link(chars, i))
+                    .or_else(|| self.parse_link(chars, i)),
+                '*' | '~' => self.parse_emphasis(chars, i, in_link),
+                'h' | 'w' if !in_link => self.parse_autolink(chars, i),
+                '"' | '\'' | '-' | '.' if self.config.smart_punctuation && !in_tag => self.parse_smart_punctuation(chars, i),
+                ':' if self.config.emoji && !in_tag => self.parse_emoji_shortcode(chars, i),
+                _ => None,
+            };
+            let parsed = parsed.or_else(|| {
+                (!in_link && !in_tag && !self.abbreviations.is_empty())
+                    .then(|| self.parse_abbreviation(chars, i))
+                    .flatten()
+            });
             
-            result.push(chars[i]);
-            i += 1;
+            match parsed {
+                Some((html, next)) => {
+                    result.push_str(&html);
+                    i = next;
+                }
+                None => {
+                    match chars[i] {
+                        '<' => {
+                            in_tag = self.config.allow_raw_html
+                                && chars.get(i + 1).is_some_and(|c| c.is_ascii_alphabetic() || *c == '/')
+                        }
+                        '>' => in_tag = false,
+                        _ => {}
+                    }
+                    // Raw tags in text are markup only when raw HTML is allowed
+                    let escape = in_link || (!self.config.allow_raw_html && matches!(chars[i], '<' | '>'));
+                    if escape {
+                        result.push_str(&escape_html(chars[i].encode_utf8(&mut [0; 4])));
+                    } else {
+                        result.push(chars[i]);
+                    }
+                    i += 1;
+                }
+            }
         }
         
         result
     }
 
+    /// Replace a straight quote, `--`, `---` or `...` at `i` with its typographic form
+    ///
+    /// A quote opens at the start of the text or after whitespace, an opening
+    /// bracket, a tag or a dash, and closes otherwise, so apostrophes come out right.
+    /// Longer runs of dashes or dots are left alone.
+    fn parse_smart_punctuation(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        let marker = chars[i];
+        let opens = || i == 0 || matches!(chars[i - 1], c if c.is_whitespace() || "([{<>-–—".contains(c));
+        let run = chars[i..].iter().take_while(|&&c| c == marker).count();
+        let replacement = match (marker, run) {
+            ('"', _) => if opens() { '“' } else { '”' },
+            ('\'', _) => if opens() { '‘' } else { '’' },
+            ('-', 2) => '–',
+            ('-', 3) => '—',
+            ('.', 3) => '…',
+            ('-' | '.', 1) => return None,
+            _ => return Some((chars[i..i + run].iter().collect(), i + run)),
+        };
+        let len = if matches!(marker, '"' | '\'') { 1 } else { run };
+        Some((replacement.to_string(), i + len))
+    }
+
+    /// Parse an emoji shortcode like `:rocket:` starting at `i`
+    ///
+    /// Names hold lowercase letters, digits, `_`, `+` and `-`. Unknown names are
+    /// left for the text.
+    fn parse_emoji_shortcode(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        let len = chars[i + 1..]
+            .iter()
+            .take_while(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '+' | '-'))
+            .count();
+        if len == 0 || chars.get(i + 1 + len) != Some(&':') {
+            return None;
+        }
+        let name: String = chars[i + 1..i + 1 + len].iter().collect();
+        let idx = EMOJI_SHORTCODES.binary_search_by(|(code, _)| code.cmp(&name.as_str())).ok()?;
+        Some((EMOJI_SHORTCODES[idx].1.to_string(), i + len + 2))
+    }
+
+    /// Wrap the first use of a defined abbreviation at `i` in `<abbr>`
+    ///
+    /// Terms match whole words only, case-sensitively.
+    fn parse_abbreviation(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+        if i > 0 && is_word(&chars[i - 1]) {
+            return None;
+        }
+        let (term, expansion) = self.abbreviations.iter().find(|(term, _)| {
+            let len = term.chars().count();
+            chars.len() >= i + len
+                && chars[i..i + len].iter().copied().eq(term.chars())
+                && !chars.get(i + len).is_some_and(is_word)
+        })?;
+        if !self.abbreviations_used.borrow_mut().insert(term.clone()) {
+            return None;
+        }
+        let html = format!("<abbr title=\"{}\">{}</abbr>", escape_attr(expansion), escape_html(term));
+        Some((html, i + term.chars().count()))
+    }
+
+    /// Find the first `target` character at or after `from`
+    fn find_char(&self, chars: &[char], from: usize, target: char) -> Option<usize> {
+        (from..chars.len()).find(|&j| chars[j] == target)
+    }
+
+    /// Find the first `target` character at or after `from` that is not inside a code span
+    fn find_char_outside_code(&self, chars: &[char], from: usize, target: char) -> Option<usize> {
+        let mut j = from;
+        while j < chars.len() {
+            if chars[j] == target {
+                return Some(j);
+            }
+            j = match chars[j] {
+                '`' => self.code_span_bounds(chars, j).map_or(j + self.backtick_run(chars, j), |(_, _, end)| end),
+                _ => j + 1,
+            };
+        }
+        None
+    }
+
+    /// Length of the run of backticks starting at `i`
+    fn backtick_run(&self, chars: &[char], i: usize) -> usize {
+        chars[i..].iter().take_while(|&&c| c == '`').count()
+    }
+
+    /// Bounds of a code span opening at `i`: content start, content end and the index after it
+    ///
+    /// The span closes at the next backtick run of the same length as the
+    /// opening run, so ``` `` a ` b `` ``` holds a literal backtick.
+    fn code_span_bounds(&self, chars: &[char], i: usize) -> Option<(usize, usize, usize)> {
+        let len = self.backtick_run(chars, i);
+        let mut j = i + len;
+        while j < chars.len() {
+            if chars[j] != '`' {
+                j += 1;
+                continue;
+            }
+            let run = self.backtick_run(chars, j);
+            if run == len {
+                return Some((i + len, j, j + run));
+            }
+            j += run;
+        }
+        None
+    }
+
+    /// Parse a code span starting at `i`, delimited by equal runs of backticks
+    ///
+    /// The content is escaped and never scanned for other inline markup. One
+    /// space on each side is dropped when both are present, so a span can
+    /// start or end with a backtick. An unmatched run of backticks is literal.
+    fn parse_code_span(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        let len = self.backtick_run(chars, i);
+        let literal = || Some((chars[i..i + len].iter().collect::<String>(), i + len));
+        let Some((start, end, next)) = self.code_span_bounds(chars, i) else {
+            return literal();
+        };
+        let code_content: String = chars[start..end].iter().collect();
+        // Skip if content is empty or contains only whitespace
+        if code_content.trim().is_empty() {
+            return literal();
+        }
+        let code_content = match code_content.strip_prefix(' ').and_then(|inner| inner.strip_suffix(' ')) {
+            Some(inner) => inner,
+            None => code_content.as_str(),
+        };
+        Some((format!("<code>{}</code>", escape_html(code_content)), next))
+    }
+
+    /// Parse inline math `$...$` starting at `i`, escaped for the client typesetter to read back
+    ///
+    /// The content may not start or end with whitespace, so prices like `$5 and $10`
+    /// stay text.
+    fn parse_math(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        let j = (i + 1..chars.len()).find(|&j| chars[j] == '$' && chars[j - 1] != '\\')?;
+        let content: String = chars[i + 1..j].iter().collect();
+        if content.is_empty() || content.starts_with(char::is_whitespace) || content.ends_with(char::is_whitespace) {
+            return None;
+        }
+        Some((format!("<span class=\"math inline\">{}</span>", escape_html(&content)), j + 1))
+    }
+
+    /// Link a bare `http://`, `https://` or `www.` URL starting at `i`
+    ///
+    /// The URL must start a word and runs to the next whitespace or `<`; trailing
+    /// punctuation and unbalanced closing parentheses are left as text.
+    fn parse_autolink(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        if i > 0 && !(chars[i - 1].is_whitespace() || chars[i - 1] == '(') {
+            return None;
+        }
+        let rest: String = chars[i..chars.len().min(i + 8)].iter().collect();
+        let prefix_len = ["https://", "http://", "www."]
+            .iter()
+            .find(|prefix| rest.starts_with(*prefix))?
+            .len();
+
+        let mut end = (i..chars.len())
+            .find(|&j| chars[j].is_whitespace() || chars[j] == '<')
+            .unwrap_or(chars.len());
+        while end > i + prefix_len {
+            let last = chars[end - 1];
+            let unbalanced_paren = last == ')'
+                && chars[i..end].iter().filter(|&&c| c == ')').count()
+                    > chars[i..end].iter().filter(|&&c| c == '(').count();
+            if matches!(last, '.' | ',' | ':' | ';' | '!' | '?' | '\'' | '"' | '*' | '_' | '~') || unbalanced_paren {
+                end -= 1;
+            } else {
+                break;
+            }
+        }
+        if end == i + prefix_len {
+            return None;
+        }
+
+        let url: String = chars[i..end].iter().collect();
+        let href = if url.starts_with("www.") { format!("http://{}", url) } else { url.clone() };
+        Some((format!("<a href=\"{}\">{}</a>", escape_attr(&href), escape_html(&url)), end))
+    }
+
+    /// Parse an image ![alt](url "title") starting at `i`
+    fn parse_image(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        if chars.get(i + 1) != Some(&'[') {
+            return None;
+        }
+        let j = self.find_char_outside_code(chars, i + 2, ']')?;
+        if chars.get(j + 1) != Some(&'(') {
+            return None;
+        }
+        let k = self.find_char(chars, j + 2, ')')?;
+        
+        let alt_text: String = chars[i + 2..j].iter().collect();
+        let raw: String = chars[j + 2..k].iter().collect();
+        let target = self.parse_link_target(&raw);
+        let html = format!("<img src=\"{}\" alt=\"{}\"{}>", 
+            escape_attr(&self.resolve_url(&target.url)), escape_attr(&alt_text), self.title_attr(&target));
+        Some((html, k + 1))
+    }
+
+    /// Parse a wiki link [[Target]] or [[Target|Label]] starting at `i`
+    fn parse_wiki_link(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        if chars.get(i + 1) != Some(&'[') {
+            return None;
+        }
+        let j = (i + 2..chars.len().saturating_sub(1))
+            .find(|&j| chars[j] == ']' && chars[j + 1] == ']')?;
+        
+        let inner: String = chars[i + 2..j].iter().collect();
+        let (target, label) = match inner.split_once('|') {
+            Some((target, label)) => (target.trim(), label.trim()),
+            None => (inner.trim(), inner.trim()),
+        };
+        if target.is_empty() {
+            return None;
+        }
+        let html = format!("<a href=\"{}\" class=\"wikilink\">{}</a>",
+            escape_attr(&self.wiki_link_href(target)), escape_html(label));
+        Some((html, j + 2))
+    }
+
+    /// Turn a wiki link target like `Guide/Getting Started` into `/guide/getting-started`
+    fn wiki_link_href(&self, target: &str) -> String {
+        let segments: Vec<String> = target
+            .split('/')
+            .map(|segment| {
+                segment.trim()
+                    .to_lowercase()
+                    .chars()
+                    .filter_map(|c| {
+                        if c.is_alphanumeric() || c == '-' || c == '_' {
+                            Some(c)
+                        } else if c.is_whitespace() {
+                            Some('-')
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<String>()
+            })
+            // Segments that slugify to nothing, such as `..`, would leave `//` in the URL
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        format!("/{}", segments.join("/"))
+    }
+
+    /// Parse a link [text](url), [text][id], [text][] or shortcut [id] starting at `i`
+    fn parse_link(&self, chars: &[char], i: usize) -> Option<(String, usize)> {
+        let j = self.find_char_outside_code(chars, i + 1, ']')?;
+        let label = &chars[i + 1..j];
+        let link_text: String = label.iter().collect();
+        
+        // Inline [text](url "title")
+        if chars.get(j + 1) == Some(&'(')
+            && let Some(k) = self.find_char(chars, j + 2, ')')
+        {
+            let raw: String = chars[j + 2..k].iter().collect();
+            return Some((self.link_html(&self.parse_link_target(&raw), label), k + 1));
+        }
+        
+        // Full [text][id] or collapsed [text][] reference
+        if chars.get(j + 1) == Some(&'[')
+            && let Some(k) = self.find_char(chars, j + 2, ']')
+        {
+            let id: String = chars[j + 2..k].iter().collect();
+            let id = if id.trim().is_empty() { link_text.clone() } else { id };
+            if let Some(target) = self.link_refs.get(&self.normalize_reference_id(&id)) {
+                return Some((self.link_html(target, label), k + 1));
+            }
+        }
+        
+        // Shortcut [id] reference
+        let target = self.link_refs.get(&self.normalize_reference_id(&link_text))?;
+        Some((self.link_html(target, label), j + 1))
+    }
+
+    /// Build an anchor element for a link target, rendering the label as inline content
+    fn link_html(&self, target: &LinkTarget, label: &[char]) -> String {
+        let mut url = target.url.clone();
+        
+        // Strip .md extension for internal links, keeping any query or fragment
+        let path_end = url.find(['?', '#']).unwrap_or(url.len());
+        if url[..path_end].ends_with(".md") && !url.starts_with("http") {
+            url.replace_range(path_end - 3..path_end, "");
+        }
+        
+        let url = self.resolve_url(&url);
+        format!("<a href=\"{}\"{}>{}</a>", escape_attr(&url), self.title_attr(target), self.render_inline(label, true))
+    }
+
+    /// Turn a relative URL into an absolute server path under the page's directory
+    ///
+    /// URLs with a scheme, absolute paths, and fragment or query-only URLs are
+    /// returned unchanged, as is everything when no page path is set.
+    fn resolve_url(&self, url: &str) -> String {
+        let Some(page_dir) = &self.page_dir else {
+            return url.to_string();
+        };
+        let path_end = url.find(['?', '#']).unwrap_or(url.len());
+        let has_scheme = url[..path_end]
+            .split_once(':')
+            .is_some_and(|(scheme, _)| !scheme.contains('/'));
+        if url.is_empty() || url.starts_with(['/', '#', '?']) || has_scheme {
+            return url.to_string();
+        }
+
+        let mut segments: Vec<&str> = page_dir.split('/').filter(|s| !s.is_empty()).collect();
+        for segment in url[..path_end].split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    segments.pop();
+                }
+                _ => segments.push(segment),
+            }
+        }
+        let trailing_slash = url[..path_end].ends_with('/') && !segments.is_empty();
+        format!(
+            "/{}{}{}",
+            segments.join("/"),
+            if trailing_slash { "/" } else { "" },
+            &url[path_end..]
+        )
+    }
+
+    /// Render the ` title="..."` attribute for a link target, if it has one
+    fn title_attr(&self, target: &LinkTarget) -> String {
+        match &target.title {
+            Some(title) => format!(" title=\"{}\"", escape_attr(title)),
+            None => String::new(),
+        }
+    }
+
+    /// Parse emphasis (`*`, `**`, `***`) or strikethrough (`~~`) starting at `i`
+    ///
+    /// The closing marker must be a run of exactly the same length, so `*a **b** c*`
+    /// nests instead of closing early. Code spans are skipped while searching.
+    fn parse_emphasis(&self, chars: &[char], i: usize, in_link: bool) -> Option<(String, usize)> {
+        let marker = chars[i];
+        let run_len = |from: usize| chars[from..].iter().take_while(|&&c| c == marker).count();
+        let len = run_len(i);
+        
+        let (open_tag, close_tag) = match (marker, len) {
+            ('~', 2) => ("<del>", "</del>"),
+            ('*', 1) => ("<em>", "</em>"),
+            ('*', 2) => ("<strong>", "</strong>"),
+            ('*', 3) => ("<strong><em>", "</em></strong>"),
+            // Runs with no matching emphasis form are kept literally
+            _ => return Some((chars[i..i + len].iter().collect(), i + len)),
+        };
+        
+        let mut j = i + len;
+        while j < chars.len() {
+            if chars[j] == '`' {
+                j = self.code_span_bounds(chars, j).map_or(j + self.backtick_run(chars, j), |(_, _, end)| end);
+                continue;
+            }
+            if chars[j] == marker {
+                let closing_len = run_len(j);
+                let content = &chars[i + len..j];
+                if closing_len == len && content.iter().any(|c| !c.is_whitespace()) {
+                    let inner = self.render_inline(content, in_link);
+                    return Some((format!("{}{}{}", open_tag, inner, close_tag), j + len));
+                }
+                j += closing_len;
+                continue;
+            }
+            j += 1;
+        }
+        
+        None
+    }
+
     /// Render table from markdown
     fn render_table(&self, lines: &[&str], start_idx: usize) -> Result<String, WikiError> {
         let mut html = String::new();
         html.push_str("<table>\n<thead>\n<tr>\n");
         
+        // Column alignment comes from the separator row, when there is one
+        let alignments: Vec<Option<&str>> = lines
+            .get(start_idx + 1)
+            .map(|line| self.split_table_row(line))
+            .filter(|cells| self.is_separator_row(cells))
+            .map(|cells| cells.iter().map(|cell| self.cell_alignment(cell)).collect())
+            .unwrap_or_default();
+        let align_attr = |col: usize| -> String {
+            match alignments.get(col).copied().flatten() {
+                Some(align) => format!(" style=\"text-align:{}\"", align),
+                None => String::new(),
+            }
+        };
+        
         // Parse header
         if start_idx < lines.len() {
             let header_line = lines[start_idx];
-            let cells: Vec<&str> = header_line.split('|').collect();
-            for cell in cells.iter().skip(1).take(cells.len().saturating_sub(2)) {
+            for (col, cell) in self.split_table_row(header_line).iter().enumerate() {
                 let cell_content = cell.trim();
                 if !cell_content.is_empty() {
-                    html.push_str(&format!("<th>{}</th>\n", escape_html(cell_content)));
+                    html.push_str(&format!("<th{}>{}</th>\n", align_attr(col), self.process_inline_markdown(cell_content)));
                 }
             }
         }
@@ -503,15 +1658,14 @@ impl MarkdownService {
         let mut i = start_idx + 2; // Skip header and separator
         while i < lines.len() && lines[i].contains('|') {
             let row_line = lines[i];
-            let cells: Vec<&str> = row_line.split('|').collect();
             
             html.push_str("<tr>\n");
-            for cell in cells.iter().skip(1).take(cells.len().saturating_sub(2)) {
+            for (col, cell) in self.split_table_row(row_line).iter().enumerate() {
                 let cell_content = cell.trim();
                 if !cell_content.is_empty() {
-                    html.push_str(&format!("<td>{}</td>\n", escape_html(cell_content)));
+                    html.push_str(&format!("<td{}>{}</td>\n", align_attr(col), self.process_inline_markdown(cell_content)));
                 } else {
-                    html.push_str("<td></td>\n");
+                    html.push_str(&format!("<td{}></td>\n", align_attr(col)));
                 }
             }
             html.push_str("</tr>\n");
@@ -522,60 +1676,115 @@ impl MarkdownService {
         Ok(html)
     }
 
-    /// Generate table of contents
-    fn generate_toc(&self, content: &str) -> Result<String, WikiError> {
+    /// Split a table row into cells, dropping the outer pipes
+    ///
+    /// An escaped `\|` stays inside the cell as a literal pipe.
+    fn split_table_row(&self, line: &str) -> Vec<String> {
+        let mut cells = Vec::new();
+        let mut current = String::new();
+        let mut chars = line.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' if chars.peek() == Some(&'|') => {
+                    current.push('|');
+                    chars.next();
+                }
+                '|' => cells.push(std::mem::take(&mut current)),
+                _ => current.push(ch),
+            }
+        }
+        cells.push(current);
+        let len = cells.len();
+        cells.into_iter().skip(1).take(len.saturating_sub(2)).collect()
+    }
+
+    /// Check whether every cell looks like `---`, `:--`, `--:` or `:-:`
+    fn is_separator_row(&self, cells: &[String]) -> bool {
+        !cells.is_empty() && cells.iter().all(|cell| {
+            let cell = cell.trim();
+            cell.contains('-') && cell.chars().all(|c| c == '-' || c == ':')
+        })
+    }
+
+    /// Alignment requested by a separator cell, if any
+    fn cell_alignment(&self, cell: &str) -> Option<&'static str> {
+        let cell = cell.trim();
+        match (cell.starts_with(':'), cell.ends_with(':')) {
+            (true, true) => Some("center"),
+            (true, false) => Some("left"),
+            (false, true) => Some("right"),
+            (false, false) => None,
+        }
+    }
+
+    /// Convert heading text into an anchor slug
+    fn slugify(&self, text: &str) -> String {
+        text.to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == ' ' { c } else { '-' })
+            .collect::<String>()
+            .replace(" ", "-")
+    }
+
+    /// Make a slug unique within the document by appending `-1`, `-2`, ...
+    fn unique_anchor(&self, slug: &str, used: &mut HashMap<String, usize>) -> String {
+        let mut count = used.get(slug).copied().unwrap_or(0);
+        let mut candidate = slug.to_string();
+        while used.contains_key(&candidate) {
+            count += 1;
+            candidate = format!("{}-{}", slug, count);
+        }
+        used.insert(slug.to_string(), count);
+        used.entry(candidate.clone()).or_insert(0);
+        candidate
+    }
+
+    /// Generate table of contents from the headings collected while rendering
+    fn generate_toc(&self, items: &[Heading]) -> Result<String, WikiError> {
         debug!("Generating table of contents");
         
         let mut toc = String::new();
-        let mut items = Vec::new();
-        let lines: Vec<&str> = content.lines().collect();
-        let mut i = 0;
-        let mut in_code_block = false;
+        let min_level = self.config.toc_min_level.clamp(1, 6);
+        let max_level = self.config.toc_max_level.clamp(min_level, 6);
+        let items: Vec<&Heading> = items
+            .iter()
+            .filter(|heading| (min_level..=max_level).contains(&heading.level))
+            .collect();
         
-        while i < lines.len() {
-            let line = lines[i];
-            
-            // Check for code block boundaries
-            if line.starts_with("```") {
-                in_code_block = !in_code_block;
-                i += 1;
-                continue;
-            }
-            
-            // Skip processing if we're inside a code block
-            if in_code_block {
-                i += 1;
-                continue;
-            }
-            
-            // Process headers only when not in code blocks
-            if line.starts_with('#') {
-                let level = line.chars().take_while(|&c| c == '#').count();
-                if level <= 6 { // Support H1-H6
-                    let text = line.trim_start_matches('#').trim();
-                    if !text.is_empty() {
-                        let anchor = text.to_lowercase()
-                            .chars()
-                            .map(|c| if c.is_alphanumeric() || c == ' ' { c } else { '-' })
-                            .collect::<String>()
-                            .replace(" ", "-");
-                        
-                        items.push((level, text, anchor));
+        // Levels of the lists currently open, outermost first; each has an open <li>
+        let mut open_levels: Vec<usize> = Vec::new();
+        for Heading { level, text, anchor } in items.iter().copied() {
+            let level = *level;
+            while let Some(&top) = open_levels.last()
+                && top > level
+            {
+                // Between the enclosing list's level and this one: continue this list
+                let parent = open_levels.len().checked_sub(2).map(|i| open_levels[i]);
+                if parent.is_none_or(|parent| parent < level) {
+                    if let Some(top) = open_levels.last_mut() {
+                        *top = level;
                     }
+                    break;
                 }
+                toc.push_str("</li>\n</ul>\n");
+                open_levels.pop();
             }
-            
-            i += 1;
-        }
-        
-        if !items.is_empty() {
-            toc.push_str("<ul class=\"toc\">\n");
-            for (level, text, anchor) in &items {
-                let indent = "  ".repeat(level - 1);
-                toc.push_str(&format!("{}<li><a href=\"#{}\">{}</a></li>\n", 
-                    indent, anchor, escape_html(text)));
+            match open_levels.last() {
+                Some(&top) if top == level => toc.push_str("</li>\n"),
+                // Deeper, possibly skipping levels: nest one list inside the open item
+                Some(_) => {
+                    toc.push_str("\n<ul>\n");
+                    open_levels.push(level);
+                }
+                None => {
+                    toc.push_str("<ul class=\"toc\">\n");
+                    open_levels.push(level);
+                }
             }
-            toc.push_str("</ul>\n");
+            toc.push_str(&format!("<li><a href=\"#{}\">{}</a>", anchor, escape_html(text)));
+        }
+        for _ in open_levels {
+            toc.push_str("</li>\n</ul>\n");
         }
         
         debug!("Generated TOC with {} items", items.len());
@@ -583,20 +1792,717 @@ impl MarkdownService {
     }
 }
 
-/// Escape HTML special characters
-fn escape_html(text: &str) -> String {
-    text.replace("&", "&amp;")
-        .replace("<", "&lt;")
-        .replace(">", "&gt;")
-        .replace("\"", "&quot;")
-        .replace("'", "&#39;")
+impl Default for MarkdownService {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-/// Escape HTML attribute values
-fn escape_attr(text: &str) -> String {
-    text.replace("&", "&amp;")
-        .replace("<", "&lt;")
-        .replace(">", "&gt;")
-        .replace("\"", "&quot;")
-        .replace("'", "&#39;")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(markdown: &str) -> String {
+        MarkdownService::new().render_with_toc(markdown).unwrap().html
+    }
+
+    #[test]
+    fn thematic_breaks_render_as_rules() {
+        assert_eq!(render("---"), "<hr>\n");
+        assert_eq!(render("****"), "<hr>\n");
+        assert_eq!(render("___"), "<hr>\n");
+    }
+
+    #[test]
+    fn rule_after_a_paragraph() {
+        assert_eq!(render("Intro\n\n---\n\nMore"), "<p>Intro</p>\n<hr>\n<p>More</p>\n");
+    }
+
+    #[test]
+    fn frontmatter_is_not_a_rule() {
+        assert_eq!(render("---\ntitle: Page\n---\nBody"), "<p>Body</p>\n");
+    }
+
+    #[test]
+    fn consecutive_lines_form_one_paragraph() {
+        assert_eq!(render("one\ntwo\nthree"), "<p>one two three</p>\n");
+    }
+
+    #[test]
+    fn trailing_double_space_is_a_hard_break() {
+        assert_eq!(render("first  \nsecond"), "<p>first<br>\nsecond</p>\n");
+    }
+
+    /// Values of `attr="..."` in `html`, in order
+    fn attr_values(html: &str, attr: &str) -> Vec<String> {
+        let needle = format!("{}=\"", attr);
+        html.match_indices(&needle)
+            .map(|(start, _)| {
+                let rest = &html[start + needle.len()..];
+                rest[..rest.find('"').unwrap()].to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_headings_get_unique_ids_matching_the_toc() {
+        let result = MarkdownService::new().render_with_toc("# Setup\n\n## Setup\n\n# Setup").unwrap();
+        let ids = attr_values(&result.html, "id");
+        assert_eq!(ids, ["setup", "setup-1", "setup-2"]);
+        let toc_targets: Vec<String> = attr_values(&result.toc, "href")
+            .into_iter()
+            .map(|href| href.trim_start_matches('#').to_string())
+            .collect();
+        assert_eq!(toc_targets, ids);
+    }
+
+    #[test]
+    fn table_alignment_applies_to_header_and_body_cells() {
+        let html = render("| L | C | R |\n|:--|:-:|--:|\n| a | b | c |");
+        for tag in ["th", "td"] {
+            assert!(html.contains(&format!("<{tag} style=\"text-align:left\">")), "{html}");
+            assert!(html.contains(&format!("<{tag} style=\"text-align:center\">")), "{html}");
+            assert!(html.contains(&format!("<{tag} style=\"text-align:right\">")), "{html}");
+        }
+    }
+
+    #[test]
+    fn table_cells_render_inline_markdown() {
+        let html = render("| a | b |\n|---|---|\n| **bold** | [link](/page) |");
+        assert!(html.contains("<td><strong>bold</strong></td>"), "{html}");
+        assert!(html.contains("<td><a href=\"/page\">link</a></td>"), "{html}");
+    }
+
+    #[test]
+    fn table_cell_markup_is_escaped() {
+        let html = render("| a | b |\n|---|---|\n| <img src=x onerror=alert(1)> | `<b>` and **<i>x</i>** |");
+        assert!(html.contains("<td>&lt;img src=x onerror=alert(1)&gt;</td>"), "{html}");
+        assert!(html.contains("<td><code>&lt;b&gt;</code> and <strong>&lt;i&gt;x&lt;/i&gt;</strong></td>"), "{html}");
+        assert!(!html.contains("<img"));
+    }
+
+    #[test]
+    fn reference_links_resolve_against_definitions() {
+        let html = render("[full][docs] and [docs] and [missing]\n\n[docs]: /guide \"Guide\"");
+        assert_eq!(
+            html,
+            "<p><a href=\"/guide\" title=\"Guide\">full</a> and <a href=\"/guide\" title=\"Guide\">docs</a> and [missing]</p>\n"
+        );
+    }
+
+    #[test]
+    fn link_and_image_titles() {
+        assert_eq!(render("[docs](/guide \"The guide\")"), "<p><a href=\"/guide\" title=\"The guide\">docs</a></p>\n");
+        assert_eq!(render("[docs](/guide 'Single')"), "<p><a href=\"/guide\" title=\"Single\">docs</a></p>\n");
+        assert_eq!(
+            render("![logo](/logo.png \"Our <logo>\")"),
+            "<p><img src=\"/logo.png\" alt=\"logo\" title=\"Our &lt;logo&gt;\"></p>\n"
+        );
+    }
+
+    #[test]
+    fn angle_bracket_urls_may_contain_spaces() {
+        assert_eq!(
+            render("[notes](<my notes.md> \"Notes\")"),
+            "<p><a href=\"my%20notes\" title=\"Notes\">notes</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn wiki_links() {
+        assert_eq!(
+            render("[[Getting Started]]"),
+            "<p><a href=\"/getting-started\" class=\"wikilink\">Getting Started</a></p>\n"
+        );
+        assert_eq!(
+            render("[[guide/intro|Intro]]"),
+            "<p><a href=\"/guide/intro\" class=\"wikilink\">Intro</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn wiki_links_inside_code_stay_literal() {
+        assert_eq!(render("`[[Getting Started]]`"), "<p><code>[[Getting Started]]</code></p>\n");
+    }
+
+    #[test]
+    fn wiki_link_targets_never_become_protocol_relative() {
+        assert!(render("[[../etc]]").contains("href=\"/etc\""));
+        assert!(render("[[//evil.example]]").contains("href=\"/evilexample\""));
+    }
+
+    /// A paragraph using every inline construct and its expected rendering
+    const INLINE_SAMPLE: (&str, &str) = (
+        "Some **bold**, *em*, ***both***, ~~gone~~, `co*de*`, [link](/a_b*c*) and ![img](/i*m*g.png).",
+        "<p>Some <strong>bold</strong>, <em>em</em>, <strong><em>both</em></strong>, <del>gone</del>, \
+         <code>co*de*</code>, <a href=\"/a_b*c*\">link</a> and <img src=\"/i*m*g.png\" alt=\"img\">.</p>\n",
+    );
+
+    #[test]
+    fn inline_constructs_render_in_one_pass() {
+        let (markdown, expected) = INLINE_SAMPLE;
+        assert_eq!(render(markdown), expected);
+        assert_eq!(render("[*x*](/p) and **[y](/q)**"), "<p><a href=\"/p\"><em>x</em></a> and <strong><a href=\"/q\">y</a></strong></p>\n");
+    }
+
+    #[test]
+    fn large_document_renders_each_paragraph_identically() {
+        let (markdown, expected) = INLINE_SAMPLE;
+        let document = vec![markdown; 2000].join("\n\n");
+        assert_eq!(render(&document), expected.repeat(2000));
+    }
+
+    #[test]
+    fn headings_render_with_ids_and_anchor_links() {
+        assert_eq!(
+            render("# Title\n\n## Getting started\n\n### Deep `code`\n"),
+            "<h1 id=\"title\">Title <a class=\"hlink\" href=\"#title\" aria-label=\"Link to section: Title\">#</a></h1>\n\
+             <h2 id=\"getting-started\">Getting started <a class=\"hlink\" href=\"#getting-started\" aria-label=\"Link to section: Getting started\">#</a></h2>\n\
+             <h3 id=\"deep--code-\">Deep <code>code</code> <a class=\"hlink\" href=\"#deep--code-\" aria-label=\"Link to section: Deep `code`\">#</a></h3>\n"
+        );
+    }
+
+    #[test]
+    fn tables_render_head_and_body() {
+        assert_eq!(
+            render("| Name | Value |\n|------|------:|\n| a | 1 |\n| b | 2 |\n"),
+            "<table>\n<thead>\n<tr>\n<th>Name</th>\n<th style=\"text-align:right\">Value</th>\n</tr>\n</thead>\n\
+             <tbody>\n<tr>\n<td>a</td>\n<td style=\"text-align:right\">1</td>\n</tr>\n\
+             <tr>\n<td>b</td>\n<td style=\"text-align:right\">2</td>\n</tr>\n</tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn lists_render_unordered_and_ordered() {
+        assert_eq!(render("- one\n- two\n"), "<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n");
+        assert_eq!(render("1. first\n2. second\n"), "<ol>\n<li>first</li>\n<li>second</li>\n</ol>\n");
+    }
+
+    #[test]
+    fn code_blocks_are_escaped_with_a_language_class() {
+        assert_eq!(
+            render("```rust\nfn main() { println!(\"<hi>\"); }\n```\n"),
+            "<pre><code class=\"language-rust\">fn main() { println!(&quot;&lt;hi&gt;&quot;); }\n</code></pre>\n"
+        );
+        assert_eq!(render("```\na && b\n```\n"), "<pre><code>a &amp;&amp; b\n</code></pre>\n");
+    }
+
+    #[test]
+    fn odd_language_strings_never_reach_the_class() {
+        assert_eq!(render("```c++ \"><script>\nx\n```\n"), "<pre><code>x\n</code></pre>\n");
+        assert_eq!(render("```\"onclick=alert(1)\nx\n```\n"), "<pre><code>x\n</code></pre>\n");
+        assert_eq!(
+            render("```objective-c extra words\nx\n```\n"),
+            "<pre><code class=\"language-objective-c\">x\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn unclosed_code_block_is_closed_at_the_end() {
+        let html = render("# T\n\n```rust\nlet a = 1;\n\nmore <b>\n");
+        assert!(html.ends_with("<pre><code class=\"language-rust\">let a = 1;\n\nmore &lt;b&gt;\n</code></pre>\n"), "{html}");
+        assert_eq!(html.matches("<pre>").count(), html.matches("</pre>").count());
+    }
+
+    /// Render with a customized markdown configuration
+    fn render_with(config: MarkdownConfig, md: &str) -> String {
+        MarkdownService::new().with_config(config).render_with_toc(md).unwrap().html
+    }
+
+    #[test]
+    fn raw_html_blocks_pass_through_when_allowed() {
+        let md = "<div class=\"note\">\nCareful *here*\n</div>\n\nAfter <b>inline</b>\n";
+        let html = render_with(MarkdownConfig { allow_raw_html: true, ..MarkdownConfig::new() }, md);
+        assert_eq!(html, "<div class=\"note\">\n<p>Careful <em>here</em></p>\n</div>\n<p>After <b>inline</b></p>\n");
+    }
+
+    #[test]
+    fn inline_html_is_escaped_by_default() {
+        assert_eq!(render("<script>alert(1)</script>\n"), "<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>\n");
+        assert_eq!(
+            render("See <iframe src=\"https://evil.example\"></iframe> and <style>p{}</style>\n"),
+            "<p>See &lt;iframe src=\"https://evil.example\"&gt;&lt;/iframe&gt; and &lt;style&gt;p{}&lt;/style&gt;</p>\n"
+        );
+        assert_eq!(render("- <b onclick=x>item</b>\n"), "<ul>\n<li>&lt;b onclick=x&gt;item&lt;/b&gt;</li>\n</ul>\n");
+    }
+
+    #[test]
+    fn inline_html_passes_through_when_allowed() {
+        let raw = |md| render_with(MarkdownConfig { allow_raw_html: true, ..MarkdownConfig::new() }, md);
+        assert_eq!(raw("<script>alert(1)</script>\n"), "<p><script>alert(1)</script></p>\n");
+        assert_eq!(
+            raw("See <iframe src=\"https://example.com\"></iframe>\n"),
+            "<p>See <iframe src=\"https://example.com\"></iframe></p>\n"
+        );
+    }
+
+    #[test]
+    fn raw_html_is_escaped_by_default() {
+        let html = render("<div class=\"note\">\nCareful\n</div>\n");
+        assert!(!html.contains("<div"), "{html}");
+        assert!(html.contains("&lt;div class=&quot;note&quot;&gt;"));
+    }
+
+    #[test]
+    fn mermaid_blocks_become_escaped_diagram_containers() {
+        assert_eq!(
+            render("```mermaid\ngraph TD\n  A[\"<b>a</b>\"] --> B & C\n```\n"),
+            "<div class=\"mermaid\">\ngraph TD\n  A[&quot;&lt;b&gt;a&lt;/b&gt;&quot;] --&gt; B &amp; C\n</div>\n"
+        );
+        assert!(!render("```mermaid\n</div><script>alert(1)</script>\n```\n").contains("<script>"));
+    }
+
+    #[test]
+    fn other_languages_stay_code_blocks() {
+        assert_eq!(
+            render("```rust\nlet a = b < c;\n```\n"),
+            "<pre><code class=\"language-rust\">let a = b &lt; c;\n</code></pre>\n"
+        );
+    }
+
+    fn render_math(md: &str) -> String {
+        render_with(MarkdownConfig { math: true, ..MarkdownConfig::new() }, md)
+    }
+
+    #[test]
+    fn inline_math_is_wrapped_and_escaped() {
+        assert_eq!(
+            render_math("Since $a<b$ and $x & y$ hold\n"),
+            "<p>Since <span class=\"math inline\">a&lt;b</span> and <span class=\"math inline\">x &amp; y</span> hold</p>\n"
+        );
+        assert_eq!(render_math("From $5 to $10\n"), "<p>From $5 to $10</p>\n");
+    }
+
+    #[test]
+    fn display_math_blocks_are_escaped() {
+        assert_eq!(render_math("$$ a < b $$\n"), "<div class=\"math block\">a &lt; b</div>\n");
+        assert_eq!(
+            render_math("$$\n\\sum_{i<n} i\n= x\n$$\n"),
+            "<div class=\"math block\">\\sum_{i&lt;n} i\n= x</div>\n"
+        );
+    }
+
+    #[test]
+    fn escaped_dollar_is_literal() {
+        assert_eq!(render_math("Costs \\$x$ total\n"), "<p>Costs $x$ total</p>\n");
+    }
+
+    #[test]
+    fn math_is_off_by_default() {
+        assert_eq!(render("Inline $a+b$\n"), "<p>Inline $a+b$</p>\n");
+        assert!(!render("$$\nx\n$$\n").contains("math"));
+    }
+
+    #[test]
+    fn bare_urls_become_links() {
+        assert_eq!(
+            render("See https://example.com/a?b=1 for more\n"),
+            "<p>See <a href=\"https://example.com/a?b=1\">https://example.com/a?b=1</a> for more</p>\n"
+        );
+        assert_eq!(
+            render("Or www.example.org today\n"),
+            "<p>Or <a href=\"http://www.example.org\">www.example.org</a> today</p>\n"
+        );
+    }
+
+    #[test]
+    fn trailing_punctuation_is_not_part_of_a_bare_url() {
+        assert_eq!(
+            render("Visit https://example.com.\n"),
+            "<p>Visit <a href=\"https://example.com\">https://example.com</a>.</p>\n"
+        );
+        assert_eq!(
+            render("(see https://example.com/x), then\n"),
+            "<p>(see <a href=\"https://example.com/x\">https://example.com/x</a>), then</p>\n"
+        );
+    }
+
+    #[test]
+    fn urls_in_code_spans_and_links_are_not_linked_again() {
+        assert_eq!(render("Use `https://example.com` here\n"), "<p>Use <code>https://example.com</code> here</p>\n");
+        assert_eq!(
+            render("[site](https://example.com)\n"),
+            "<p><a href=\"https://example.com\">site</a></p>\n"
+        );
+        assert_eq!(
+            render("[https://example.com](https://example.com)\n"),
+            "<p><a href=\"https://example.com\">https://example.com</a></p>\n"
+        );
+    }
+
+    fn render_at(path: &str, md: &str) -> String {
+        MarkdownService::new().with_page_path(Path::new(path)).render_with_toc(md).unwrap().html
+    }
+
+    #[test]
+    fn relative_urls_resolve_against_the_page_directory() {
+        assert_eq!(render_at("guide/intro.md", "![](diagram.png)\n"), "<p><img src=\"/guide/diagram.png\" alt=\"\"></p>\n");
+        assert_eq!(
+            render_at("guide/intro.md", "![x](../img/a.png) [b](sub/b)\n"),
+            "<p><img src=\"/img/a.png\" alt=\"x\"> <a href=\"/guide/sub/b\">b</a></p>\n"
+        );
+        assert_eq!(render_at("intro.md", "![](diagram.png)\n"), "<p><img src=\"/diagram.png\" alt=\"\"></p>\n");
+    }
+
+    #[test]
+    fn absolute_and_external_urls_are_untouched() {
+        assert_eq!(
+            render_at("guide/intro.md", "![](https://cdn.example.com/a.png) [c](/abs) [h](#top)\n"),
+            "<p><img src=\"https://cdn.example.com/a.png\" alt=\"\"> <a href=\"/abs\">c</a> <a href=\"#top\">h</a></p>\n"
+        );
+        assert_eq!(render("![](diagram.png)\n"), "<p><img src=\"diagram.png\" alt=\"\"></p>\n");
+    }
+
+    #[test]
+    fn footnote_links_to_a_definition_with_a_back_link() {
+        assert_eq!(
+            render("Text[^1] more.\n\n[^1]: The note.\n"),
+            "<p>Text<sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1\">1</a></sup> more.</p>\n\
+             <section class=\"footnotes\">\n<hr>\n<ol>\n\
+             <li id=\"fn-1\">The note. <a href=\"#fnref-1\" class=\"footnote-backref\">↩</a></li>\n\
+             </ol>\n</section>\n"
+        );
+    }
+
+    #[test]
+    fn footnotes_are_numbered_by_first_reference() {
+        let html = render("A[^b] B[^a] C[^b]\n\n[^a]: Alpha\n[^b]: Beta\n");
+        assert_eq!(attr_values(&html, "href")[..3], ["#fn-1", "#fn-2", "#fn-1"]);
+        assert!(html.contains("<li id=\"fn-1\">Beta <a href=\"#fnref-1\" class=\"footnote-backref\">↩</a> <a href=\"#fnref-1-2\" class=\"footnote-backref\">↩</a></li>"));
+        assert!(html.contains("<li id=\"fn-2\">Alpha "));
+        assert_eq!(html.matches("<li id=").count(), 2);
+    }
+
+    #[test]
+    fn undefined_footnote_references_stay_literal() {
+        assert_eq!(render("Missing[^nope] here\n"), "<p>Missing[^nope] here</p>\n");
+    }
+
+    #[test]
+    fn every_heading_gets_a_permalink_to_its_id() {
+        let html = render("# One\n\n## Two & more\n\ntext\n\n### One\n");
+        let ids = attr_values(&html, "id");
+        assert_eq!(ids, ["one", "two---more", "one-1"]);
+        let hlinks: Vec<String> = html
+            .match_indices("<a class=\"hlink\" href=\"#")
+            .map(|(start, _)| {
+                let rest = &html[start + "<a class=\"hlink\" href=\"#".len()..];
+                rest[..rest.find('"').unwrap()].to_string()
+            })
+            .collect();
+        assert_eq!(hlinks, ids);
+        assert!(html.contains("<a class=\"hlink\" href=\"#two---more\" aria-label=\"Link to section: Two &amp; more\">#</a></h2>"), "{html}");
+    }
+
+    fn toc_with(config: MarkdownConfig, md: &str) -> String {
+        MarkdownService::new().with_config(config).render_with_toc(md).unwrap().toc
+    }
+
+    const TOC_LEVELS: &str = "# Title\n\n## Setup\n\n### Install\n\n#### Linux\n\n##### Deep\n\n###### Deeper\n\n## Usage\n";
+
+    #[test]
+    fn toc_range_drops_headings_outside_it() {
+        let toc = toc_with(MarkdownConfig { toc_min_level: 2, toc_max_level: 4, ..MarkdownConfig::new() }, TOC_LEVELS);
+        assert_eq!(attr_values(&toc, "href"), ["#setup", "#install", "#linux", "#usage"]);
+    }
+
+    #[test]
+    fn toc_range_nests_relative_to_the_shallowest_level() {
+        let toc = toc_with(MarkdownConfig { toc_min_level: 2, toc_max_level: 4, ..MarkdownConfig::new() }, TOC_LEVELS);
+        assert_eq!(
+            toc,
+            "<ul class=\"toc\">\n<li><a href=\"#setup\">Setup</a>\n\
+             <ul>\n<li><a href=\"#install\">Install</a>\n\
+             <ul>\n<li><a href=\"#linux\">Linux</a></li>\n</ul>\n</li>\n</ul>\n</li>\n\
+             <li><a href=\"#usage\">Usage</a></li>\n</ul>\n"
+        );
+    }
+
+    /// Each TOC link with the number of lists enclosing it
+    fn toc_depths(toc: &str) -> Vec<(String, usize)> {
+        let mut depth = 0;
+        let mut links = Vec::new();
+        let mut rest = toc;
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start..];
+            if rest.starts_with("<ul") {
+                depth += 1;
+            } else if rest.starts_with("</ul>") {
+                depth -= 1;
+            } else if let Some(href) = rest.strip_prefix("<a href=\"#") {
+                links.push((href[..href.find('"').unwrap()].to_string(), depth));
+            }
+            rest = &rest[1..];
+        }
+        assert_eq!(depth, 0, "unbalanced lists in {toc}");
+        links
+    }
+
+    #[test]
+    fn toc_nesting_follows_heading_levels() {
+        let toc = toc_with(MarkdownConfig::new(), TOC_LEVELS);
+        let depths: Vec<usize> = toc_depths(&toc).into_iter().map(|(_, depth)| depth).collect();
+        assert_eq!(depths, [1, 2, 3, 4, 5, 6, 2]);
+        assert_eq!(toc.matches("<ul").count(), toc.matches("</ul>").count());
+        assert_eq!(toc.matches("<li>").count(), toc.matches("</li>").count());
+    }
+
+    #[test]
+    fn toc_skipped_levels_nest_one_step() {
+        let toc = toc_with(MarkdownConfig::new(), "## A\n\n#### B\n\n### C\n\n## D\n\n### E\n");
+        let depths: Vec<(String, usize)> = toc_depths(&toc);
+        let expected = [("a", 1), ("b", 2), ("c", 2), ("d", 1), ("e", 2)];
+        assert_eq!(depths, expected.map(|(id, depth)| (id.to_string(), depth)));
+    }
+
+    #[test]
+    fn toc_starting_deep_then_going_shallower_stays_balanced() {
+        let toc = toc_with(MarkdownConfig::new(), "#### A\n\n## B\n\n# C\n");
+        let depths: Vec<usize> = toc_depths(&toc).into_iter().map(|(_, depth)| depth).collect();
+        assert_eq!(depths, [1, 1, 1]);
+    }
+
+    #[test]
+    fn nested_lists_go_inside_their_parent_item() {
+        let expected = "<ul>\n<li>one\n<ul>\n<li>nested</li>\n</ul>\n</li>\n<li>two</li>\n</ul>\n";
+        assert_eq!(render("- one\n  - nested\n- two\n"), expected);
+        assert_eq!(render("- one\n    - nested\n- two\n"), expected);
+        assert_eq!(render("- one\n\t- nested\n- two\n"), expected);
+    }
+
+    #[test]
+    fn nesting_follows_the_parent_item_text() {
+        assert_eq!(
+            render("1. a\n   - b\n   - c\n2. d\n"),
+            "<ol>\n<li>a\n<ul>\n<li>b</li>\n<li>c</li>\n</ul>\n</li>\n<li>d</li>\n</ol>\n"
+        );
+        assert_eq!(
+            render("- a\n  - b\n    - c\n- d\n"),
+            "<ul>\n<li>a\n<ul>\n<li>b\n<ul>\n<li>c</li>\n</ul>\n</li>\n</ul>\n</li>\n<li>d</li>\n</ul>\n"
+        );
+        // Short of the item text, a marker is a sibling
+        assert_eq!(render("- a\n - b\n"), "<ul>\n<li>a</li>\n<li>b</li>\n</ul>\n");
+    }
+
+    #[test]
+    fn wrapped_lines_continue_the_item() {
+        assert_eq!(
+            render("- first line\nwrapped lazily\n  and indented\n- second\n"),
+            "<ul>\n<li>first line wrapped lazily and indented</li>\n<li>second</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn tight_lists_have_no_paragraphs() {
+        assert_eq!(render("1. a\n2. b\n3. c\n"), "<ol>\n<li>a</li>\n<li>b</li>\n<li>c</li>\n</ol>\n");
+    }
+
+    #[test]
+    fn loose_lists_wrap_items_in_paragraphs() {
+        assert_eq!(
+            render("- a\n\n- b\n  more b\n\n  second paragraph\n"),
+            "<ul>\n<li><p>a</p>\n</li>\n<li><p>b more b</p>\n<p>second paragraph</p>\n</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn blank_line_before_a_list_of_the_other_kind_keeps_both_tight() {
+        assert_eq!(
+            render("- a\n- b\n\n1. x\n2. y\n"),
+            "<ul>\n<li>a</li>\n<li>b</li>\n</ul>\n<ol>\n<li>x</li>\n<li>y</li>\n</ol>\n"
+        );
+    }
+
+    fn description(md: &str) -> Option<String> {
+        MarkdownService::new().extract_description(md)
+    }
+
+    #[test]
+    fn frontmatter_description_wins() {
+        let md = "---\ndescription:  Setting up the server  \n---\n# Setup\n\nFirst paragraph.\n";
+        assert_eq!(description(md).as_deref(), Some("Setting up the server"));
+        // A blank one falls back to the body
+        assert_eq!(description("---\ndescription:\n---\nFirst paragraph.\n").as_deref(), Some("First paragraph."));
+    }
+
+    #[test]
+    fn description_falls_back_to_the_first_paragraph() {
+        let md = "# Title\n\n```\ncode first\n```\n\n- a list\n\nThe *first* [real](x)\nparagraph.\n\nThe second.\n";
+        assert_eq!(description(md).as_deref(), Some("The first real paragraph."));
+        assert_eq!(description("# Only a heading\n\n```\ncode\n```\n"), None);
+    }
+
+    #[test]
+    fn long_descriptions_are_cut_at_a_word() {
+        let text = "word ".repeat(60);
+        let cut = description(&text).unwrap();
+        assert!(cut.ends_with("word…"), "{cut}");
+        assert!(cut.chars().count() <= DESCRIPTION_MAX_CHARS + 1);
+    }
+
+    #[test]
+    fn display_math_bounds_the_description() {
+        let service = MarkdownService::new().with_config(MarkdownConfig { math: true, ..MarkdownConfig::new() });
+        let md = "Before the sum\n$$\n\\sum_i x_i\n$$\nAfter it.\n";
+        assert_eq!(service.extract_description(md).as_deref(), Some("Before the sum"));
+        let md = "$$\n\\sum_i x_i\n$$\n$$ a = b $$\nThe prose.\n";
+        assert_eq!(service.extract_description(md).as_deref(), Some("The prose."));
+    }
+
+    #[test]
+    fn word_count_skips_frontmatter_code_and_markup() {
+        let md = "---\ntitle: Not counted here\n---\n# Two words\n\n- one\n- | two |\n\n```\nnot counted at all\n```\n\nthe end.\n";
+        assert_eq!(MarkdownService::new().word_count(md), 6);
+    }
+
+    #[test]
+    fn reading_time_rounds_at_the_configured_speed() {
+        let service = MarkdownService::new().with_config(MarkdownConfig { words_per_minute: 100, ..MarkdownConfig::new() });
+        let result = service.render_with_toc(&"word ".repeat(420)).unwrap();
+        assert_eq!((result.word_count, result.reading_minutes), (420, 4));
+        assert_eq!(service.reading_minutes(450), 5);
+        assert_eq!(MarkdownService::new().reading_minutes(800), 4);
+    }
+
+    #[test]
+    fn short_and_code_only_pages_read_in_under_a_minute() {
+        let result = MarkdownService::new().render_with_toc("```\nlet words = many * more;\n```\n").unwrap();
+        assert_eq!((result.word_count, result.reading_minutes), (0, 0));
+        assert_eq!(MarkdownService::new().reading_minutes(99), 0);
+    }
+
+    #[test]
+    fn internal_links_are_decoded_server_paths() {
+        let html = "<a href=\"/a%20b?x=1\">a</a> <a href=\"https://example.com/\">b</a> <a href=\"//cdn/x\">c</a> \
+                    <a href=\"#top\">d</a> <a href=\"/a%20b#frag\">e</a> <a href=\"/c&amp;d\">f</a>";
+        assert_eq!(MarkdownService::new().internal_links(html), ["/a b", "/c&d"]);
+    }
+
+    #[test]
+    fn code_spans_are_literal_and_escaped() {
+        assert_eq!(render("`a*b*c`\n"), "<p><code>a*b*c</code></p>\n");
+        assert_eq!(render("`<div>`\n"), "<p><code>&lt;div&gt;</code></p>\n");
+        assert_eq!(
+            render("*see `a*b` and `[x](y)` here*\n"),
+            "<p><em>see <code>a*b</code> and <code>[x](y)</code> here</em></p>\n"
+        );
+        assert_eq!(render("**`<b>` tag**\n"), "<p><strong><code>&lt;b&gt;</code> tag</strong></p>\n");
+        assert_eq!(render("``a ` b``\n"), "<p><code>a ` b</code></p>\n");
+    }
+
+    #[test]
+    fn list_like_lines_inside_code_blocks_stay_code() {
+        assert_eq!(render("```\n1. code\n- item\n```\n"), "<pre><code>1. code\n- item\n</code></pre>\n");
+    }
+
+    fn render_smart(md: &str) -> String {
+        render_with(MarkdownConfig { smart_punctuation: true, ..MarkdownConfig::new() }, md)
+    }
+
+    #[test]
+    fn smart_punctuation_curls_quotes_and_joins_dashes() {
+        assert_eq!(render_smart("\"hi\" -- there...\n"), "<p>“hi” – there…</p>\n");
+        assert_eq!(render_smart("It's 'quoted' --- ok ---- no\n"), "<p>It’s ‘quoted’ — ok ---- no</p>\n");
+        // Escaped text is not a tag, so its quotes still curl
+        assert_eq!(render_smart("if a <b then \"c\"\n"), "<p>if a &lt;b then “c”</p>\n");
+        assert_eq!(render_smart("*\"em\"* [\"link\"](/a--b...)\n"), "<p><em>“em”</em> <a href=\"/a--b...\">“link”</a></p>\n");
+    }
+
+    #[test]
+    fn smart_punctuation_leaves_code_alone() {
+        assert_eq!(render_smart("`\"a\" -- b...`\n"), "<p><code>&quot;a&quot; -- b...</code></p>\n");
+        assert_eq!(render_smart("```\n\"a\" -- b...\n```\n"), "<pre><code>&quot;a&quot; -- b...\n</code></pre>\n");
+    }
+
+    #[test]
+    fn smart_punctuation_is_off_by_default() {
+        assert_eq!(render("\"hi\" -- there...\n"), "<p>\"hi\" -- there...</p>\n");
+    }
+
+    fn render_emoji(md: &str) -> String {
+        render_with(MarkdownConfig { emoji: true, ..MarkdownConfig::new() }, md)
+    }
+
+    #[test]
+    fn known_shortcodes_become_emoji() {
+        assert_eq!(render_emoji("Ship it :rocket: :+1:\n"), "<p>Ship it 🚀 👍</p>\n");
+        assert_eq!(render_emoji("**:bug:** at 10:30:00\n"), "<p><strong>🐛</strong> at 10:30:00</p>\n");
+    }
+
+    #[test]
+    fn unknown_and_code_shortcodes_stay_literal() {
+        assert_eq!(render_emoji(":not_emoji: and :Rocket:\n"), "<p>:not_emoji: and :Rocket:</p>\n");
+        assert_eq!(render_emoji("`:rocket:`\n"), "<p><code>:rocket:</code></p>\n");
+        assert_eq!(render(":rocket:\n"), "<p>:rocket:</p>\n");
+    }
+
+    #[test]
+    fn emoji_table_is_sorted_for_lookup() {
+        assert!(EMOJI_SHORTCODES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    fn render_abbr(md: &str) -> String {
+        let config = MarkdownConfig { abbreviations: vec!["HTML: Hypertext Markup Language".to_string()], ..MarkdownConfig::new() };
+        render_with(config, md)
+    }
+
+    #[test]
+    fn defined_abbreviations_are_wrapped_once() {
+        assert_eq!(
+            render_abbr("HTML and HTML5 and HTML again\n"),
+            "<p><abbr title=\"Hypertext Markup Language\">HTML</abbr> and HTML5 and HTML again</p>\n"
+        );
+        assert_eq!(render_abbr("Plain words, html too\n"), "<p>Plain words, html too</p>\n");
+    }
+
+    #[test]
+    fn abbreviations_skip_code_and_links() {
+        assert_eq!(
+            render_abbr("`HTML` [HTML](/h) then HTML\n"),
+            "<p><code>HTML</code> <a href=\"/h\">HTML</a> then <abbr title=\"Hypertext Markup Language\">HTML</abbr></p>\n"
+        );
+    }
+
+    #[test]
+    fn page_abbreviations_override_configured_ones() {
+        let md = "---\nabbreviations: [\"HTML=HyperText\", \"CSS: Cascading Style Sheets\"]\n---\nHTML with CSS\n";
+        assert_eq!(
+            render_abbr(md),
+            "<p><abbr title=\"HyperText\">HTML</abbr> with <abbr title=\"Cascading Style Sheets\">CSS</abbr></p>\n"
+        );
+    }
+
+    #[test]
+    fn each_page_wraps_its_own_first_use() {
+        let config = MarkdownConfig { abbreviations: vec!["CSS=Cascading Style Sheets".to_string()], ..MarkdownConfig::new() };
+        let service = MarkdownService::new().with_config(config);
+        for _ in 0..2 {
+            let html = service.render_with_toc("CSS\n\nMore CSS\n").unwrap().html;
+            assert_eq!(html.matches("<abbr").count(), 1, "{html}");
+        }
+    }
+
+    fn rendered_description(md: &str) -> Option<String> {
+        MarkdownService::new().render_with_toc(md).unwrap().description
+    }
+
+    #[test]
+    fn descriptions_resolve_reference_links() {
+        let md = "A [ref][Foo Bar] and [foo bar].\n\n[foo bar]: /guide \"Guide\"\n";
+        assert_eq!(rendered_description(md).as_deref(), Some("A ref and foo bar."));
+        // Definitions are not text, even when they come first
+        let md = "[docs]: /guide\n\nSee [the docs][docs].\n";
+        assert_eq!(rendered_description(md).as_deref(), Some("See the docs."));
+    }
+
+    #[test]
+    fn descriptions_leave_out_footnote_references() {
+        let md = "Footnote[^1] and again[^1], but not [^nope].\n\n[^1]: The note.\n";
+        let result = MarkdownService::new().render_with_toc(md).unwrap();
+        assert_eq!(result.description.as_deref(), Some("Footnote and again, but not [^nope]."));
+        // Describing the page does not renumber the rendered references
+        assert!(result.html.contains("<a href=\"#fn-1\" id=\"fnref-1\">1</a>"), "{}", result.html);
+        assert!(result.html.contains("id=\"fnref-1-2\""), "{}", result.html);
+        assert!(!result.html.contains("fnref-1-3"));
+    }
 }
@@ -0,0 +1,234 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::SystemTime;
+use log::{debug, info};
+use crate::types::RecentPage;
+
+/// A markdown document held in the search index
+#[derive(Debug, Clone)]
+pub struct IndexedDocument {
+    /// Path relative to the wiki root
+    pub path: String,
+    pub title: String,
+    pub content: String,
+    /// Lowercased frontmatter tags
+    pub tags: Vec<String>,
+    /// Lowercased content, precomputed for case-insensitive matching
+    pub content_lower: String,
+    /// Lowercased title and frontmatter, used for title-only matching
+    pub metadata_lower: String,
+    /// Modification time of the file when it was indexed
+    pub modified: SystemTime,
+}
+
+/// Documents plus an inverted index from tokens to document positions
+#[derive(Default)]
+struct IndexData {
+    documents: Vec<IndexedDocument>,
+    tokens: HashMap<String, Vec<usize>>,
+    /// Every page read by the last build, whether it is a hidden draft, and its modification time
+    drafts: HashMap<String, (bool, SystemTime)>,
+}
+
+/// In-memory search index shared across requests
+///
+/// Tokens are maximal runs of alphanumeric characters of the lowercased content,
+/// so every alphanumeric run of a query substring lies inside a single token.
+/// That lets the index narrow candidates without changing which documents match.
+pub struct SearchIndex {
+    data: RwLock<IndexData>,
+}
+
+impl SearchIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        debug!("Creating empty SearchIndex");
+        Self {
+            data: RwLock::new(IndexData::default()),
+        }
+    }
+
+    /// Replace the indexed documents
+    ///
+    /// `hidden_drafts` holds the path and modification time of each draft left
+    /// out of the index, so listings can skip them without reading them.
+    pub fn replace(&self, documents: Vec<IndexedDocument>, hidden_drafts: Vec<(String, SystemTime)>) {
+        let mut tokens: HashMap<String, Vec<usize>> = HashMap::new();
+        for (idx, document) in documents.iter().enumerate() {
+            let unique: HashSet<&str> = Self::tokenize(&document.content_lower).collect();
+            for token in unique {
+                tokens.entry(token.to_string()).or_default().push(idx);
+            }
+        }
+
+        let drafts = documents
+            .iter()
+            .map(|document| (document.path.clone(), (false, document.modified)))
+            .chain(hidden_drafts.into_iter().map(|(path, modified)| (path, (true, modified))))
+            .collect();
+
+        info!("Search index updated: {} documents, {} tokens", documents.len(), tokens.len());
+        let mut data = self.data.write().unwrap_or_else(|e| e.into_inner());
+        *data = IndexData { documents, tokens, drafts };
+    }
+
+    /// Whether the page at `path` is a draft left out of the index
+    ///
+    /// `None` when the page was not read by the last build or has changed since,
+    /// going by its modification time `modified`; the caller has to read it then.
+    pub fn is_hidden_draft(&self, path: &str, modified: SystemTime) -> Option<bool> {
+        let data = self.data.read().unwrap_or_else(|e| e.into_inner());
+        data.drafts
+            .get(path)
+            .filter(|(_, indexed)| *indexed == modified)
+            .map(|(draft, _)| *draft)
+    }
+
+    /// Number of indexed documents
+    pub fn len(&self) -> usize {
+        self.data.read().unwrap_or_else(|e| e.into_inner()).documents.len()
+    }
+
+    /// Whether the index holds no documents
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `limit` most recently modified documents, newest first
+    ///
+    /// Ties are broken by path so the order is stable between rebuilds.
+    pub fn recent_pages(&self, limit: usize) -> Vec<RecentPage> {
+        let data = self.data.read().unwrap_or_else(|e| e.into_inner());
+        let mut documents: Vec<&IndexedDocument> = data.documents.iter().collect();
+        documents.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path)));
+        documents
+            .into_iter()
+            .take(limit)
+            .map(|document| RecentPage {
+                path: PathBuf::from(&document.path),
+                title: document.title.clone(),
+                modified: document.modified,
+            })
+            .collect()
+    }
+
+    /// Visit every document that may contain `query_lower` as a substring
+    pub fn for_each_candidate<F>(&self, query_lower: &str, mut visit: F)
+    where
+        F: FnMut(&IndexedDocument),
+    {
+        let data = self.data.read().unwrap_or_else(|e| e.into_inner());
+        let terms: Vec<&str> = Self::tokenize(query_lower).collect();
+
+        if terms.is_empty() {
+            // Nothing to narrow on (e.g. punctuation-only queries)
+            data.documents.iter().for_each(visit);
+            return;
+        }
+
+        let mut candidates: Option<HashSet<usize>> = None;
+        for term in terms {
+            let matching: HashSet<usize> = data.tokens
+                .iter()
+                .filter(|(token, _)| token.contains(term))
+                .flat_map(|(_, docs)| docs.iter().copied())
+                .collect();
+            candidates = Some(match candidates {
+                Some(current) => current.intersection(&matching).copied().collect(),
+                None => matching,
+            });
+        }
+
+        let mut candidates: Vec<usize> = candidates.unwrap_or_default().into_iter().collect();
+        candidates.sort_unstable();
+        debug!("Search index narrowed query to {} candidate documents", candidates.len());
+        for idx in candidates {
+            visit(&data.documents[idx]);
+        }
+    }
+
+    /// Visit every document holding, for each word, a token within `max_distance(word)` edits
+    ///
+    /// The visitor also receives the closest token found for each word, in the
+    /// order of `words`. Words are expected lowercased.
+    pub fn for_each_fuzzy_match<F>(&self, words: &[&str], max_distance: fn(&str) -> usize, mut visit: F)
+    where
+        F: FnMut(&IndexedDocument, &[&str]),
+    {
+        let data = self.data.read().unwrap_or_else(|e| e.into_inner());
+        if words.is_empty() {
+            return;
+        }
+
+        // Closest token and its distance per document, for each word
+        let mut per_word: Vec<HashMap<usize, (usize, &str)>> = Vec::with_capacity(words.len());
+        for word in words {
+            let limit = max_distance(word);
+            let word_len = word.chars().count();
+            let mut closest: HashMap<usize, (usize, &str)> = HashMap::new();
+            for (token, docs) in &data.tokens {
+                if token.chars().count().abs_diff(word_len) > limit {
+                    continue;
+                }
+                let Some(distance) = edit_distance(word, token, limit) else {
+                    continue;
+                };
+                for &idx in docs {
+                    let entry = closest.entry(idx).or_insert((distance, token.as_str()));
+                    if distance < entry.0 {
+                        *entry = (distance, token.as_str());
+                    }
+                }
+            }
+            per_word.push(closest);
+        }
+
+        let mut candidates: Vec<usize> = per_word[0]
+            .keys()
+            .copied()
+            .filter(|idx| per_word.iter().all(|closest| closest.contains_key(idx)))
+            .collect();
+        candidates.sort_unstable();
+        debug!("Fuzzy matching found {} candidate documents", candidates.len());
+        for idx in candidates {
+            let tokens: Vec<&str> = per_word.iter().map(|closest| closest[&idx].1).collect();
+            visit(&data.documents[idx], &tokens);
+        }
+    }
+
+    /// Split lowercased text into alphanumeric tokens
+    fn tokenize(text: &str) -> impl Iterator<Item = &str> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+    }
+}
+
+/// Levenshtein distance between `a` and `b` in chars, or `None` when it exceeds `limit`
+fn edit_distance(a: &str, b: &str, limit: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, &ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        // Every later row is at least the smallest value of this one
+        if current.iter().min().is_some_and(|&min| min > limit) {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|&distance| distance <= limit)
+}
+
+impl Default for SearchIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
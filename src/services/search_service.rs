@@ -1,18 +1,339 @@
-use std::path::Path;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use log::{debug, info, warn, error};
+use crate::config::SearchConfig;
 use crate::errors::WikiError;
-use crate::types::SearchResult;
+use crate::types::{RecentPage, SearchResult};
+use crate::utils::{url_path, Frontmatter};
 use crate::services::FileService;
+use crate::services::search_index::{IndexedDocument, SearchIndex};
+
+/// Characters of context shown on each side of a match in an excerpt
+const EXCERPT_CONTEXT_CHARS: usize = 100;
+
+/// Queries with fewer characters than this get suggestions instead of results
+pub const MIN_QUERY_CHARS: usize = 2;
+
+/// Query words at least this long accept two edits in fuzzy matching, shorter ones one
+const FUZZY_LONG_WORD_CHARS: usize = 6;
+
+/// Edits a query word may be away from a document word in fuzzy matching
+///
+/// Words under three characters must match exactly; anything looser would
+/// match most short words.
+fn fuzzy_distance(word: &str) -> usize {
+    match word.chars().count() {
+        0..=2 => 0,
+        len if len < FUZZY_LONG_WORD_CHARS => 1,
+        _ => 2,
+    }
+}
+
+/// Whether `c` can be part of a word for whole-word matching
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Byte positions of `term` in `text`
+///
+/// With `whole_word`, a match must not continue a word on either side; edges
+/// of the term that are punctuation need no boundary.
+fn term_positions<'a>(text: &'a str, term: &'a str, whole_word: bool) -> impl Iterator<Item = usize> + 'a {
+    text.match_indices(term).map(|(pos, _)| pos).filter(move |&pos| {
+        if !whole_word {
+            return true;
+        }
+        let starts_word = term.chars().next().is_some_and(is_word_char);
+        let ends_word = term.chars().next_back().is_some_and(is_word_char);
+        let before = text[..pos].chars().next_back();
+        let after = text[pos + term.len()..].chars().next();
+        (!starts_word || !before.is_some_and(is_word_char)) && (!ends_word || !after.is_some_and(is_word_char))
+    })
+}
+
+/// Whether `text` contains `term`, honoring whole-word matching
+fn contains_term(text: &str, term: &str, whole_word: bool) -> bool {
+    term_positions(text, term, whole_word).next().is_some()
+}
+
+/// Search query split into its operators
+///
+/// Terms are lowercased unless matching is case-sensitive. A document matches when every required clause has at
+/// least one term present, no excluded term is present, and, if there are no
+/// required clauses, at least one optional term is present. `tag:` filters
+/// restrict matches to documents carrying every listed frontmatter tag.
+#[derive(Debug, Default)]
+struct ParsedQuery {
+    /// Clauses that must match; each clause is satisfied by any of its terms
+    required: Vec<Vec<String>>,
+    /// Bare words that only contribute to relevance
+    optional: Vec<String>,
+    /// Terms that exclude a document
+    excluded: Vec<String>,
+    /// Tags a document must carry
+    tags: Vec<String>,
+}
+
+/// Lexical pieces of a search query
+enum QueryToken {
+    Term { text: String, phrase: bool, negated: bool },
+    Tag(String),
+    And,
+    Or,
+}
+
+impl ParsedQuery {
+    /// Parse `"exact phrase"`, `a AND b`, `a OR b`, `-term` and `tag:name` operators
+    ///
+    /// A query without operators is kept whole as a single required substring,
+    /// matching the behavior of a plain search. Tags are always lowercased.
+    fn parse(query: &str, case_sensitive: bool) -> Self {
+        let fold = |text: &str| if case_sensitive { text.to_string() } else { text.to_lowercase() };
+        let tokens = Self::tokenize(query, fold);
+        let has_operators = tokens.iter().any(|token| match token {
+            QueryToken::Term { phrase, negated, .. } => *phrase || *negated,
+            QueryToken::Tag(_) | QueryToken::And | QueryToken::Or => true,
+        });
+        if !has_operators {
+            return Self {
+                required: vec![vec![fold(query.trim())]],
+                ..Self::default()
+            };
+        }
+
+        // Clauses in query order, flagged when something makes them required
+        let mut clauses: Vec<(Vec<String>, bool)> = Vec::new();
+        let mut excluded = Vec::new();
+        let mut tags = Vec::new();
+        let mut pending: Option<QueryToken> = None;
+
+        for token in tokens {
+            match token {
+                QueryToken::And | QueryToken::Or => pending = Some(token),
+                QueryToken::Tag(tag) => tags.push(tag),
+                QueryToken::Term { text, negated: true, .. } => {
+                    excluded.push(text);
+                    pending = None;
+                }
+                QueryToken::Term { text, phrase, negated: false } => {
+                    match (pending.take(), clauses.last_mut()) {
+                        (Some(QueryToken::Or), Some((alternatives, required))) => {
+                            alternatives.push(text);
+                            *required = true;
+                        }
+                        (Some(QueryToken::And), Some((_, required))) => {
+                            *required = true;
+                            clauses.push((vec![text], true));
+                        }
+                        _ => clauses.push((vec![text], phrase)),
+                    }
+                }
+            }
+        }
+
+        let mut parsed = Self { excluded, tags, ..Self::default() };
+        for (terms, required) in clauses {
+            if required {
+                parsed.required.push(terms);
+            } else {
+                parsed.optional.extend(terms);
+            }
+        }
+        parsed
+    }
+
+    /// Split a query into terms, quoted phrases and operators
+    fn tokenize(query: &str, fold: impl Fn(&str) -> String) -> Vec<QueryToken> {
+        let mut tokens = Vec::new();
+        let mut chars = query.chars().peekable();
+
+        while let Some(&ch) = chars.peek() {
+            if ch.is_whitespace() {
+                chars.next();
+                continue;
+            }
+
+            let negated = ch == '-';
+            if negated {
+                chars.next();
+            }
+
+            let (text, phrase) = if chars.peek() == Some(&'"') {
+                chars.next();
+                let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                (phrase, true)
+            } else {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                (word, false)
+            };
+
+            let text = text.trim();
+            if text.is_empty() {
+                if negated && !phrase {
+                    // A lone `-` is just a character to search for
+                    tokens.push(QueryToken::Term { text: "-".to_string(), phrase: false, negated: false });
+                }
+                continue;
+            }
+
+            match text {
+                "AND" if !phrase && !negated => tokens.push(QueryToken::And),
+                "OR" if !phrase && !negated => tokens.push(QueryToken::Or),
+                _ if !phrase && !negated && text.len() > 4
+                    && text.get(..4).is_some_and(|prefix| prefix.eq_ignore_ascii_case("tag:")) => {
+                    tokens.push(QueryToken::Tag(text[4..].to_lowercase()))
+                }
+                _ => tokens.push(QueryToken::Term { text: fold(text), phrase, negated }),
+            }
+        }
+
+        tokens
+    }
+
+    /// Term that every match must contain, used to narrow index candidates
+    fn narrowing_term(&self) -> Option<&str> {
+        self.required
+            .iter()
+            .find(|clause| clause.len() == 1)
+            .map(|clause| clause[0].as_str())
+    }
+
+    /// Whether the document carries every tag and contains no excluded term
+    fn passes_filters(&self, document: &IndexedDocument, text: &str, whole_word: bool) -> bool {
+        self.tags.iter().all(|tag| document.tags.contains(tag))
+            && !self.excluded.iter().any(|term| contains_term(text, term, whole_word))
+    }
+
+    /// Lowercased alphanumeric words of the positive terms, for fuzzy matching
+    fn fuzzy_words(&self) -> Vec<String> {
+        let mut words: Vec<String> = Vec::new();
+        for term in self.required.iter().flatten().chain(&self.optional) {
+            for word in term.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+                let word = word.to_lowercase();
+                if !words.contains(&word) {
+                    words.push(word);
+                }
+            }
+        }
+        words
+    }
+
+    /// Positive terms found in `text`, or `None` when the document does not match
+    fn matched_terms<'a>(&'a self, document: &IndexedDocument, text: &str, whole_word: bool) -> Option<Vec<&'a str>> {
+        if !self.passes_filters(document, text, whole_word) {
+            return None;
+        }
+
+        let mut matched = Vec::new();
+        for clause in &self.required {
+            let before = matched.len();
+            matched.extend(clause.iter().map(String::as_str).filter(|term| contains_term(text, term, whole_word)));
+            if matched.len() == before {
+                return None;
+            }
+        }
+
+        let optional_before = matched.len();
+        matched.extend(self.optional.iter().map(String::as_str).filter(|term| contains_term(text, term, whole_word)));
+        // A tag filter on its own matches every tagged document
+        let filter_only = self.optional.is_empty() && !self.tags.is_empty();
+        if self.required.is_empty() && matched.len() == optional_before && !filter_only {
+            return None;
+        }
+
+        Some(matched)
+    }
+}
+
+/// Upper bound on threads used to read files while indexing
+const MAX_INDEX_THREADS: usize = 8;
+
+/// A markdown file read while rebuilding the index
+enum IndexedFile {
+    Document(IndexedDocument),
+    /// A draft left out of the index, by path and modification time
+    HiddenDraft(String, SystemTime),
+}
 
 /// Service for handling search operations
 pub struct SearchService {
     file_service: FileService,
+    index: Arc<SearchIndex>,
+    config: SearchConfig,
+    /// Index pages marked `draft: true`
+    show_drafts: bool,
 }
 
 impl SearchService {
-    /// Create a new search service
-    pub fn new(file_service: FileService) -> Self {
-        Self { file_service }
+    /// Create a new search service backed by a shared index
+    pub fn new(file_service: FileService, index: Arc<SearchIndex>, config: SearchConfig) -> Self {
+        Self { file_service, index, config, show_drafts: false }
+    }
+
+    /// Include pages marked `draft: true` when rebuilding the index
+    pub fn with_drafts(mut self, show_drafts: bool) -> Self {
+        self.show_drafts = show_drafts;
+        self
+    }
+
+    /// Whether a query is too short to search, so suggestions are shown instead
+    pub fn is_short_query(query: &str) -> bool {
+        query.trim().chars().count() < MIN_QUERY_CHARS
+    }
+
+    /// Recently updated pages to offer in place of results for a short query
+    ///
+    /// These come from the modification times recorded in the index, so no
+    /// query is run and no file is read.
+    pub fn suggestions(&self) -> Vec<RecentPage> {
+        self.index.recent_pages(self.config.suggestion_count)
+    }
+
+    /// Walk the wiki tree and rebuild the search index from disk
+    pub fn rebuild_index(&self) -> Result<(), WikiError> {
+        info!("Rebuilding search index");
+        let start_time = std::time::Instant::now();
+        
+        // Wrap the walk in a panic handler to prevent crashes
+        let walk_result = std::panic::catch_unwind(|| {
+            let mut paths = Vec::new();
+            self.search_directory(Path::new(""), 1, &mut paths, &mut HashSet::new())
+                .map(|_| self.index_files(&paths))
+        });
+        
+        match walk_result {
+            Ok(Ok(files)) => {
+                let mut documents = Vec::with_capacity(files.len());
+                let mut hidden_drafts = Vec::new();
+                for file in files {
+                    match file {
+                        IndexedFile::Document(document) => documents.push(document),
+                        IndexedFile::HiddenDraft(path, modified) => hidden_drafts.push((path, modified)),
+                    }
+                }
+                self.index.replace(documents, hidden_drafts);
+                info!("Search index rebuilt in {:?}ms", start_time.elapsed().as_millis());
+                Ok(())
+            }
+            Ok(Err(e)) => {
+                error!("Search index rebuild failed with error: {:?}", e);
+                Err(e)
+            }
+            Err(_) => {
+                error!("Search index rebuild panicked, keeping previous index");
+                Err(WikiError::SearchError("index rebuild panicked".to_string()))
+            }
+        }
     }
 
     /// Search for content in the wiki
@@ -25,14 +346,39 @@ impl SearchService {
         info!("Starting search for query: '{}'", query);
         let start_time = std::time::Instant::now();
         
+        let parsed = ParsedQuery::parse(query, self.config.case_sensitive);
+        debug!("Parsed search query: {:?}", parsed);
+        
         // Wrap the search in a panic handler to prevent crashes
         let search_result = std::panic::catch_unwind(|| {
             let mut results = Vec::new();
-            self.search_directory(Path::new(""), query, &mut results).map(|_| results)
+            // The token index covers page content only, so titles cannot narrow
+            let narrowing = if self.config.title_only {
+                String::new()
+            } else {
+                parsed.narrowing_term().unwrap_or("").to_lowercase()
+            };
+            self.index.for_each_candidate(&narrowing, |document| {
+                if let Some(result) = self.score_document(document, &parsed) {
+                    results.push(result);
+                }
+            });
+            Ok::<_, WikiError>(results)
         });
         
         match search_result {
             Ok(Ok(mut results)) => {
+                let before = results.len();
+                results.retain(|result| result.relevance >= self.config.min_relevance);
+                if results.len() < before {
+                    debug!("Dropped {} results below relevance {:.1}", before - results.len(), self.config.min_relevance);
+                }
+
+                // The token index covers page content only, so title-only search stays exact
+                if self.config.fuzzy && !self.config.title_only && results.len() < self.config.fuzzy_min_results {
+                    self.add_fuzzy_results(&parsed, &mut results);
+                }
+
                 // Sort by relevance (simple implementation)
                 results.sort_by(|a, b| b.relevance.partial_cmp(&a.relevance).unwrap_or(std::cmp::Ordering::Equal));
                 
@@ -59,59 +405,205 @@ impl SearchService {
         }
     }
 
-    /// Recursively search through directories
+    /// Match a single indexed document against the query
+    fn score_document(&self, document: &IndexedDocument, query: &ParsedQuery) -> Option<SearchResult> {
+        // Check which query terms the content contains
+        let matched = query.matched_terms(document, &self.match_text(document), self.config.whole_word)?;
+        
+        // Safely generate excerpt and calculate relevance
+        let (excerpt, excerpts) = self.result_excerpts(document, &matched);
+        let relevance = matched.iter().map(|term| self.calculate_relevance(&document.content, term)).sum();
+        
+        debug!("Found match in {:?} with relevance: {:.1}", document.path, relevance);
+        
+        Some(SearchResult {
+            title: document.title.clone(),
+            path: document.path.clone(),
+            excerpt,
+            excerpts,
+            relevance,
+        })
+    }
+
+    /// Text the query is matched against, folded to lowercase unless matching is case-sensitive
+    fn match_text<'a>(&self, document: &'a IndexedDocument) -> Cow<'a, str> {
+        match (self.config.title_only, self.config.case_sensitive) {
+            (true, true) => Cow::Owned(format!("{}\n{}", document.title, Frontmatter::block(&document.content).unwrap_or(""))),
+            (true, false) => Cow::Borrowed(&document.metadata_lower),
+            (false, true) => Cow::Borrowed(&document.content),
+            (false, false) => Cow::Borrowed(&document.content_lower),
+        }
+    }
+
+    /// Main excerpt and the excerpts listed for a result matching `terms`
+    fn result_excerpts(&self, document: &IndexedDocument, terms: &[&str]) -> (String, Vec<String>) {
+        // Excerpts come from the body so they never show frontmatter lines
+        let body = Frontmatter::body(&document.content);
+        let excerpt = self.generate_excerpt_safe(body, terms.first().copied().unwrap_or(""));
+        let mut excerpts = if self.config.max_excerpts > 1 {
+            self.generate_excerpts_safe(body, terms, self.config.max_excerpts)
+        } else {
+            Vec::new()
+        };
+        if excerpts.is_empty() {
+            excerpts.push(excerpt.clone());
+        }
+        (excerpt, excerpts)
+    }
+
+    /// Add documents whose words are within a few edits of every query word
+    ///
+    /// Documents already found by exact matching are skipped. Each word scores
+    /// `word_weight` when it appears as is and `fuzzy_weight` otherwise.
+    fn add_fuzzy_results(&self, query: &ParsedQuery, results: &mut Vec<SearchResult>) {
+        let words = query.fuzzy_words();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let found: HashSet<String> = results.iter().map(|result| result.path.clone()).collect();
+        let before = results.len();
+
+        self.index.for_each_fuzzy_match(&words, fuzzy_distance, |document, tokens| {
+            if found.contains(&document.path) || !query.passes_filters(document, &self.match_text(document), self.config.whole_word) {
+                return;
+            }
+            let relevance: f32 = words
+                .iter()
+                .zip(tokens)
+                .map(|(word, token)| if word == token { self.config.word_weight } else { self.config.fuzzy_weight })
+                .sum();
+            if relevance < self.config.min_relevance {
+                return;
+            }
+            let (excerpt, excerpts) = self.result_excerpts(document, tokens);
+            results.push(SearchResult {
+                title: document.title.clone(),
+                path: document.path.clone(),
+                excerpt,
+                excerpts,
+                relevance,
+            });
+        });
+
+        debug!("Fuzzy matching added {} results", results.len() - before);
+    }
+
+    /// Recursively collect the paths of markdown documents to index
+    ///
+    /// Symlinked directories are followed; `visited` holds the canonical paths
+    /// of directories already indexed, so a symlink cycle is entered only once.
+    /// `depth` is the level of `current_path`, 1 for the wiki root; directories
+    /// below `max_search_depth` are not entered.
     fn search_directory(
         &self,
         current_path: &Path,
-        query: &str,
-        results: &mut Vec<SearchResult>,
+        depth: usize,
+        paths: &mut Vec<PathBuf>,
+        visited: &mut HashSet<PathBuf>,
     ) -> Result<(), WikiError> {
-        debug!("Searching directory: {:?}", current_path);
+        debug!("Indexing directory: {:?}", current_path);
+
+        if !visited.insert(self.file_service.canonical_path(current_path)?) {
+            warn!("Skipping already indexed directory: {:?}", current_path);
+            return Ok(());
+        }
         
         let entries = self.file_service.list_directory(current_path)?;
         
-        for entry in entries {
+        // Dotfiles are hidden from search as they are from navigation
+        for entry in entries.into_iter().filter(|entry| !entry.name.starts_with('.')) {
             let entry_path = if current_path.as_os_str().is_empty() {
                 entry.path.clone()
             } else {
                 current_path.join(&entry.name)
             };
 
-            if entry.is_dir {
-                // Recursively search subdirectories
-                self.search_directory(&entry_path, query, results)?;
-            } else if entry.name.ends_with(".md") {
-                // Search in markdown files
-                debug!("Searching markdown file: {:?}", entry_path);
-                match self.file_service.read_file(&entry_path) {
-                    Ok(content) => {
-                        // Check if content contains the query (case-insensitive)
-                        if content.to_lowercase().contains(&query.to_lowercase()) {
-                            // Safely generate excerpt and calculate relevance
-                            let excerpt = self.generate_excerpt_safe(&content, query);
-                            let relevance = self.calculate_relevance(&content, query);
-                            let title = self.extract_title(&content, &entry.name);
-                            
-                            debug!("Found match in {:?} with relevance: {:.1}", entry_path, relevance);
-                            
-                            results.push(SearchResult {
-                                title,
-                                path: entry_path.to_string_lossy().to_string(),
-                                excerpt,
-                                relevance,
-                            });
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Failed to read file {:?}: {:?}", entry_path, e);
-                    }
+            if entry.is_dir || self.file_service.dir_exists(&entry_path) {
+                if depth >= self.config.max_search_depth {
+                    debug!("Not indexing {:?} below the maximum search depth", entry_path);
+                    continue;
                 }
+                // Recursively index subdirectories
+                self.search_directory(&entry_path, depth + 1, paths, visited)?;
+            } else if entry.name.ends_with(".md") {
+                paths.push(entry_path);
             }
         }
         
         Ok(())
     }
 
+    /// Read and index files in parallel, keeping the input order
+    fn index_files(&self, paths: &[PathBuf]) -> Vec<IndexedFile> {
+        if paths.is_empty() {
+            return Vec::new();
+        }
+        
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .clamp(1, MAX_INDEX_THREADS);
+        let chunk_size = paths.len().div_ceil(threads);
+        debug!("Indexing {} files on {} threads", paths.len(), threads);
+        
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = paths
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || {
+                    chunk.iter().filter_map(|path| self.index_file_safe(path)).collect::<Vec<_>>()
+                }))
+                .collect();
+            
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_default())
+                .collect()
+        })
+    }
+
+    /// Index a single file, isolating read errors and panics to that file
+    fn index_file_safe(&self, path: &Path) -> Option<IndexedFile> {
+        debug!("Indexing markdown file: {:?}", path);
+        let indexed = std::panic::catch_unwind(|| self.file_service.read_file(path));
+        match indexed {
+            Ok(Ok(content)) => {
+                let modified = self
+                    .file_service
+                    .get_metadata(path)
+                    .and_then(|meta| Ok(meta.modified()?))
+                    .unwrap_or(UNIX_EPOCH);
+                if !self.show_drafts && Frontmatter::parse(&content).is_draft() {
+                    debug!("Leaving draft {:?} out of the index", path);
+                    return Some(IndexedFile::HiddenDraft(url_path(path), modified));
+                }
+                let name = path.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let title = self.extract_title(&content, &name);
+                let frontmatter = Frontmatter::block(&content).unwrap_or("");
+                Some(IndexedFile::Document(IndexedDocument {
+                    path: url_path(path),
+                    metadata_lower: format!("{}\n{}", title, frontmatter).to_lowercase(),
+                    title,
+                    tags: Frontmatter::parse(&content)
+                        .list("tags")
+                        .iter()
+                        .map(|tag| tag.to_lowercase())
+                        .collect(),
+                    content_lower: content.to_lowercase(),
+                    content,
+                    modified,
+                }))
+            }
+            Ok(Err(e)) => {
+                warn!("Failed to read file {:?}: {:?}", path, e);
+                None
+            }
+            Err(_) => {
+                error!("Indexing panicked for file {:?}, skipping it", path);
+                None
+            }
+        }
+    }
+
     /// Extract title from markdown content or use filename
     fn extract_title(&self, content: &str, filename: &str) -> String {
         // Try to extract title from frontmatter or first heading
@@ -143,7 +635,12 @@ impl SearchService {
     }
 
     /// Calculate search relevance score
+    ///
+    /// The body is scored for phrase, word, title and heading matches; the
+    /// frontmatter only counts through its own bonus.
     fn calculate_relevance(&self, content: &str, query: &str) -> f32 {
+        let frontmatter = Frontmatter::block(content).unwrap_or("");
+        let content = Frontmatter::body(content);
         let content_lower = content.to_lowercase();
         let query_lower = query.to_lowercase();
         
@@ -151,7 +648,7 @@ impl SearchService {
         
         // Exact phrase match gets highest score
         if content_lower.contains(&query_lower) {
-            score += 20.0;
+            score += self.config.phrase_weight;
         }
         
         // Word boundary matches
@@ -159,7 +656,7 @@ impl SearchService {
         for word in &words {
             if word.len() > 2 { // Only count words longer than 2 characters
                 if content_lower.contains(word) {
-                    score += 3.0;
+                    score += self.config.word_weight;
                 }
             }
         }
@@ -167,31 +664,25 @@ impl SearchService {
         // Title matches get bonus
         if let Some(first_line) = content.lines().next() {
             if first_line.to_lowercase().contains(&query_lower) {
-                score += 15.0;
+                score += self.config.title_phrase_weight;
             }
             // Check individual words in title
             for word in &words {
                 if word.len() > 2 && first_line.to_lowercase().contains(word) {
-                    score += 5.0;
+                    score += self.config.title_word_weight;
                 }
             }
         }
         
         // Frontmatter matches get bonus
-        if content.contains("---") {
-            let frontmatter_end = content.find("---").unwrap_or(0);
-            let frontmatter = &content[..frontmatter_end];
-            if frontmatter.to_lowercase().contains(&query_lower) {
-                score += 10.0;
-            }
+        if frontmatter.to_lowercase().contains(&query_lower) {
+            score += self.config.frontmatter_weight;
         }
         
         // Headings matches get bonus
         for line in content.lines() {
-            if line.starts_with('#') {
-                if line.to_lowercase().contains(&query_lower) {
-                    score += 8.0;
-                }
+            if line.starts_with('#') && line.to_lowercase().contains(&query_lower) {
+                score += self.config.heading_weight;
             }
         }
         
@@ -200,36 +691,15 @@ impl SearchService {
 
     /// Generate search result excerpt with better context
     fn generate_excerpt(&self, content: &str, query: &str) -> String {
-        let content_lower = content.to_lowercase();
-        let query_lower = query.to_lowercase();
+        let content_lower = self.fold_case(content);
+        let query_lower = self.fold_case(query);
         
-        if let Some(pos) = content_lower.find(&query_lower) {
+        if let Some(pos) = term_positions(&content_lower, &query_lower, self.config.whole_word).next() {
             // Convert byte position to char position for safe slicing
             let char_pos = content_lower.char_indices()
                 .position(|(i, _)| i == pos)
                 .unwrap_or(0);
-            
-            let start = char_pos.saturating_sub(100);
-            let end = (char_pos + query.chars().count() + 100).min(content.chars().count());
-            
-            // Get the excerpt using char indices
-            let excerpt: String = content.chars().skip(start).take(end - start).collect();
-            
-            // Try to start at a word boundary
-            let mut final_start = 0;
-            if start > 0 {
-                if let Some(word_start) = excerpt.find(' ') {
-                    final_start = word_start + 1;
-                }
-            }
-            
-            let final_excerpt = &excerpt[final_start..];
-            
-            if start > 0 {
-                format!("...{}...", final_excerpt)
-            } else {
-                format!("{}...", final_excerpt)
-            }
+            self.excerpt_around(content, char_pos, query.chars().count())
         } else {
             // Fallback to first meaningful content
             let lines: Vec<&str> = content.lines().collect();
@@ -250,6 +720,79 @@ impl SearchService {
         }
     }
 
+    /// Lowercase `text` unless matching is case-sensitive
+    fn fold_case<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.config.case_sensitive {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(text.to_lowercase())
+        }
+    }
+
+    /// Excerpt of the context around a match at `char_pos` spanning `len` chars
+    fn excerpt_around(&self, content: &str, char_pos: usize, len: usize) -> String {
+        let start = char_pos.saturating_sub(EXCERPT_CONTEXT_CHARS);
+        let end = (char_pos + len + EXCERPT_CONTEXT_CHARS).min(content.chars().count());
+        
+        // Get the excerpt using char indices
+        let excerpt: String = content.chars().skip(start).take(end.saturating_sub(start)).collect();
+        
+        // Try to start at a word boundary
+        let mut final_start = 0;
+        if start > 0 && let Some(word_start) = excerpt.find(' ') {
+            final_start = word_start + 1;
+        }
+        
+        let final_excerpt = &excerpt[final_start..];
+        
+        if start > 0 {
+            format!("...{}...", final_excerpt)
+        } else {
+            format!("{}...", final_excerpt)
+        }
+    }
+
+    /// Excerpts around up to `limit` matches of any of the terms, in document order
+    ///
+    /// A match whose context would overlap an excerpt already taken is skipped,
+    /// so each passage is shown once.
+    fn generate_excerpts(&self, content: &str, terms: &[&str], limit: usize) -> Vec<String> {
+        let content_lower = self.fold_case(content);
+        let mut matches: Vec<(usize, usize)> = Vec::new();
+        for term in terms {
+            let term_lower = self.fold_case(term);
+            if term_lower.is_empty() {
+                continue;
+            }
+            for pos in term_positions(&content_lower, &term_lower, self.config.whole_word) {
+                matches.push((content_lower[..pos].chars().count(), term_lower.chars().count()));
+            }
+        }
+        matches.sort_unstable();
+
+        let mut excerpts = Vec::new();
+        let mut covered_until = 0;
+        for (char_pos, len) in matches {
+            if excerpts.len() >= limit {
+                break;
+            }
+            if !excerpts.is_empty() && char_pos.saturating_sub(EXCERPT_CONTEXT_CHARS) < covered_until {
+                continue;
+            }
+            covered_until = char_pos + len + EXCERPT_CONTEXT_CHARS;
+            excerpts.push(self.excerpt_around(content, char_pos, len));
+        }
+        excerpts
+    }
+
+    /// Safe version of generate_excerpts, returning no excerpts if generation fails
+    fn generate_excerpts_safe(&self, content: &str, terms: &[&str], limit: usize) -> Vec<String> {
+        std::panic::catch_unwind(|| self.generate_excerpts(content, terms, limit)).unwrap_or_else(|_| {
+            warn!("Failed to generate excerpts for content, using a single excerpt");
+            Vec::new()
+        })
+    }
+
     /// Safe version of generate_excerpt that handles UTF-8 errors gracefully
     fn generate_excerpt_safe(&self, content: &str, query: &str) -> String {
         match std::panic::catch_unwind(|| self.generate_excerpt(content, query)) {
@@ -262,3 +805,481 @@ impl SearchService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A wiki in a temporary directory holding `pages`
+    fn wiki(pages: &[(&str, &str)]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in pages {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    /// A search service over `dir` with its index built
+    fn service(dir: &TempDir, config: SearchConfig) -> SearchService {
+        let service = SearchService::new(FileService::new(dir.path().to_path_buf()), Arc::new(SearchIndex::new()), config);
+        service.rebuild_index().unwrap();
+        service
+    }
+
+    /// Sorted result paths of a query
+    fn paths(service: &SearchService, query: &str) -> Vec<String> {
+        let mut paths: Vec<String> = service.search(query).unwrap().into_iter().map(|result| result.path).collect();
+        paths.sort();
+        paths
+    }
+
+    /// Pages whose content contains `query`, found by reading every file under `dir`
+    fn walk_matches(dir: &Path, root: &Path, query: &str, matches: &mut Vec<String>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                walk_matches(&path, root, query, matches);
+            } else if path.extension().is_some_and(|ext| ext == "md")
+                && std::fs::read_to_string(&path).unwrap().to_lowercase().contains(query)
+            {
+                matches.push(url_path(path.strip_prefix(root).unwrap()));
+            }
+        }
+    }
+
+    const FIXTURE: &[(&str, &str)] = &[
+        ("index.md", "# Home\n\nWelcome to the wiki.\n"),
+        ("rust.md", "# Rust\n\nOwnership and borrowing in Rust.\n"),
+        ("guide/intro.md", "# Intro\n\nStart here, then read about Rust ownership.\n"),
+        ("guide/setup.md", "# Setup\n\nInstall the toolchain.\n"),
+        ("notes.txt", "Rust ownership in a text file\n"),
+    ];
+
+    #[test]
+    fn searches_use_the_index_without_rereading_files() {
+        let dir = wiki(FIXTURE);
+        let service = service(&dir, SearchConfig::new());
+        let first = paths(&service, "ownership");
+
+        for (path, _) in FIXTURE {
+            std::fs::remove_file(dir.path().join(path)).unwrap();
+        }
+
+        assert_eq!(paths(&service, "ownership"), first);
+        assert_eq!(first, vec!["guide/intro.md", "rust.md"]);
+    }
+
+    #[test]
+    fn index_results_match_a_filesystem_walk() {
+        let dir = wiki(FIXTURE);
+        let service = service(&dir, SearchConfig::new());
+
+        for query in ["rust", "ownership", "the", "toolchain", "wiki", "missing"] {
+            let mut expected = Vec::new();
+            walk_matches(dir.path(), dir.path(), query, &mut expected);
+            expected.sort();
+            assert_eq!(paths(&service, query), expected, "query {query:?}");
+        }
+    }
+
+    #[test]
+    fn rebuilding_picks_up_changes() {
+        let dir = wiki(FIXTURE);
+        let service = service(&dir, SearchConfig::new());
+        assert!(paths(&service, "toolchain").contains(&"guide/setup.md".to_string()));
+
+        std::fs::write(dir.path().join("guide/setup.md"), "# Setup\n\nNothing to install.\n").unwrap();
+        assert_eq!(paths(&service, "toolchain"), vec!["guide/setup.md"]);
+        service.rebuild_index().unwrap();
+        assert!(paths(&service, "toolchain").is_empty());
+    }
+
+    const OPERATORS: &[(&str, &str)] = &[
+        ("exact.md", "The quick brown fox jumps.\n"),
+        ("reversed.md", "A brown and quick fox.\n"),
+        ("lazy.md", "The quick brown dog sleeps.\n"),
+    ];
+
+    #[test]
+    fn quoted_phrase_requires_the_words_in_order() {
+        let dir = wiki(OPERATORS);
+        let service = service(&dir, SearchConfig::new());
+        assert_eq!(paths(&service, "\"quick brown\""), vec!["exact.md", "lazy.md"]);
+        assert_eq!(paths(&service, "\"brown fox\""), vec!["exact.md"]);
+    }
+
+    #[test]
+    fn and_requires_every_term() {
+        let dir = wiki(OPERATORS);
+        let service = service(&dir, SearchConfig::new());
+        assert_eq!(paths(&service, "fox AND sleeps"), Vec::<String>::new());
+        assert_eq!(paths(&service, "quick AND dog"), vec!["lazy.md"]);
+        assert_eq!(paths(&service, "fox OR dog"), vec!["exact.md", "lazy.md", "reversed.md"]);
+    }
+
+    #[test]
+    fn minus_excludes_documents() {
+        let dir = wiki(OPERATORS);
+        let service = service(&dir, SearchConfig::new());
+        assert_eq!(paths(&service, "quick -dog"), vec!["exact.md", "reversed.md"]);
+        assert_eq!(paths(&service, "\"brown\" -\"quick fox\""), vec!["exact.md", "lazy.md"]);
+    }
+
+    #[test]
+    fn parallel_indexing_matches_sequential_indexing() {
+        let pages: Vec<(String, String)> = (0..200)
+            .map(|i| (format!("section{}/page{i}.md", i % 7), format!("# Page {i}\n\nshared term {}\n", i % 3)))
+            .collect();
+        let pages: Vec<(&str, &str)> = pages.iter().map(|(path, content)| (path.as_str(), content.as_str())).collect();
+        let dir = wiki(&pages);
+        let service = service(&dir, SearchConfig::new());
+
+        let mut files = Vec::new();
+        service.search_directory(Path::new(""), 1, &mut files, &mut HashSet::new()).unwrap();
+        assert_eq!(files.len(), 200);
+        let text = |file: IndexedFile| match file {
+            IndexedFile::Document(doc) => (doc.path, doc.content),
+            IndexedFile::HiddenDraft(path, _) => (path, String::new()),
+        };
+        let parallel: Vec<(String, String)> = service.index_files(&files).into_iter().map(text).collect();
+        let sequential: Vec<(String, String)> = files
+            .iter()
+            .filter_map(|path| service.index_file_safe(path))
+            .map(text)
+            .collect();
+        assert_eq!(parallel, sequential);
+
+        let mut expected: Vec<String> = sequential
+            .iter()
+            .filter(|(_, content)| content.contains("shared term 1"))
+            .map(|(path, _)| path.clone())
+            .collect();
+        expected.sort();
+        assert_eq!(paths(&service, "shared term 1"), expected);
+    }
+
+    #[test]
+    fn an_unreadable_file_does_not_abort_indexing() {
+        let dir = wiki(&[("small.md", "needle\n"), ("large.md", &format!("needle {}\n", "x".repeat(4096)))]);
+        let service = SearchService::new(
+            FileService::new(dir.path().to_path_buf()).with_max_file_bytes(1024),
+            Arc::new(SearchIndex::new()),
+            SearchConfig::new(),
+        );
+        service.rebuild_index().unwrap();
+        assert_eq!(paths(&service, "needle"), vec!["small.md"]);
+    }
+
+    #[test]
+    fn nested_result_paths_use_forward_slashes() {
+        let dir = wiki(&[("guide/setup/intro.md", "# Intro\n\nnested page\n")]);
+        let service = service(&dir, SearchConfig::new());
+        assert_eq!(paths(&service, "nested"), vec!["guide/setup/intro.md"]);
+    }
+
+    const TAGGED: &[(&str, &str)] = &[
+        ("tagged.md", "---\ntags: [Guide, rust]\n---\nhello from a guide\n"),
+        ("listed.md", "---\ntags:\n  - guide\n---\nhello from a list\n"),
+        ("plain.md", "hello without tags\n"),
+        ("other.md", "---\ntags: [guide]\n---\ngoodbye\n"),
+    ];
+
+    #[test]
+    fn tag_filter_combines_with_text_terms() {
+        let dir = wiki(TAGGED);
+        let service = service(&dir, SearchConfig::new());
+        assert_eq!(paths(&service, "tag:guide hello"), vec!["listed.md", "tagged.md"]);
+        assert_eq!(paths(&service, "TAG:GUIDE"), vec!["listed.md", "other.md", "tagged.md"]);
+        assert_eq!(paths(&service, "tag:guide tag:rust"), vec!["tagged.md"]);
+    }
+
+    #[test]
+    fn unknown_tag_matches_nothing() {
+        let dir = wiki(TAGGED);
+        let service = service(&dir, SearchConfig::new());
+        assert!(paths(&service, "tag:missing hello").is_empty());
+        assert!(paths(&service, "tag:missing").is_empty());
+    }
+
+    /// Result paths of a query in relevance order
+    fn ranked(service: &SearchService, query: &str) -> Vec<String> {
+        service.search(query).unwrap().into_iter().map(|result| result.path).collect()
+    }
+
+    const WEIGHTED: &[(&str, &str)] = &[
+        ("title.md", "# Widget guide\n\nAbout things.\n"),
+        ("meta.md", "---\ntitle: Other\nkeywords: widget\n---\nSome text.\n\nA widget appears here.\n"),
+        ("body.md", "# Intro\n\nA widget in the body only.\n"),
+    ];
+
+    #[test]
+    fn title_only_mode_ignores_body_matches() {
+        let dir = wiki(WEIGHTED);
+        let service = service(&dir, SearchConfig { title_only: true, ..SearchConfig::new() });
+        assert_eq!(paths(&service, "widget"), vec!["meta.md", "title.md"]);
+        assert!(paths(&service, "body").is_empty());
+    }
+
+    #[test]
+    fn custom_weights_reorder_results() {
+        let dir = wiki(WEIGHTED);
+        assert_eq!(ranked(&service(&dir, SearchConfig::new()), "widget")[0], "title.md");
+
+        let config = SearchConfig {
+            title_phrase_weight: 0.0,
+            title_word_weight: 0.0,
+            heading_weight: 0.0,
+            frontmatter_weight: 50.0,
+            ..SearchConfig::new()
+        };
+        assert_eq!(ranked(&service(&dir, config), "widget")[0], "meta.md");
+    }
+
+    #[test]
+    fn relevance_threshold_drops_weak_matches() {
+        let dir = wiki(&[("strong.md", "# Widget\n\nAll about the widget.\n"), ("weak.md", "Some text.\n\nA widget.\n")]);
+        assert_eq!(paths(&service(&dir, SearchConfig::new()), "widget"), vec!["strong.md", "weak.md"]);
+        let strict = service(&dir, SearchConfig { min_relevance: 30.0, ..SearchConfig::new() });
+        assert_eq!(paths(&strict, "widget"), vec!["strong.md"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycles_are_indexed_once() {
+        let dir = wiki(FIXTURE);
+        std::os::unix::fs::symlink(dir.path().join("guide"), dir.path().join("guide/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("guide/root")).unwrap();
+
+        let service = service(&dir, SearchConfig::new());
+        assert_eq!(paths(&service, "ownership"), ["guide/intro.md", "rust.md"]);
+        assert_eq!(paths(&service, "toolchain"), ["guide/setup.md"]);
+    }
+
+    #[test]
+    fn non_utf8_files_do_not_stop_indexing() {
+        let dir = wiki(FIXTURE);
+        std::fs::write(dir.path().join("guide/binary.md"), [0xff, 0xfe, 0x00, 0x80]).unwrap();
+
+        let service = service(&dir, SearchConfig::new());
+        assert_eq!(paths(&service, "ownership"), ["guide/intro.md", "rust.md"]);
+    }
+
+    /// Excerpts of the only result for `query`
+    fn excerpts(service: &SearchService, query: &str) -> Vec<String> {
+        let results = service.search(query).unwrap();
+        assert_eq!(results.len(), 1, "one result for {query:?}");
+        results.into_iter().next().unwrap().excerpts
+    }
+
+    #[test]
+    fn excerpts_come_from_the_body_not_the_frontmatter() {
+        let dir = wiki(&[(
+            "page.md",
+            "---\ntitle: Deploying\ntags: [ops, release]\n---\n# Deploying\n\nRoll out the release with the deploy script.\n",
+        )]);
+        let service = service(&dir, SearchConfig::new());
+
+        let results = service.search("script").unwrap();
+        assert_eq!(results.len(), 1);
+        let excerpt = &results[0].excerpt;
+        assert!(excerpt.contains("deploy script"), "{excerpt}");
+        assert!(!excerpt.contains("---") && !excerpt.contains("tags:") && !excerpt.contains("title:"), "{excerpt}");
+        assert_eq!(results[0].excerpts.as_slice(), std::slice::from_ref(excerpt));
+    }
+
+    #[test]
+    fn frontmatter_matches_still_score_with_a_clean_excerpt() {
+        let dir = wiki(&[("page.md", "---\ntags: [kubernetes]\n---\n# Cluster\n\nNodes and pods.\n")]);
+        let service = service(&dir, SearchConfig::new());
+
+        let excerpt = &excerpts(&service, "kubernetes")[0];
+        assert!(!excerpt.contains("---") && !excerpt.contains("tags:"), "{excerpt}");
+    }
+
+    /// A long page mentioning "beacon" in three far-apart paragraphs
+    fn scattered() -> String {
+        let filler = "Nothing to see in this filler paragraph at all. ".repeat(8);
+        format!("# Lights\n\nFirst beacon on the hill.\n\n{filler}\n\nSecond beacon by the sea.\n\n{filler}\n\nThird beacon in the town.\n")
+    }
+
+    #[test]
+    fn scattered_matches_yield_separate_excerpts() {
+        let dir = wiki(&[("lights.md", &scattered())]);
+        let three = service(&dir, SearchConfig { max_excerpts: 3, ..SearchConfig::new() });
+        let found = excerpts(&three, "beacon");
+        assert_eq!(found.len(), 3, "{found:?}");
+        for (excerpt, place) in found.iter().zip(["hill", "sea", "town"]) {
+            assert!(excerpt.contains(place), "{excerpt:?} should mention {place}");
+        }
+
+        let two = service(&dir, SearchConfig { max_excerpts: 2, ..SearchConfig::new() });
+        assert_eq!(excerpts(&two, "beacon").len(), 2);
+    }
+
+    #[test]
+    fn one_excerpt_by_default() {
+        let dir = wiki(&[("lights.md", &scattered())]);
+        let service = service(&dir, SearchConfig::new());
+        let excerpts = excerpts(&service, "beacon");
+        assert_eq!(excerpts.len(), 1);
+        assert!(excerpts[0].contains("hill"));
+    }
+
+    #[test]
+    fn nearby_matches_share_one_excerpt() {
+        let dir = wiki(&[("lights.md", "# Lights\n\nA beacon and another beacon and a third beacon.\n")]);
+        let service = service(&dir, SearchConfig { max_excerpts: 3, ..SearchConfig::new() });
+        assert_eq!(excerpts(&service, "beacon").len(), 1);
+    }
+
+    const ANIMALS: &[(&str, &str)] = &[
+        ("cat.md", "# Pets\n\nThe cat sleeps all day.\n"),
+        ("strings.md", "# Strings\n\nConcatenate the two values.\n"),
+        ("capital.md", "# Names\n\nCat is the name of the shop.\n"),
+    ];
+
+    #[test]
+    fn substring_matching_by_default() {
+        let dir = wiki(ANIMALS);
+        let service = service(&dir, SearchConfig::new());
+        assert_eq!(paths(&service, "cat"), ["capital.md", "cat.md", "strings.md"]);
+    }
+
+    #[test]
+    fn whole_word_mode_skips_longer_words() {
+        let dir = wiki(ANIMALS);
+        let service = service(&dir, SearchConfig { whole_word: true, ..SearchConfig::new() });
+        assert_eq!(paths(&service, "cat"), ["capital.md", "cat.md"]);
+        assert_eq!(paths(&service, "concatenate"), ["strings.md"]);
+    }
+
+    #[test]
+    fn case_sensitive_mode_distinguishes_case() {
+        let dir = wiki(ANIMALS);
+        let service = service(&dir, SearchConfig { case_sensitive: true, whole_word: true, ..SearchConfig::new() });
+        assert_eq!(paths(&service, "cat"), ["cat.md"]);
+        assert_eq!(paths(&service, "Cat"), ["capital.md"]);
+    }
+
+    const TYPOS: &[(&str, &str)] = &[
+        ("search.md", "# Finding pages\n\nUse the search box at the top.\n"),
+        ("deploy.md", "# Deploying\n\nRun the deployment pipeline.\n"),
+    ];
+
+    #[test]
+    fn exact_mode_does_not_forgive_typos() {
+        let dir = wiki(TYPOS);
+        let service = service(&dir, SearchConfig::new());
+        assert!(paths(&service, "serch").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_mode_matches_words_a_few_edits_away() {
+        let dir = wiki(TYPOS);
+        let service = service(&dir, SearchConfig { fuzzy: true, ..SearchConfig::new() });
+        assert_eq!(paths(&service, "serch"), ["search.md"]);
+        assert_eq!(paths(&service, "deploymnet"), ["deploy.md"]);
+        // Short words allow a single edit only
+        assert!(paths(&service, "sxrcx").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_matches_rank_below_exact_ones() {
+        let dir = wiki(&[("exact.md", "# Notes\n\nThe search box.\n"), ("typo.md", "# Notes\n\nThe serch box.\n")]);
+        let service = service(&dir, SearchConfig { fuzzy: true, fuzzy_min_results: 5, ..SearchConfig::new() });
+        assert_eq!(ranked(&service, "search"), ["exact.md", "typo.md"]);
+    }
+
+    #[test]
+    fn fuzzy_matching_waits_for_too_few_exact_results() {
+        let dir = wiki(&[("exact.md", "# Notes\n\nThe search box.\n"), ("typo.md", "# Notes\n\nThe serch box.\n")]);
+        let service = service(&dir, SearchConfig { fuzzy: true, fuzzy_min_results: 1, ..SearchConfig::new() });
+        assert_eq!(paths(&service, "search"), ["exact.md"]);
+    }
+
+    /// Set the modification time of a wiki file to `secs` after the epoch
+    fn touch(dir: &TempDir, path: &str, secs: u64) {
+        let file = std::fs::File::options().write(true).open(dir.path().join(path)).unwrap();
+        file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(secs)).unwrap();
+    }
+
+    fn recent(service: &SearchService, limit: usize) -> Vec<String> {
+        service.index.recent_pages(limit).into_iter().map(|page| url_path(&page.path)).collect()
+    }
+
+    #[test]
+    fn touching_a_page_moves_it_to_the_top_of_the_recent_list() {
+        let dir = wiki(FIXTURE);
+        for (n, (path, _)) in FIXTURE.iter().enumerate() {
+            touch(&dir, path, 1_000 + n as u64);
+        }
+        let service = service(&dir, SearchConfig::new());
+        assert_eq!(recent(&service, 10), ["guide/setup.md", "guide/intro.md", "rust.md", "index.md"]);
+        assert_eq!(service.index.recent_pages(1)[0].title, "Setup");
+
+        touch(&dir, "index.md", 5_000);
+        service.rebuild_index().unwrap();
+        assert_eq!(recent(&service, 2), ["index.md", "guide/setup.md"]);
+    }
+
+    #[test]
+    fn hidden_drafts_are_not_recent() {
+        let dir = wiki(&[("draft.md", "---\ndraft: true\n---\n# Draft\n"), ("page.md", "# Page\n")]);
+        assert_eq!(recent(&service(&dir, SearchConfig::new()), 10), ["page.md"]);
+    }
+
+    #[test]
+    fn suggestions_come_from_the_index() {
+        let dir = wiki(FIXTURE);
+        let service = service(&dir, SearchConfig { suggestion_count: 2, ..SearchConfig::new() });
+        // Files gone since the last rebuild are still suggested; nothing is read per call
+        std::fs::remove_dir_all(dir.path().join("guide")).unwrap();
+        assert_eq!(service.suggestions().len(), 2);
+
+        let config = SearchConfig { suggestion_count: 0, ..SearchConfig::new() };
+        let none = SearchService::new(service.file_service.clone(), service.index.clone(), config);
+        assert!(none.suggestions().is_empty());
+    }
+
+    const DEEP: &[(&str, &str)] = &[
+        ("top.md", "marker at the top\n"),
+        ("a/one.md", "marker one level down\n"),
+        ("a/b/two.md", "marker two levels down\n"),
+    ];
+
+    #[test]
+    fn depth_one_searches_only_top_level_pages() {
+        let dir = wiki(DEEP);
+        let service = service(&dir, SearchConfig { max_search_depth: 1, ..SearchConfig::new() });
+        assert_eq!(paths(&service, "marker"), ["top.md"]);
+    }
+
+    #[test]
+    fn depth_limits_count_directory_levels() {
+        let dir = wiki(DEEP);
+        let service = service(&dir, SearchConfig { max_search_depth: 2, ..SearchConfig::new() });
+        assert_eq!(paths(&service, "marker"), ["a/one.md", "top.md"]);
+    }
+
+    #[test]
+    fn search_depth_is_unlimited_by_default() {
+        let dir = wiki(DEEP);
+        assert_eq!(paths(&service(&dir, SearchConfig::new()), "marker"), ["a/b/two.md", "a/one.md", "top.md"]);
+    }
+
+    #[test]
+    fn the_index_remembers_which_pages_are_hidden_drafts() {
+        let dir = wiki(&[("guide/intro.md", "# Intro"), ("guide/plan.md", "---\ndraft: true\n---\n# Plan")]);
+        let service = service(&dir, SearchConfig::default());
+        let modified = |path: &str| std::fs::metadata(dir.path().join(path)).unwrap().modified().unwrap();
+
+        assert_eq!(service.index.is_hidden_draft("guide/plan.md", modified("guide/plan.md")), Some(true));
+        assert_eq!(service.index.is_hidden_draft("guide/intro.md", modified("guide/intro.md")), Some(false));
+        // Changed or unknown pages have to be read again
+        assert_eq!(service.index.is_hidden_draft("guide/plan.md", UNIX_EPOCH), None);
+        assert_eq!(service.index.is_hidden_draft("guide/new.md", modified("guide/intro.md")), None);
+    }
+}
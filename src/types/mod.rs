@@ -1,11 +1,80 @@
 use std::sync::Arc;
 use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::components::{FabActionTemplate, SidebarCache, TemplateComponent};
+use crate::config::{Config, MarkdownConfig, SearchConfig};
+use crate::metrics::Metrics;
+use crate::services::{ContentCache, SearchIndex};
+use crate::utils::PathFilter;
 
 /// Application state shared across all handlers
 #[derive(Clone)]
 pub struct AppState {
+    /// Site name shown in page titles
+    pub site_title: String,
     pub base_dir: Arc<PathBuf>,
     pub static_dir: Arc<PathBuf>,
+    /// Theme directory whose files are served in preference to `static_dir`
+    pub template_dir: Option<Arc<PathBuf>>,
+    pub templates: Arc<TemplateComponent>,
+    pub sidebar_cache: Arc<SidebarCache>,
+    pub search_index: Arc<SearchIndex>,
+    pub search_config: SearchConfig,
+    /// Paths hidden from navigation, listings and search
+    pub exclude: PathFilter,
+    /// Longest request path accepted, in bytes
+    pub max_path_len: usize,
+    /// Largest file read into memory to render or serve
+    pub max_file_bytes: u64,
+    /// Serve, list and index pages marked `draft: true`
+    pub show_drafts: bool,
+    /// Page sources kept in memory while unchanged; `None` when disabled
+    pub content_cache: Option<Arc<ContentCache>>,
+    /// Sub-directory levels expanded in the sidebar
+    pub nav_depth: usize,
+    /// Page actions offered in the floating action bar
+    pub fab_actions: Vec<FabActionTemplate>,
+    pub markdown_config: MarkdownConfig,
+    /// Request and search counters served at `/metrics`
+    pub metrics: Arc<Metrics>,
+}
+
+impl AppState {
+    /// Build the shared state for a configuration around an existing search index
+    pub fn from_config(config: &Config, search_index: Arc<SearchIndex>, metrics: Arc<Metrics>) -> Self {
+        let exclude = PathFilter::new(&config.exclude);
+        let show_drafts = config.show_drafts || config.dev_mode;
+        Self {
+            base_dir: config.base_dir.clone(),
+            static_dir: config.static_dir.clone(),
+            template_dir: config.template_dir.clone().map(Arc::new),
+            templates: Arc::new(
+                TemplateComponent::with_template(config.template_path(), config.dev_mode)
+                    .with_site_title(&config.site_title)
+                    .with_theme(&config.theme, config.theme_toggle)
+                    .with_recent_pages(search_index.clone(), config.recent_pages),
+            ),
+            site_title: config.site_title.clone(),
+            sidebar_cache: Arc::new(SidebarCache::new()),
+            search_index,
+            search_config: config.search.clone(),
+            exclude,
+            max_path_len: config.max_path_len,
+            max_file_bytes: config.max_file_bytes,
+            show_drafts,
+            content_cache: (!config.dev_mode && config.content_cache_bytes > 0)
+                .then(|| Arc::new(ContentCache::new(config.content_cache_bytes))),
+            nav_depth: config.nav_depth,
+            fab_actions: config.fab_actions
+                .iter()
+                .filter_map(|spec| FabActionTemplate::parse(spec))
+                .chain((!config.edit_url.is_empty()).then(|| FabActionTemplate::edit_source(&config.edit_url)))
+                .collect(),
+            markdown_config: config.markdown.clone(),
+            metrics,
+        }
+    }
 }
 
 /// Directory entry information
@@ -16,12 +85,33 @@ pub struct DirEntry {
     pub path: PathBuf,
 }
 
+/// Directory entry with the size and modification time of the file
+#[derive(Debug, Clone)]
+pub struct DirEntryMeta {
+    pub entry: DirEntry,
+    /// Size in bytes
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// Page listed among the recently updated pages
+#[derive(Debug, Clone)]
+pub struct RecentPage {
+    /// Markdown file relative to the wiki root
+    pub path: PathBuf,
+    pub title: String,
+    pub modified: SystemTime,
+}
+
 /// Search result information
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     pub title: String,
     pub path: String,
     pub excerpt: String,
+    /// Passages around separate matches, starting with the first; holds just
+    /// `excerpt` unless more excerpts per result are configured
+    pub excerpts: Vec<String>,
     pub relevance: f32,
 }
 
@@ -31,6 +121,12 @@ pub struct MarkdownResult {
     pub html: String,
     pub toc: String,
     pub title: Option<String>,
+    /// Plain-text summary for `<meta name="description">` and link previews
+    pub description: Option<String>,
+    /// Words in the body, not counting frontmatter and code blocks
+    pub word_count: usize,
+    /// Estimated reading time in whole minutes; 0 for pages read in under a minute
+    pub reading_minutes: usize,
 }
 
 /// Template rendering context
@@ -41,4 +137,6 @@ pub struct TemplateContext {
     pub sidebar: String,
     pub fab: String,
     pub toc: Option<String>,
+    /// Page summary emitted as description and OpenGraph meta tags
+    pub description: Option<String>,
 }
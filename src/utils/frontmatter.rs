@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+/// Key/value metadata from a `---` delimited block at the top of a page
+///
+/// Only the simple subset used by wiki pages is understood: `key: value`
+/// scalars, inline lists (`tags: [a, b]`) and block lists (`- a` lines under a
+/// key). Each key keeps its values in order; scalars hold a single value.
+#[derive(Debug, Clone, Default)]
+pub struct Frontmatter {
+    fields: HashMap<String, Vec<String>>,
+}
+
+impl Frontmatter {
+    /// Parse the frontmatter block, returning an empty set when there is none
+    pub fn parse(content: &str) -> Self {
+        let mut lines = content.lines();
+        let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+
+        if lines.next().map(str::trim_end) != Some("---") {
+            return Self { fields };
+        }
+
+        let mut current_key: Option<String> = None;
+        for line in lines {
+            if line.trim_end() == "---" {
+                return Self { fields };
+            }
+
+            let trimmed = line.trim();
+            if let Some(item) = trimmed.strip_prefix("- ") {
+                // Block list item belonging to the last key
+                if let Some(key) = &current_key {
+                    fields.entry(key.clone()).or_default().push(Self::unquote(item).to_string());
+                }
+                continue;
+            }
+
+            let Some((key, value)) = trimmed.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_string();
+            let value = value.trim();
+            let values = if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                inner
+                    .split(',')
+                    .map(|item| Self::unquote(item.trim()).to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            } else if value.is_empty() {
+                Vec::new()
+            } else {
+                vec![Self::unquote(value).to_string()]
+            };
+            fields.insert(key.clone(), values);
+            current_key = Some(key);
+        }
+
+        // Unterminated block: not frontmatter after all
+        Self::default()
+    }
+
+    /// Text between the opening and closing `---` lines, if the page has frontmatter
+    pub fn block(content: &str) -> Option<&str> {
+        let rest = content.strip_prefix("---")?;
+        let rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n'))?;
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == "---" {
+                return Some(&rest[..offset]);
+            }
+            offset += line.len();
+        }
+        None
+    }
+
+    /// Content after the frontmatter block, or all of it when there is none
+    pub fn body(content: &str) -> &str {
+        let Some(block) = Self::block(content) else {
+            return content;
+        };
+        // The block is a slice of `content`, so the closing line starts right after it
+        let block_end = block.as_ptr() as usize - content.as_ptr() as usize + block.len();
+        let rest = &content[block_end..];
+        match rest.find('\n') {
+            Some(newline) => &rest[newline + 1..],
+            None => "",
+        }
+    }
+
+    /// Whether the page is marked `draft: true`
+    pub fn is_draft(&self) -> bool {
+        self.get("draft").is_some_and(|value| value.trim().eq_ignore_ascii_case("true"))
+    }
+
+    /// Scalar value of a key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key)?.first().map(String::as_str)
+    }
+
+    /// List value of a key; a scalar is split on commas
+    pub fn list(&self, key: &str) -> Vec<String> {
+        match self.fields.get(key).map(Vec::as_slice) {
+            Some([single]) => single
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect(),
+            Some(values) => values.to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Strip one pair of matching quotes
+    fn unquote(value: &str) -> &str {
+        for quote in ['"', '\''] {
+            if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+                return inner;
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draft_flag_must_be_true() {
+        let draft = |md: &str| Frontmatter::parse(md).is_draft();
+        assert!(draft("---\ndraft: true\n---\n# Page\n"));
+        assert!(draft("---\ndraft: \"True\"\n---\n"));
+        assert!(!draft("---\ndraft: false\n---\n"));
+        assert!(!draft("---\ntitle: Page\n---\n"));
+        // Outside a frontmatter block the line is just text
+        assert!(!draft("# Page\n\ndraft: true\n"));
+        assert!(!draft("---\ndraft: true\n"));
+    }
+}
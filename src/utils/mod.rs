@@ -1,98 +1,330 @@
-use std::path::Path;
+use std::path::{Component, Path};
+use std::time::SystemTime;
 use time::OffsetDateTime;
 
+use crate::errors::WikiError;
+
+mod frontmatter;
+mod path_filter;
+
+pub use frontmatter::Frontmatter;
+pub use path_filter::PathFilter;
+
 /// Escape HTML special characters
 pub fn escape_html(text: &str) -> String {
-    text.replace("&", "&amp;")
-        .replace("<", "&lt;")
-        .replace(">", "&gt;")
-        .replace("\"", "&quot;")
-        .replace("'", "&#39;")
+    let mut escaped = String::with_capacity(text.len() + text.len() / 8);
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Escape text for use inside a JSON string literal
+pub fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + text.len() / 8);
+    for ch in text.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
 }
 
 /// Escape HTML attribute values
 pub fn escape_attr(text: &str) -> String {
-    text.replace("&", "&amp;")
-        .replace("<", "&lt;")
-        .replace(">", "&gt;")
-        .replace("\"", "&quot;")
-        .replace("'", "&#39;")
+    escape_html(text)
 }
 
 /// Generate last modified metadata HTML
 pub fn last_modified_html(path: &Path) -> String {
     match std::fs::metadata(path).and_then(|m| m.modified()) {
-        Ok(mtime) => {
-            match mtime.duration_since(std::time::UNIX_EPOCH) {
-                Ok(dur) => {
-                    let secs = dur.as_secs() as i64;
-                    let datetime = OffsetDateTime::from_unix_timestamp(secs).ok();
-                    if let Some(dt) = datetime {
-                        let fmt = time::format_description::well_known::Rfc3339;
-                        if let Ok(s) = dt.format(&fmt) {
-                            return format!("<p class=\"meta\">Last modified: {}</p>", escape_html(&s));
-                        }
-                    }
-                    String::new()
-                }
-                Err(_) => String::new(),
-            }
-        }
+        Ok(mtime) => match format_rfc3339(mtime) {
+            Some(s) => format!("<p class=\"meta\">Last modified: {}</p>", escape_html(&s)),
+            None => String::new(),
+        },
         Err(_) => String::new(),
     }
 }
 
+/// Format a time as an RFC 3339 UTC timestamp to the second
+pub fn format_rfc3339(time: SystemTime) -> Option<String> {
+    let secs = time.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs() as i64;
+    let datetime = OffsetDateTime::from_unix_timestamp(secs).ok()?;
+    datetime.format(&time::format_description::well_known::Rfc3339).ok()
+}
+
+/// Format a byte count for display, e.g. `512 B` or `1.5 KiB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Join path components with `/` so URLs are platform independent
+pub fn url_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// URL of a markdown page, e.g. `/guide/intro` for `guide/intro.md`
+///
+/// `index.md` and `README.md` are served as their directory, e.g. `/guide/`.
+pub fn page_url(path: &Path) -> String {
+    let dir = path.parent().map(url_path).unwrap_or_default();
+    let dir_url = if dir.is_empty() { String::new() } else { format!("/{}", dir) };
+    match path.file_name().and_then(|name| name.to_str()) {
+        Some("index.md" | "README.md") => format!("{}/", dir_url),
+        _ => {
+            let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+            format!("{}/{}", dir_url, stem)
+        }
+    }
+}
+
+/// Percent-encode each segment of a URL path, keeping the `/` separators
+///
+/// Unreserved characters and the sub-delimiters allowed in a path segment are
+/// kept; everything else, including non-ASCII bytes, becomes `%XX`.
+pub fn encode_url_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b'!' | b'$' | b'&'
+            | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':' | b'@' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
 /// Normalize request path
+///
+/// Outer and repeated slashes and `.` segments are dropped, and `..` removes
+/// the segment before it. A `..` that would climb above the root is kept so
+/// that `ensure_safe_path` rejects the path.
 pub fn normalize_path(path: &str) -> String {
-    let mut normalized = path.trim_matches('/').to_string();
-    if normalized.is_empty() {
-        normalized = "".to_string();
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." if segments.last().is_some_and(|last| *last != "..") => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
     }
-    normalized
+    segments.join("/")
 }
 
-/// Parse query parameter with basic URL decoding
+/// Reject request paths that could escape the serving root.
+///
+/// Expects the decoded, normalized path. Both `/` and `\` are treated as
+/// separators so Windows-style `..\` segments are caught on every platform.
+pub fn ensure_safe_path(path: &str) -> Result<(), WikiError> {
+    if path.starts_with('/') || path.starts_with('\\') {
+        return Err(WikiError::InvalidPath);
+    }
+
+    if path.split(['/', '\\']).any(|segment| segment == "..") {
+        return Err(WikiError::InvalidPath);
+    }
+
+    let unsafe_component = Path::new(path).components().any(|c| {
+        matches!(c, Component::ParentDir | Component::RootDir | Component::Prefix(_))
+    });
+    if unsafe_component {
+        return Err(WikiError::InvalidPath);
+    }
+
+    Ok(())
+}
+
+/// Parse query parameter with URL decoding
 pub fn parse_query_param(query: &str, param: &str) -> String {
     let query_string = query.trim_start_matches('?');
     for pair in query_string.split('&') {
-        if let Some((key, value)) = pair.split_once('=') {
-            if key == param {
-                // Basic URL decoding (replace %20 with space, etc.)
-                return value.replace("%20", " ")
-                    .replace("%21", "!")
-                    .replace("%22", "\"")
-                    .replace("%23", "#")
-                    .replace("%24", "$")
-                    .replace("%25", "%")
-                    .replace("%26", "&")
-                    .replace("%27", "'")
-                    .replace("%28", "(")
-                    .replace("%29", ")")
-                    .replace("%2A", "*")
-                    .replace("%2B", "+")
-                    .replace("%2C", ",")
-                    .replace("%2D", "-")
-                    .replace("%2E", ".")
-                    .replace("%2F", "/")
-                    .replace("%3A", ":")
-                    .replace("%3B", ";")
-                    .replace("%3C", "<")
-                    .replace("%3D", "=")
-                    .replace("%3E", ">")
-                    .replace("%3F", "?")
-                    .replace("%40", "@")
-                    .replace("%5B", "[")
-                    .replace("%5C", "\\")
-                    .replace("%5D", "]")
-                    .replace("%5E", "^")
-                    .replace("%5F", "_")
-                    .replace("%60", "`")
-                    .replace("%7B", "{")
-                    .replace("%7C", "|")
-                    .replace("%7D", "}")
-                    .replace("%7E", "~");
-            }
+        if let Some((key, value)) = pair.split_once('=')
+            && key == param
+        {
+            return percent_decode(value, true);
         }
     }
     String::new()
 }
+
+/// Decode `%XX` escapes in a URL path, leaving `+` as is
+pub fn decode_url_path(path: &str) -> String {
+    percent_decode(path, false)
+}
+
+/// Decode a URL component in a single pass.
+///
+/// `%XX` escapes become raw bytes, and `+` becomes a space when
+/// `plus_as_space` is set, as in form-encoded queries. Malformed or
+/// truncated escapes are kept literally, and invalid UTF-8 is replaced
+/// lossily once at the end.
+fn percent_decode(value: &str, plus_as_space: bool) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' if plus_as_space => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b'%' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(hi), Some(lo)) => {
+                        decoded.push(hi << 4 | lo);
+                        i += 3;
+                    }
+                    _ => {
+                        decoded.push(b'%');
+                        i += 1;
+                    }
+                }
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Value of a single ASCII hex digit
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn safe_paths_are_accepted() {
+        assert!(ensure_safe_path("").is_ok());
+        assert!(ensure_safe_path("guide/intro.md").is_ok());
+        assert!(ensure_safe_path("notes/..hidden.md").is_ok());
+    }
+
+    #[test]
+    fn traversal_and_absolute_paths_are_rejected() {
+        for path in ["..", "../Cargo.toml", "guide/../../etc/passwd", "..\\Cargo.toml", "/etc/passwd", "\\windows"] {
+            assert!(
+                matches!(ensure_safe_path(path), Err(WikiError::InvalidPath)),
+                "{path:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn query_params_are_percent_decoded() {
+        assert_eq!(parse_query_param("q=a%2Bb", "q"), "a+b");
+        assert_eq!(parse_query_param("q=%E2%9C%93", "q"), "✓");
+        assert_eq!(parse_query_param("q=hello+world", "q"), "hello world");
+        assert_eq!(parse_query_param("q=100%25", "q"), "100%");
+    }
+
+    #[test]
+    fn query_param_lookup_matches_whole_keys() {
+        assert_eq!(parse_query_param("?page=2&q=x", "q"), "x");
+        assert_eq!(parse_query_param("qq=1", "q"), "");
+        assert_eq!(parse_query_param("", "q"), "");
+    }
+
+    #[test]
+    fn malformed_escapes_are_kept_literally() {
+        assert_eq!(parse_query_param("q=50%", "q"), "50%");
+        assert_eq!(parse_query_param("q=%zz", "q"), "%zz");
+    }
+
+    #[test]
+    fn escape_html_matches_the_chained_replacements() {
+        let input = "a<b>&\"c\" 'd' — ünï<код>&amp; 🚀";
+        let chained = input
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\'', "&#39;");
+        assert_eq!(escape_html(input), chained);
+    }
+
+    #[test]
+    fn html_and_attribute_escaping_cover_all_five_characters() {
+        for escape in [escape_html, escape_attr] {
+            assert_eq!(escape("&<>\"'"), "&amp;&lt;&gt;&quot;&#39;");
+        }
+    }
+
+    #[test]
+    fn url_paths_use_forward_slashes() {
+        let nested: PathBuf = ["guide", "setup", "intro.md"].iter().collect();
+        assert_eq!(url_path(&nested), "guide/setup/intro.md");
+        assert_eq!(url_path(Path::new("intro.md")), "intro.md");
+        assert_eq!(page_url(&nested), "/guide/setup/intro");
+    }
+
+    #[test]
+    fn url_paths_are_percent_encoded_per_segment() {
+        assert_eq!(encode_url_path("/guide/intro/"), "/guide/intro/");
+        assert_eq!(encode_url_path("/my notes/a?b#c/"), "/my%20notes/a%3Fb%23c/");
+        assert_eq!(encode_url_path("/café/100%"), "/caf%C3%A9/100%25");
+        assert_eq!(encode_url_path("/a+b,c(d)"), "/a+b,c(d)");
+        assert_eq!(decode_url_path(&encode_url_path("/x y/ü%")), "/x y/ü%");
+    }
+
+    #[test]
+    fn normalize_path_collapses_segments() {
+        assert_eq!(normalize_path("//guide///intro/"), "guide/intro");
+        assert_eq!(normalize_path("a/./b"), "a/b");
+        assert_eq!(normalize_path("a/../b"), "b");
+        assert_eq!(normalize_path("a/b/../../c/./"), "c");
+        assert_eq!(normalize_path("/"), "");
+        assert_eq!(normalize_path("..hidden/.x"), "..hidden/.x");
+    }
+
+    #[test]
+    fn normalize_path_keeps_climbs_above_the_root_for_rejection() {
+        for path in ["..", "../a", "a/../../b", "./../a"] {
+            let normalized = normalize_path(path);
+            assert!(normalized.starts_with(".."), "{path:?} -> {normalized:?}");
+            assert!(ensure_safe_path(&normalized).is_err(), "{path:?}");
+        }
+        assert_eq!(normalize_path("a/../../b"), "../b");
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::{Component, Path};
+
+/// Glob patterns for wiki paths that are hidden from navigation, listings and search
+///
+/// A pattern without `/` matches any single path segment (`drafts`, `*.tmp`);
+/// a pattern with `/` matches the whole path relative to the wiki root
+/// (`guide/old/*`, `assets/**`). `*` and `?` stay within a segment and `**`
+/// spans any number of segments. Excluding a directory hides everything below it.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    patterns: Vec<String>,
+}
+
+impl PathFilter {
+    /// Create a filter from glob patterns, ignoring blank ones
+    pub fn new(patterns: &[String]) -> Self {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| pattern.trim().trim_matches('/').to_string())
+            .filter(|pattern| !pattern.is_empty())
+            .collect();
+        Self { patterns }
+    }
+
+    /// Whether no patterns are configured
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Check a path relative to the wiki root against the patterns
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+
+        let segments: Vec<String> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(segment) => Some(segment.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        self.patterns.iter().any(|pattern| {
+            if pattern.contains('/') {
+                // Match the path or any of its ancestors
+                let parts: Vec<&str> = pattern.split('/').collect();
+                (1..=segments.len()).any(|len| glob_segments(&parts, &segments[..len]))
+            } else {
+                segments.iter().any(|segment| glob_segment(pattern, segment))
+            }
+        })
+    }
+}
+
+/// Match path segments against pattern segments, where `**` spans any number of segments
+fn glob_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| glob_segments(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path_rest)) => {
+                glob_segment(first, segment) && glob_segments(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Match one segment against a pattern with `*` and `?` wildcards
+fn glob_segment(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_chars(&pattern, &text)
+}
+
+/// Wildcard matching over characters
+fn glob_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| glob_chars(rest, &text[skip..])),
+        Some(('?', rest)) => !text.is_empty() && glob_chars(rest, &text[1..]),
+        Some((ch, rest)) => text.first() == Some(ch) && glob_chars(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(patterns: &[&str]) -> PathFilter {
+        PathFilter::new(&patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn bare_patterns_match_any_segment() {
+        let filter = filter(&["drafts", "*.tmp"]);
+        assert!(filter.is_excluded(Path::new("drafts")));
+        assert!(filter.is_excluded(Path::new("guide/drafts/idea.md")));
+        assert!(filter.is_excluded(Path::new("notes/scratch.tmp")));
+        assert!(!filter.is_excluded(Path::new("guide/intro.md")));
+        assert!(!filter.is_excluded(Path::new("drafts-old/page.md")));
+    }
+
+    #[test]
+    fn slashed_patterns_match_from_the_root() {
+        let filter = filter(&["assets/**", "guide/**/private"]);
+        assert!(filter.is_excluded(Path::new("assets/logo.png")));
+        assert!(!filter.is_excluded(Path::new("guide/assets/logo.png")));
+        assert!(filter.is_excluded(Path::new("guide/private/a.md")));
+        assert!(filter.is_excluded(Path::new("guide/x/y/private")));
+        assert!(!filter.is_excluded(Path::new("private")));
+    }
+
+    #[test]
+    fn outer_slashes_are_trimmed() {
+        let filter = filter(&["/assets/"]);
+        assert!(filter.is_excluded(Path::new("assets/logo.png")));
+        assert!(filter.is_excluded(Path::new("guide/assets/logo.png")));
+    }
+
+    #[test]
+    fn blank_patterns_are_ignored() {
+        assert!(filter(&["", " / "]).is_empty());
+        assert!(!filter(&[""]).is_excluded(Path::new("anything")));
+    }
+}
@@ -0,0 +1,104 @@
+//! JSON page and search APIs
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, TestWiki};
+use serde_json::Value;
+
+fn json(body: &str) -> Value {
+    serde_json::from_str(body).unwrap_or_else(|e| panic!("invalid JSON {body:?}: {e}"))
+}
+
+#[tokio::test]
+async fn page_api_returns_the_rendered_page() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro\n\n## Setup\n\nHello **world**.\n");
+
+    let response = get(&wiki.app(), "/api/page/guide/intro").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("application/json"));
+    let page = json(&response.text());
+    assert_eq!(page["title"], "Intro");
+    assert_eq!(page["path"], "guide/intro");
+    assert!(page["html"].as_str().unwrap().contains("<strong>world</strong>"));
+    assert!(page["toc"].as_str().unwrap().contains("Setup"));
+}
+
+#[tokio::test]
+async fn page_api_returns_a_json_404() {
+    let wiki = TestWiki::new();
+
+    let response = get(&wiki.app(), "/api/page/missing").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.header("content-type"), Some("application/json"));
+    assert!(json(&response.text())["error"].is_string());
+}
+
+#[tokio::test]
+async fn search_api_returns_results_with_a_total() {
+    let wiki = TestWiki::new();
+    wiki.page("rust.md", "# Rust\n\nOwnership and borrowing.\n");
+    wiki.page("go.md", "# Go\n\nGoroutines.\n");
+
+    let response = get(&wiki.app(), "/api/search?q=ownership").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("application/json"));
+    let body = json(&response.text());
+    assert_eq!(body["total"], 1);
+    let result = &body["results"][0];
+    assert_eq!(result["title"], "Rust");
+    assert_eq!(result["path"], "rust.md");
+    assert!(result["excerpt"].as_str().unwrap().contains("Ownership"));
+    assert!(result["excerpts"].is_array());
+    assert!(result["relevance"].as_f64().unwrap() > 0.0);
+}
+
+#[tokio::test]
+async fn search_api_returns_an_empty_array_for_an_empty_query() {
+    let wiki = TestWiki::new();
+    wiki.page("rust.md", "# Rust\n");
+    let app = wiki.app();
+
+    for uri in ["/api/search", "/api/search?q=", "/api/search?q=%20%20"] {
+        let response = get(&app, uri).await;
+        assert_eq!(response.status, StatusCode::OK, "{uri}");
+        assert_eq!(json(&response.text()), serde_json::json!({ "total": 0, "results": [] }), "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn search_api_truncates_long_queries() {
+    let wiki = TestWiki::new();
+    wiki.page("long.md", &"a".repeat(1000));
+
+    // Only the first 1000 characters are searched, so the trailing "zzz" is ignored
+    let response = get(&wiki.app(), &format!("/api/search?q={}zzz", "a".repeat(1000))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(json(&response.text())["total"], 1);
+}
+
+
+#[tokio::test]
+async fn linkcheck_reports_dangling_internal_links() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "[Setup](setup) [Gone](missing#top) [Web](https://example.com/nope) [Up](/)\n")
+        .page("guide/setup.md", "[Back](/guide/intro) [Search](/search?q=x) [Source](/raw/guide/setup.md)\n");
+
+    let response = get(&wiki.app(), "/api/linkcheck").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let report = json(&response.text());
+    assert_eq!(report["total"], 1);
+    assert_eq!(report["pages"].as_array().unwrap().len(), 1);
+    assert_eq!(report["pages"][0]["path"], "guide/intro.md");
+    assert_eq!(report["pages"][0]["broken"], serde_json::json!(["/guide/missing"]));
+}
+
+#[tokio::test]
+async fn linkcheck_of_a_healthy_wiki_is_empty() {
+    let wiki = TestWiki::new();
+    wiki.page("index.md", "[Guide](guide/) [Intro](guide/intro.md)\n").page("guide/intro.md", "# Intro\n");
+
+    let report = json(&get(&wiki.app(), "/api/linkcheck").await.text());
+    assert_eq!(report, serde_json::json!({"total": 0, "pages": []}));
+}
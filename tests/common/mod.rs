@@ -0,0 +1,138 @@
+//! Fixture wiki and request helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use axum::Router;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+use strata::config::Config;
+use strata::handlers::router;
+use strata::metrics::Metrics;
+use strata::services::{FileService, SearchIndex, SearchService};
+use strata::types::AppState;
+
+/// A wiki and static directory in a temporary directory, removed on drop
+pub struct TestWiki {
+    pub dir: TempDir,
+    pub config: Config,
+}
+
+impl TestWiki {
+    /// Empty `wiki/` and `static/` directories with the default configuration
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let mut config = Config::new();
+        config.base_dir = Arc::new(dir.path().join("wiki"));
+        config.static_dir = Arc::new(dir.path().join("static"));
+        std::fs::create_dir_all(config.base_dir.as_ref()).unwrap();
+        std::fs::create_dir_all(config.static_dir.as_ref()).unwrap();
+        Self { dir, config }
+    }
+
+    pub fn wiki_dir(&self) -> PathBuf {
+        self.config.base_dir.as_ref().clone()
+    }
+
+    pub fn static_dir(&self) -> PathBuf {
+        self.config.static_dir.as_ref().clone()
+    }
+
+    /// Write a wiki file, creating its parent directories
+    pub fn page(&self, path: &str, content: &str) -> &Self {
+        write(&self.wiki_dir().join(path), content.as_bytes());
+        self
+    }
+
+    /// Write a static asset, creating its parent directories
+    pub fn asset(&self, path: &str, content: &[u8]) -> &Self {
+        write(&self.static_dir().join(path), content);
+        self
+    }
+
+    /// Use the repository's own `base.html` instead of the built-in shell
+    pub fn with_repo_template(mut self) -> Self {
+        self.config.template_dir = Some(Path::new(env!("CARGO_MANIFEST_DIR")).join("static/html"));
+        self
+    }
+
+    /// The application state for the current configuration, with the search index built
+    pub fn state(&self) -> AppState {
+        let index = Arc::new(SearchIndex::new());
+        let state = AppState::from_config(&self.config, index.clone(), Arc::new(Metrics::new()));
+        SearchService::new(
+            FileService::new(self.wiki_dir())
+                .with_exclude(state.exclude.clone())
+                .with_max_file_bytes(self.config.max_file_bytes),
+            index,
+            self.config.search.clone(),
+        )
+        .with_drafts(state.show_drafts)
+        .rebuild_index()
+        .expect("build search index");
+        state
+    }
+
+    /// The full router for the current configuration
+    pub fn app(&self) -> Router {
+        router(self.state(), Duration::from_secs(1))
+    }
+}
+
+pub fn write(path: &Path, content: &[u8]) {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    std::fs::write(path, content).unwrap();
+}
+
+/// A response with its body read to the end
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+/// Send a request through the router
+pub async fn send(app: &Router, request: Request<Body>) -> TestResponse {
+    let response = app.clone().oneshot(request).await.expect("infallible router");
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("read body").to_vec();
+    TestResponse { status, headers, body }
+}
+
+/// Build a request with the given method, URI and headers
+pub fn request(method: Method, uri: &str, headers: &[(&str, &str)]) -> Request<Body> {
+    let mut builder = Request::builder().method(method).uri(uri);
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+/// GET a URI
+pub async fn get(app: &Router, uri: &str) -> TestResponse {
+    send(app, request(Method::GET, uri, &[])).await
+}
+
+/// GET a URI with extra request headers
+pub async fn get_with(app: &Router, uri: &str, headers: &[(&str, &str)]) -> TestResponse {
+    send(app, request(Method::GET, uri, headers)).await
+}
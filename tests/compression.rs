@@ -0,0 +1,38 @@
+//! Response compression through the full router
+
+mod common;
+
+use std::io::Read;
+
+use axum::http::StatusCode;
+use common::{get, get_with, TestWiki};
+
+#[tokio::test]
+async fn pages_decompress_to_the_uncompressed_response() {
+    let wiki = TestWiki::new();
+    wiki.page("long.md", &format!("# Long\n\n{}\n", "Some words repeated. ".repeat(500)));
+    let app = wiki.app();
+
+    let plain = get(&app, "/long").await;
+    let compressed = get_with(&app, "/long", &[("accept-encoding", "gzip")]).await;
+    assert_eq!(compressed.status, StatusCode::OK);
+    assert_eq!(compressed.header("content-encoding"), Some("gzip"));
+    assert!(compressed.body.len() < plain.body.len());
+
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(compressed.body.as_slice()).read_to_end(&mut decoded).unwrap();
+    assert_eq!(decoded, plain.body);
+}
+
+#[tokio::test]
+async fn images_pass_through_uncompressed() {
+    let wiki = TestWiki::new();
+    let png: Vec<u8> = (0..8192).map(|i| (i % 7) as u8).collect();
+    wiki.asset("logo.png", &png);
+
+    let response = get_with(&wiki.app(), "/static/logo.png", &[("accept-encoding", "gzip, deflate")]).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("image/png"));
+    assert_eq!(response.header("content-encoding"), None);
+    assert_eq!(response.body, png);
+}
@@ -0,0 +1,77 @@
+//! Pages marked `draft: true` are hidden unless drafts are shown
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, TestWiki};
+
+const DRAFT: &str = "---\ndraft: true\n---\n# Plan\n\nsecret sauce\n";
+
+fn wiki() -> TestWiki {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro\n\nsecret sauce\n").page("guide/plan.md", DRAFT);
+    wiki
+}
+
+#[tokio::test]
+async fn drafts_leave_the_sidebar_search_and_sitemap() {
+    let wiki = wiki();
+    let app = wiki.app();
+
+    let page = get(&app, "/guide/intro").await.text();
+    assert!(page.contains("href=\"/guide/intro\""), "{page}");
+    assert!(!page.contains("/guide/plan"), "{page}");
+
+    let results = get(&app, "/search?q=secret").await.text();
+    assert!(results.contains("Found 1 result"), "{results}");
+    assert!(!results.contains("/guide/plan"));
+
+    let sitemap = get(&app, "/sitemap.xml").await.text();
+    assert!(sitemap.contains("/guide/intro"), "{sitemap}");
+    assert!(!sitemap.contains("/guide/plan"));
+}
+
+#[tokio::test]
+async fn drafts_are_not_found_by_default() {
+    let wiki = wiki();
+    let app = wiki.app();
+
+    assert_eq!(get(&app, "/guide/plan").await.status, StatusCode::NOT_FOUND);
+    assert_eq!(get(&app, "/raw/guide/plan.md").await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn drafts_are_served_when_shown() {
+    let mut wiki = wiki();
+    wiki.config.show_drafts = true;
+    let app = wiki.app();
+
+    let page = get(&app, "/guide/plan").await;
+    assert_eq!(page.status, StatusCode::OK);
+    assert!(page.text().contains("secret sauce"));
+    assert!(get(&app, "/search?q=secret").await.text().contains("Found 2 results"));
+}
+
+#[tokio::test]
+async fn a_draft_index_falls_back_to_the_listing() {
+    let wiki = TestWiki::new();
+    wiki.page("docs/index.md", DRAFT).page("docs/page.md", "# Page");
+
+    let body = get(&wiki.app(), "/docs/").await.text();
+    assert!(body.contains("<ul class=\"listing\">"), "{body}");
+    assert!(!body.contains("secret sauce"));
+}
+
+
+#[tokio::test]
+async fn drafts_written_after_indexing_stay_out_of_listings() {
+    let wiki = wiki();
+    let app = wiki.app();
+    wiki.page("guide/later.md", DRAFT).page("guide/other.md", "# Other");
+
+    let body = get(&app, "/guide/").await.text();
+    assert!(body.contains("/guide/intro"), "{body}");
+    assert!(body.contains("/guide/other"), "{body}");
+    assert!(!body.contains("/guide/plan"), "{body}");
+    assert!(!body.contains("/guide/later"), "{body}");
+}
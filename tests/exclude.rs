@@ -0,0 +1,40 @@
+//! Excluded paths stay out of navigation, listings and search
+
+mod common;
+
+use common::{get, TestWiki};
+
+fn wiki() -> TestWiki {
+    let mut wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro\n\nsecret sauce\n");
+    wiki.page("drafts/plan.md", "# Plan\n\nsecret sauce\n");
+    wiki.page("guide/.hidden.md", "# Hidden\n\nsecret sauce\n");
+    wiki.config.exclude = vec!["drafts".to_string()];
+    wiki
+}
+
+#[tokio::test]
+async fn excluded_directories_leave_the_sidebar_and_listings() {
+    let wiki = wiki();
+    let app = wiki.app();
+
+    let page = get(&app, "/guide/intro").await.text();
+    assert!(page.contains("href=\"/guide/intro\""));
+    assert!(!page.contains("drafts"), "{page}");
+    assert!(!page.contains("hidden"));
+
+    let listing = get(&app, "/guide/").await.text();
+    assert!(listing.contains("intro"), "{listing}");
+    assert!(!listing.contains("hidden"));
+}
+
+#[tokio::test]
+async fn excluded_directories_yield_no_search_hits() {
+    let wiki = wiki();
+    let app = wiki.app();
+
+    let results = get(&app, "/search?q=secret").await.text();
+    assert!(results.contains("Found 1 result"), "{results}");
+    assert!(results.contains("/guide/intro"));
+    assert!(!results.contains("drafts/plan"));
+}
@@ -0,0 +1,44 @@
+//! HEAD requests share the GET handlers but send no body
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{get, request, send, TestWiki};
+
+/// Headers that must match between GET and HEAD
+const SHARED_HEADERS: &[&str] = &["content-type", "content-length", "etag", "last-modified", "cache-control"];
+
+async fn assert_head_matches_get(wiki: &TestWiki, uri: &str, status: StatusCode) {
+    let app = wiki.app();
+    let get = get(&app, uri).await;
+    let head = send(&app, request(Method::HEAD, uri, &[])).await;
+
+    assert_eq!(get.status, status, "GET {uri}");
+    assert_eq!(head.status, status, "HEAD {uri}");
+    assert!(!get.body.is_empty(), "GET {uri}");
+    assert!(head.body.is_empty(), "HEAD {uri}");
+    assert_eq!(head.header("content-length"), Some(get.body.len().to_string().as_str()), "HEAD {uri}");
+    for name in SHARED_HEADERS {
+        assert_eq!(head.header(name), get.header(name), "{name} for {uri}");
+    }
+}
+
+#[tokio::test]
+async fn head_on_a_page() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro\n\nHello.\n");
+    assert_head_matches_get(&wiki, "/guide/intro", StatusCode::OK).await;
+}
+
+#[tokio::test]
+async fn head_on_a_static_file() {
+    let wiki = TestWiki::new();
+    wiki.asset("css/site.css", b"body { margin: 0 }");
+    assert_head_matches_get(&wiki, "/static/css/site.css", StatusCode::OK).await;
+}
+
+#[tokio::test]
+async fn head_on_a_missing_page() {
+    let wiki = TestWiki::new();
+    assert_head_matches_get(&wiki, "/missing", StatusCode::NOT_FOUND).await;
+}
@@ -0,0 +1,49 @@
+//! Size limits on files read into memory
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, get_with, TestWiki};
+
+fn wiki_with_limit(max_file_bytes: u64) -> TestWiki {
+    let mut wiki = TestWiki::new();
+    wiki.config.max_file_bytes = max_file_bytes;
+    wiki
+}
+
+#[tokio::test]
+async fn oversized_pages_are_413() {
+    let wiki = wiki_with_limit(64);
+    wiki.page("big.md", &"word ".repeat(100)).page("small.md", "# Small");
+    let app = wiki.app();
+
+    assert_eq!(get(&app, "/big").await.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(get(&app, "/raw/big.md").await.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(get(&app, "/small").await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn oversized_wiki_files_are_413() {
+    let wiki = wiki_with_limit(64);
+    wiki.page("data/big.csv", &"a,b\n".repeat(100)).page("data/small.csv", "a,b\n");
+    let app = wiki.app();
+
+    let response = get(&app, "/data/big.csv").await;
+    assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(response.text(), "File too large to display (400 bytes)");
+    assert_eq!(get(&app, "/data/small.csv").await.body, b"a,b\n");
+}
+
+#[tokio::test]
+async fn oversized_static_files_are_413_unless_a_range_fits() {
+    let wiki = wiki_with_limit(64);
+    wiki.asset("big.bin", &[7u8; 100]);
+    let app = wiki.app();
+
+    assert_eq!(get(&app, "/static/big.bin").await.status, StatusCode::PAYLOAD_TOO_LARGE);
+    let range = get_with(&app, "/static/big.bin", &[("range", "bytes=0-9")]).await;
+    assert_eq!(range.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(range.body, [7u8; 10]);
+    let too_long = get_with(&app, "/static/big.bin", &[("range", "bytes=0-")]).await;
+    assert_eq!(too_long.status, StatusCode::PAYLOAD_TOO_LARGE);
+}
@@ -0,0 +1,146 @@
+//! Directory listings
+
+mod common;
+
+use common::{get, TestWiki};
+
+/// Link targets of a listing's entries, in order
+fn listed(body: &str) -> Vec<String> {
+    let start = body.find("<ul class=\"listing\">").unwrap_or_else(|| panic!("no listing in {body}"));
+    let end = start + body[start..].find("</ul>").unwrap();
+    body[start..end]
+        .split("<li><a href=\"")
+        .skip(1)
+        .map(|item| item[..item.find('"').unwrap()].to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn directories_are_listed_before_files_each_by_name() {
+    let wiki = TestWiki::new();
+    for file in ["docs/zeta.md", "docs/Alpha.md", "docs/beta.txt", "docs/yard/a.md", "docs/Bin/a.md", "docs/.hidden.md", "docs/.git/config"] {
+        wiki.page(file, "text");
+    }
+
+    let body = get(&wiki.app(), "/docs/").await.text();
+    assert_eq!(listed(&body), ["/docs/Bin/", "/docs/yard/", "/docs/Alpha", "/docs/beta.txt", "/docs/zeta"]);
+}
+
+#[tokio::test]
+async fn index_pages_are_not_listed() {
+    let wiki = TestWiki::new();
+    // Only `index.md` itself is served for the directory, but any casing is left out of the listing
+    wiki.page("docs/INDEX.md", "# Index")
+        .page("docs/Readme.md", "# Readme")
+        .page("docs/page.md", "# Page")
+        .page("docs/readme-notes.md", "# More")
+        .page("docs/notes/index.md", "# Notes");
+
+    let body = get(&wiki.app(), "/docs/").await.text();
+    assert_eq!(listed(&body), ["/docs/notes/", "/docs/page", "/docs/readme-notes"]);
+}
+
+/// A wiki whose `many/` directory holds `page-01.md` to `page-25.md`
+fn many_pages() -> TestWiki {
+    let wiki = TestWiki::new();
+    for n in 1..=25 {
+        wiki.page(&format!("many/page-{n:02}.md"), "text");
+    }
+    wiki
+}
+
+fn pages(range: std::ops::RangeInclusive<usize>) -> Vec<String> {
+    range.map(|n| format!("/many/page-{n:02}")).collect()
+}
+
+#[tokio::test]
+async fn first_listing_page_links_to_the_next() {
+    let body = get(&many_pages().app(), "/many/?per_page=10").await.text();
+    assert_eq!(listed(&body), pages(1..=10));
+    assert!(body.contains("<span>Page 1 of 3 (25 entries)</span>"), "{body}");
+    assert!(body.contains("<a href=\"?page=2&amp;per_page=10\" rel=\"next\">"));
+    assert!(!body.contains("rel=\"prev\""));
+}
+
+#[tokio::test]
+async fn middle_listing_page_links_both_ways() {
+    let wiki = many_pages();
+    let app = wiki.app();
+    let body = get(&app, "/many/?page=2&per_page=10").await.text();
+    assert_eq!(listed(&body), pages(11..=20));
+    assert!(body.contains("<a href=\"?page=1&amp;per_page=10\" rel=\"prev\">"), "{body}");
+    assert!(body.contains("<a href=\"?page=3&amp;per_page=10\" rel=\"next\">"));
+
+    // Pages split the same way every time and past the end clamp to the last
+    assert_eq!(listed(&get(&app, "/many/?page=3&per_page=10").await.text()), pages(21..=25));
+    assert_eq!(listed(&get(&app, "/many/?page=99&per_page=10").await.text()), pages(21..=25));
+}
+
+#[tokio::test]
+async fn page_size_beyond_the_entry_count_shows_everything() {
+    let body = get(&many_pages().app(), "/many/?per_page=500").await.text();
+    assert_eq!(listed(&body), pages(1..=25));
+    assert!(!body.contains("class=\"pagination\""));
+}
+
+
+#[tokio::test]
+async fn directory_counts_skip_what_their_listing_hides() {
+    let mut wiki = TestWiki::new();
+    wiki.config.exclude = vec!["docs/private/**".to_string()];
+    wiki.page("docs/a.md", "# A")
+        .page("docs/index.md", "# Docs")
+        .page("docs/draft.md", "---\ndraft: true\n---\n# Draft")
+        .page("docs/.hidden.md", "hidden")
+        .page("docs/private/secret.md", "secret")
+        .page("docs/sub/b.md", "# B")
+        .page("solo/README.md", "# Solo")
+        .page("solo/page.md", "# Page");
+
+    // a.md and sub/ are listed; the index, draft, dotfile and excluded directory are not
+    let body = get(&wiki.app(), "/").await.text();
+    assert!(body.contains("<a href=\"/docs/\">docs/</a><span class=\"entry-meta\">2 items</span>"), "{body}");
+    assert!(body.contains("<a href=\"/solo/\">solo/</a><span class=\"entry-meta\">1 item</span>"), "{body}");
+
+    wiki.config.show_drafts = true;
+    let body = get(&wiki.app(), "/").await.text();
+    assert!(body.contains("<a href=\"/docs/\">docs/</a><span class=\"entry-meta\">3 items</span>"), "{body}");
+}
+
+
+#[tokio::test]
+async fn home_listing_ends_with_recently_updated_pages() {
+    let mut wiki = TestWiki::new();
+    wiki.page("old.md", "# Old").page("docs/new.md", "---\ntitle: New & shiny\n---\nBody");
+    let stamp = |secs| std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+    for (path, secs) in [("old.md", 1_000_000), ("docs/new.md", 2_000_000)] {
+        let file = std::fs::File::options().write(true).open(wiki.wiki_dir().join(path)).unwrap();
+        file.set_modified(stamp(secs)).unwrap();
+    }
+
+    let body = get(&wiki.app(), "/").await.text();
+    assert!(
+        body.contains(
+            "<section class=\"recent-pages\"><h2>Recently updated</h2><ul>\
+             <li><a href=\"/docs/new\">New &amp; shiny</a> <time datetime=\"1970-01-24T03:33:20Z\">1970-01-24</time></li>\
+             <li><a href=\"/old\">Old</a> <time datetime=\"1970-01-12T13:46:40Z\">1970-01-12</time></li></ul></section>"
+        ),
+        "{body}"
+    );
+
+    wiki.config.recent_pages = 0;
+    assert!(!get(&wiki.app(), "/").await.text().contains("recent-pages"));
+}
+
+
+#[tokio::test]
+async fn child_counts_follow_drafts_changed_after_indexing() {
+    let wiki = TestWiki::new();
+    wiki.page("docs/a.md", "# A").page("docs/b.md", "# B");
+    let app = wiki.app();
+
+    // Pages written or edited since the index was built are read to check
+    wiki.page("docs/b.md", "---\ndraft: true\n---\n# B").page("docs/c.md", "---\ndraft: true\n---\n# C");
+    let body = get(&app, "/").await.text();
+    assert!(body.contains("<a href=\"/docs/\">docs/</a><span class=\"entry-meta\">1 item</span>"), "{body}");
+}
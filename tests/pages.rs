@@ -0,0 +1,159 @@
+//! Rendered wiki pages, directories and the 404 page
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, get_with, TestWiki};
+
+#[tokio::test]
+async fn missing_pages_get_the_styled_404_page() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro");
+
+    let response = get(&wiki.app(), "/guide/missing%3Cb%3E").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.header("content-type"), Some("text/html; charset=utf-8"));
+    let body = response.text();
+    assert!(body.contains("class=\"error-container\""), "{body}");
+    assert!(body.contains("Page Not Found"));
+    assert!(body.contains("<code>/guide/missing&lt;b&gt;</code>"));
+    // The sidebar and FAB let the reader navigate away
+    assert!(body.contains("class=\"sidebar-nav\""));
+    assert!(body.contains("href=\"/guide/intro\""));
+    assert!(body.contains("class=\"fab"));
+}
+
+#[tokio::test]
+async fn configured_site_title_is_used_across_pages() {
+    let mut wiki = TestWiki::new().with_repo_template();
+    wiki.config.site_title = "Team Docs".to_string();
+    wiki.page("guide/intro.md", "# Intro");
+    let app = wiki.app();
+
+    let page = get(&app, "/guide/intro").await.text();
+    assert!(page.contains("<title>Intro — Team Docs</title>"), "{page}");
+    assert!(page.contains("<a class=\"site-title\" href=\"/\">Team Docs</a>"));
+    let home = get(&app, "/").await.text();
+    assert!(home.contains("<title>Team Docs</title>"), "{home}");
+}
+
+#[tokio::test]
+async fn unchanged_pages_answer_conditional_requests_with_304() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro");
+    let app = wiki.app();
+
+    let first = get(&app, "/guide/intro").await;
+    let etag = first.header("etag").expect("pages carry an ETag").to_string();
+    let modified = first.header("last-modified").expect("pages carry a Last-Modified").to_string();
+    let again = get_with(&app, "/guide/intro", &[("if-none-match", &etag)]).await;
+    assert_eq!(again.status, StatusCode::NOT_MODIFIED);
+    assert!(again.body.is_empty());
+    assert_eq!(again.header("etag"), Some(etag.as_str()));
+
+    let since = get_with(&app, "/guide/intro", &[("if-modified-since", &modified)]).await;
+    assert_eq!(since.status, StatusCode::NOT_MODIFIED);
+    let before = get_with(&app, "/guide/intro", &[("if-modified-since", "Thu, 01 Jan 1970 00:00:00 GMT")]).await;
+    assert_eq!(before.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn touched_pages_are_sent_again() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro");
+    let app = wiki.app();
+    let etag = get(&app, "/guide/intro").await.header("etag").unwrap().to_string();
+
+    std::fs::File::options()
+        .write(true)
+        .open(wiki.wiki_dir().join("guide/intro.md"))
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60))
+        .unwrap();
+    let touched = get_with(&app, "/guide/intro", &[("if-none-match", &etag)]).await;
+    assert_eq!(touched.status, StatusCode::OK);
+    assert_ne!(touched.header("etag"), Some(etag.as_str()));
+}
+
+#[tokio::test]
+async fn new_sidebar_entries_change_the_page_etag() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro");
+    let app = wiki.app();
+    let etag = get(&app, "/guide/intro").await.header("etag").unwrap().to_string();
+
+    wiki.page("guide/next.md", "# Next");
+    let response = get_with(&app, "/guide/intro", &[("if-none-match", &etag)]).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains("/guide/next"));
+}
+
+#[tokio::test]
+async fn touched_pages_fail_if_modified_since() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro");
+    let app = wiki.app();
+    let modified = get(&app, "/guide/intro").await.header("last-modified").unwrap().to_string();
+
+    std::fs::File::options()
+        .write(true)
+        .open(wiki.wiki_dir().join("guide/intro.md"))
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60))
+        .unwrap();
+    let touched = get_with(&app, "/guide/intro", &[("if-modified-since", &modified)]).await;
+    assert_eq!(touched.status, StatusCode::OK);
+    assert_ne!(touched.header("last-modified"), Some(modified.as_str()));
+}
+
+#[tokio::test]
+async fn edit_url_links_a_nested_page_to_its_source() {
+    let mut wiki = TestWiki::new().with_repo_template();
+    wiki.config.edit_url = "https://github.com/org/repo/edit/main/wiki/{path}".to_string();
+    wiki.page("guide/setup/intro.md", "# Intro");
+
+    let body = get(&wiki.app(), "/guide/setup/intro").await.text();
+    assert!(
+        body.contains("<a href=\"https://github.com/org/repo/edit/main/wiki/guide/setup/intro.md\" title=\"Edit source\" class=\"fab-action-source\">"),
+        "{body}"
+    );
+}
+
+#[tokio::test]
+async fn no_source_link_without_an_edit_url() {
+    let wiki = TestWiki::new().with_repo_template();
+    wiki.page("guide/setup/intro.md", "# Intro");
+
+    let body = get(&wiki.app(), "/guide/setup/intro").await.text();
+    assert!(body.contains("fab-action-raw"), "{body}");
+    assert!(!body.contains("fab-action-source"));
+}
+
+
+#[tokio::test]
+async fn pages_describe_themselves_for_link_previews() {
+    let wiki = TestWiki::new();
+    wiki.page("described.md", "---\ndescription: Fish & chips\n---\n# Described\n\nBody text.\n")
+        .page("plain.md", "# Plain\n\nThe \"first\" paragraph.\n\nThe second.\n");
+    let app = wiki.app();
+
+    let body = get(&app, "/described").await.text();
+    assert!(body.contains("<meta property=\"og:title\" content=\"Described\">"), "{body}");
+    assert!(body.contains("<meta name=\"description\" content=\"Fish &amp; chips\">"));
+    assert!(body.contains("<meta property=\"og:description\" content=\"Fish &amp; chips\">"));
+
+    let body = get(&app, "/plain").await.text();
+    assert!(body.contains("<meta name=\"description\" content=\"The &quot;first&quot; paragraph.\">"), "{body}");
+}
+
+
+#[tokio::test]
+async fn pages_show_a_reading_time() {
+    let wiki = TestWiki::new();
+    wiki.page("long.md", &format!("# Long\n\n{}", "word ".repeat(600)))
+        .page("code.md", "```\nfn main() {}\n```\n");
+    let app = wiki.app();
+
+    assert!(get(&app, "/long").await.text().contains("<p class=\"reading-time\">~3 min read</p>"));
+    assert!(get(&app, "/code").await.text().contains("<p class=\"reading-time\">&lt; 1 min read</p>"));
+}
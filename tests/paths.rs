@@ -0,0 +1,93 @@
+//! Request path handling: traversal, normalization and length limits
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, TestWiki};
+
+#[tokio::test]
+async fn encoded_traversal_is_rejected() {
+    let wiki = TestWiki::new();
+    wiki.page("index.md", "# Home");
+    let app = wiki.app();
+
+    assert_eq!(get(&app, "/..%2fCargo.toml").await.status, StatusCode::BAD_REQUEST);
+    assert_eq!(get(&app, "/raw/..%2f..%2fCargo.toml").await.status, StatusCode::BAD_REQUEST);
+    assert_eq!(get(&app, "/static/..%2fwiki%2findex.md").await.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn dot_dot_segments_above_the_root_are_rejected() {
+    let wiki = TestWiki::new();
+    let app = wiki.app();
+
+    assert_eq!(get(&app, "/static/../src/main.rs").await.status, StatusCode::BAD_REQUEST);
+    assert_eq!(get(&app, "/static/../../src/main.rs").await.status, StatusCode::BAD_REQUEST);
+    assert_eq!(get(&app, "/raw/..%5c..%5cCargo.toml").await.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn directories_redirect_to_their_slash_url() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/index.md", "# Guide");
+    wiki.page("my notes/ünï.md", "# Notes");
+    let app = wiki.app();
+
+    let response = get(&app, "/guide").await;
+    assert_eq!(response.status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.header("location"), Some("/guide/"));
+    assert!(response.body.is_empty());
+
+    let response = get(&app, "/guide?page=2&sort=name").await;
+    assert_eq!(response.header("location"), Some("/guide/?page=2&sort=name"));
+
+    let response = get(&app, "/my%20notes").await;
+    assert_eq!(response.status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.header("location"), Some("/my%20notes/"));
+
+    assert_eq!(get(&app, "/guide/").await.status, StatusCode::OK);
+    assert_eq!(get(&app, "/").await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn files_are_not_redirected() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro");
+    let app = wiki.app();
+
+    for uri in ["/guide/intro", "/guide/intro.md"] {
+        let response = get(&app, uri).await;
+        assert_eq!(response.status, StatusCode::OK, "{uri}");
+        assert_eq!(response.header("location"), None, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn paths_over_the_length_limit_are_rejected() {
+    let mut wiki = TestWiki::new();
+    wiki.config.max_path_len = 16;
+    wiki.page("abcdefghijklmnop.md", "# At the limit");
+    wiki.asset("abcdefghijklmnop", b"asset");
+    let app = wiki.app();
+
+    for uri in ["/abcdefghijklmnopq", "/raw/abcdefghijklmnop.md", "/static/abcdefghijklmnopq"] {
+        let response = get(&app, uri).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(response.text(), "Bad request: path longer than 16 bytes");
+    }
+}
+
+#[tokio::test]
+async fn paths_at_the_length_limit_proceed() {
+    let mut wiki = TestWiki::new();
+    wiki.config.max_path_len = 16;
+    wiki.page("abcdefghijklmnop.md", "# At the limit").page("abcdefghijkl.md", "# Raw");
+    wiki.asset("abcdefghijklmnop", b"asset");
+    let app = wiki.app();
+
+    assert_eq!(get(&app, "/abcdefghijklmnop").await.status, StatusCode::OK);
+    assert_eq!(get(&app, "/raw/abcdefghijkl.md").await.status, StatusCode::OK);
+    assert_eq!(get(&app, "/static/abcdefghijklmnop").await.status, StatusCode::OK);
+    // Within the limit but missing is an ordinary 404
+    assert_eq!(get(&app, "/abcdefghijklmnoq").await.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,75 @@
+//! Health, readiness and metrics endpoints
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, TestWiki};
+
+#[tokio::test]
+async fn healthz_answers_without_the_wiki() {
+    let wiki = TestWiki::new();
+    let app = wiki.app();
+    std::fs::remove_dir_all(wiki.wiki_dir()).unwrap();
+
+    let response = get(&app, "/healthz").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("application/json"));
+    assert_eq!(response.text(), "{\"status\":\"ok\"}");
+}
+
+#[tokio::test]
+async fn readyz_reflects_the_wiki_directory() {
+    let wiki = TestWiki::new();
+    let app = wiki.app();
+
+    let ready = get(&app, "/readyz").await;
+    assert_eq!(ready.status, StatusCode::OK);
+    assert_eq!(ready.text(), "{\"status\":\"ready\"}");
+
+    std::fs::remove_dir_all(wiki.wiki_dir()).unwrap();
+    let missing = get(&app, "/readyz").await;
+    assert_eq!(missing.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(missing.text(), "{\"status\":\"unavailable\"}");
+}
+
+#[tokio::test]
+async fn probes_win_over_pages_of_the_same_name() {
+    let wiki = TestWiki::new();
+    wiki.page("healthz.md", "# Not a probe");
+
+    assert_eq!(get(&wiki.app(), "/healthz").await.text(), "{\"status\":\"ok\"}");
+}
+
+/// Value of the sample named exactly `name`, labels included
+fn sample(metrics: &str, name: &str) -> u64 {
+    metrics
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .unwrap_or_else(|| panic!("no {name} in {metrics}"))
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn metrics_count_requests_by_status_class_and_searches() {
+    let wiki = TestWiki::new();
+    wiki.page("intro.md", "# Intro");
+    let app = wiki.app();
+
+    for uri in ["/intro", "/intro", "/missing", "/search?q=intro"] {
+        get(&app, uri).await;
+    }
+    let response = get(&app, "/metrics").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("text/plain; version=0.0.4; charset=utf-8"));
+    let metrics = response.text();
+    assert_eq!(sample(&metrics, "strata_requests_total"), 4);
+    assert_eq!(sample(&metrics, "strata_responses_total{class=\"2xx\"}"), 3);
+    assert_eq!(sample(&metrics, "strata_responses_total{class=\"4xx\"}"), 1);
+    assert_eq!(sample(&metrics, "strata_search_queries_total"), 1);
+    assert_eq!(sample(&metrics, "strata_request_duration_seconds_count"), 4);
+    assert_eq!(sample(&metrics, "strata_request_duration_seconds_bucket{le=\"+Inf\"}"), 4);
+
+    let metrics = get(&app, "/metrics").await.text();
+    assert_eq!(sample(&metrics, "strata_requests_total"), 5);
+}
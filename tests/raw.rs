@@ -0,0 +1,44 @@
+//! The raw markdown viewer
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, TestWiki};
+
+#[tokio::test]
+async fn fab_raw_link_of_a_nested_page_opens_its_source() {
+    let wiki = TestWiki::new().with_repo_template();
+    wiki.page("guide/intro.md", "# Intro\n\nBody text");
+    let app = wiki.app();
+
+    let page = get(&app, "/guide/intro").await.text();
+    assert!(page.contains("<a href=\"/raw/guide/intro.md\" title=\"View raw markdown\""), "{page}");
+
+    let raw = get(&app, "/raw/guide/intro.md").await;
+    assert_eq!(raw.status, StatusCode::OK);
+    let body = raw.text();
+    assert!(body.contains("<code># Intro\n\nBody text</code>"), "{body}");
+    assert!(body.contains("<a href=\"/guide/intro\" class=\"raw-btn primary\">"));
+}
+
+#[tokio::test]
+async fn extension_less_raw_links_redirect_with_an_encoded_location() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/my page.md", "# Mine");
+
+    let response = get(&wiki.app(), "/raw/guide/my%20page").await;
+    assert_eq!(response.status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.header("location"), Some("/raw/guide/my%20page.md"));
+}
+
+#[tokio::test]
+async fn raw_viewer_escapes_the_page_path() {
+    let wiki = TestWiki::new();
+    wiki.page("a<b>&\"c.md", "text");
+
+    let body = get(&wiki.app(), "/raw/a%3Cb%3E%26%22c.md").await.text();
+    assert!(body.contains("<h1>Raw Markdown: a&lt;b&gt;&amp;&quot;c.md</h1>"), "{body}");
+    assert!(body.contains("<title>Raw: a&lt;b&gt;&amp;&quot;c.md — "));
+    assert!(body.contains("<a href=\"/a%3Cb%3E&amp;%22c\" class=\"raw-btn primary\">"));
+    assert!(!body.contains("<b>"));
+}
@@ -0,0 +1,57 @@
+//! Search page and search API
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, TestWiki};
+
+#[tokio::test]
+async fn empty_result_set_renders_the_no_results_page() {
+    let mut wiki = TestWiki::new();
+    wiki.page("strong.md", "# Widget\n\nAll about the widget.\n");
+    wiki.config.search.min_relevance = 1000.0;
+
+    let response = get(&wiki.app(), "/search?q=widget").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.text();
+    assert!(body.contains("Found 0 results"), "{body}");
+    assert!(body.contains("class=\"no-results\""));
+    assert!(!body.contains("search-results-list"));
+}
+
+#[tokio::test]
+async fn several_excerpts_render_as_a_list() {
+    let mut wiki = TestWiki::new();
+    let filler = "Nothing to see in this filler paragraph at all. ".repeat(8);
+    wiki.page("lights.md", &format!("# Lights\n\nFirst beacon on the hill.\n\n{filler}\n\nSecond beacon by the sea.\n"));
+    wiki.config.search.max_excerpts = 3;
+
+    let body = get(&wiki.app(), "/search?q=beacon").await.text();
+    assert!(body.contains("<ul class=\"result-excerpts\">"), "{body}");
+    assert_eq!(body.matches("<li class=\"result-excerpt\">").count(), 2);
+
+    wiki.config.search.max_excerpts = 1;
+    let body = get(&wiki.app(), "/search?q=beacon").await.text();
+    assert!(!body.contains("result-excerpts"), "{body}");
+    assert_eq!(body.matches("<p class=\"result-excerpt\">").count(), 1);
+}
+
+
+#[tokio::test]
+async fn empty_and_short_queries_suggest_recent_pages() {
+    let wiki = TestWiki::new();
+    wiki.page("guide/intro.md", "# Intro\n\nA body.\n");
+    let app = wiki.app();
+
+    for uri in ["/search?q=", "/search?q=a"] {
+        let response = get(&app, uri).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let body = response.text();
+        assert!(
+            body.contains("<div class=\"search-suggestions\"><h3>Recently updated</h3><ul><li><a href=\"/guide/intro\">Intro</a></li></ul></div>"),
+            "{body}"
+        );
+        assert!(!body.contains("search-results-list"));
+        assert!(!body.contains("Found "));
+    }
+}
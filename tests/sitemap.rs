@@ -0,0 +1,53 @@
+//! sitemap.xml generation
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get_with, TestWiki};
+
+/// Check that every element is closed in order, returning the element names seen
+fn element_names(xml: &str) -> Vec<String> {
+    let body = xml.strip_prefix("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n").expect("XML declaration");
+    let mut open: Vec<String> = Vec::new();
+    let mut names = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find('<') {
+        assert!(!rest[..start].contains(['<', '>']), "stray markup in {rest:?}");
+        let end = rest[start..].find('>').expect("unclosed tag") + start;
+        let tag = &rest[start + 1..end];
+        if let Some(name) = tag.strip_prefix('/') {
+            assert_eq!(open.pop().as_deref(), Some(name), "mismatched closing tag");
+        } else {
+            let name = tag.split_whitespace().next().unwrap().to_string();
+            names.push(name.clone());
+            open.push(name);
+        }
+        rest = &rest[end + 1..];
+    }
+    assert!(open.is_empty(), "unclosed elements {open:?}");
+    assert!(rest.trim().is_empty());
+    names
+}
+
+#[tokio::test]
+async fn sitemap_lists_every_page() {
+    let wiki = TestWiki::new();
+    wiki.page("index.md", "# Home");
+    wiki.page("guide/README.md", "# Guide");
+    wiki.page("guide/intro.md", "# Intro");
+    wiki.page("notes.txt", "not a page");
+
+    let response = get_with(&wiki.app(), "/sitemap.xml", &[("host", "wiki.example")]).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("application/xml"));
+
+    let xml = response.text();
+    let names = element_names(&xml);
+    assert_eq!(names.first().map(String::as_str), Some("urlset"));
+    assert_eq!(names.iter().filter(|name| *name == "url").count(), 3);
+    assert_eq!(names.iter().filter(|name| *name == "lastmod").count(), 3);
+    for url in ["http://wiki.example/", "http://wiki.example/guide/", "http://wiki.example/guide/intro"] {
+        assert!(xml.contains(&format!("<loc>{url}</loc>")), "{url} missing from {xml}");
+    }
+    assert!(!xml.contains("notes"));
+}
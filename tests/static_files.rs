@@ -0,0 +1,166 @@
+//! Static file serving: caching, ranges and precompressed variants
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, get_with, TestWiki};
+
+#[tokio::test]
+async fn static_files_carry_cache_validators() {
+    let wiki = TestWiki::new();
+    wiki.asset("css/site.css", b"body { color: red }");
+
+    let response = get(&wiki.app(), "/static/css/site.css").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("text/css; charset=utf-8"));
+    assert!(response.header("cache-control").is_some());
+    assert!(response.header("etag").is_some());
+    assert!(response.header("last-modified").is_some());
+    assert_eq!(response.body, b"body { color: red }");
+}
+
+#[tokio::test]
+async fn matching_validators_yield_304_until_the_file_changes() {
+    let wiki = TestWiki::new();
+    wiki.asset("app.js", b"console.log(1);");
+    let app = wiki.app();
+
+    let first = get(&app, "/static/app.js").await;
+    let etag = first.header("etag").unwrap().to_string();
+    let last_modified = first.header("last-modified").unwrap().to_string();
+
+    let cached = get_with(&app, "/static/app.js", &[("if-none-match", &etag)]).await;
+    assert_eq!(cached.status, StatusCode::NOT_MODIFIED);
+    assert!(cached.body.is_empty());
+    let cached = get_with(&app, "/static/app.js", &[("if-modified-since", &last_modified)]).await;
+    assert_eq!(cached.status, StatusCode::NOT_MODIFIED);
+
+    wiki.asset("app.js", b"console.log('changed');");
+    let changed = get_with(&app, "/static/app.js", &[("if-none-match", &etag)]).await;
+    assert_eq!(changed.status, StatusCode::OK);
+    assert_ne!(changed.header("etag"), Some(etag.as_str()));
+    assert_eq!(changed.body, b"console.log('changed');");
+}
+
+#[tokio::test]
+async fn byte_ranges_are_served_as_partial_content() {
+    let wiki = TestWiki::new();
+    wiki.asset("data.bin", b"0123456789");
+    let app = wiki.app();
+
+    let response = get_with(&app, "/static/data.bin", &[("range", "bytes=2-5")]).await;
+    assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.header("content-range"), Some("bytes 2-5/10"));
+    assert_eq!(response.header("accept-ranges"), Some("bytes"));
+    assert_eq!(response.body, b"2345");
+
+    let response = get_with(&app, "/static/data.bin", &[("range", "bytes=7-")]).await;
+    assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.header("content-range"), Some("bytes 7-9/10"));
+    assert_eq!(response.body, b"789");
+
+    let response = get_with(&app, "/static/data.bin", &[("range", "bytes=-3")]).await;
+    assert_eq!(response.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.body, b"789");
+}
+
+#[tokio::test]
+async fn out_of_bounds_ranges_are_unsatisfiable() {
+    let wiki = TestWiki::new();
+    wiki.asset("data.bin", b"0123456789");
+
+    let response = get_with(&wiki.app(), "/static/data.bin", &[("range", "bytes=20-30")]).await;
+    assert_eq!(response.status, StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.header("content-range"), Some("bytes */10"));
+    assert!(response.body.is_empty());
+}
+
+#[tokio::test]
+async fn full_responses_advertise_ranges() {
+    let wiki = TestWiki::new();
+    wiki.asset("data.bin", b"0123456789");
+
+    let response = get(&wiki.app(), "/static/data.bin").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("accept-ranges"), Some("bytes"));
+    assert_eq!(response.body, b"0123456789");
+}
+
+#[tokio::test]
+async fn nested_assets_are_served() {
+    let wiki = TestWiki::new();
+    wiki.asset("img/icons/logo.svg", b"<svg/>");
+
+    let response = get(&wiki.app(), "/static/img/icons/logo.svg").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("image/svg+xml"));
+    assert_eq!(response.body, b"<svg/>");
+}
+
+#[tokio::test]
+async fn traversal_out_of_the_static_root_is_rejected() {
+    let wiki = TestWiki::new();
+    wiki.page("secret.md", "# Secret");
+    wiki.asset("css/site.css", b"body{}");
+    let app = wiki.app();
+
+    for uri in ["/static/../wiki/secret.md", "/static/css/../../wiki/secret.md", "/static/%2e%2e/wiki/secret.md"] {
+        let response = get(&app, uri).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{uri}");
+        assert!(!response.text().contains("Secret"), "{uri}");
+    }
+    // An absolute-looking path stays inside the root
+    assert_eq!(get(&app, "/static//etc/passwd").await.status, StatusCode::NOT_FOUND);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinks_out_of_the_static_root_are_rejected() {
+    let wiki = TestWiki::new();
+    wiki.page("secret.md", "# Secret");
+    std::os::unix::fs::symlink(wiki.wiki_dir(), wiki.static_dir().join("wiki")).unwrap();
+
+    let response = get(&wiki.app(), "/static/wiki/secret.md").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(!response.text().contains("Secret"));
+}
+
+#[tokio::test]
+async fn gzip_capable_clients_get_the_precompressed_sibling() {
+    let wiki = TestWiki::new();
+    wiki.asset("css/strata.css", b"body{}").asset("css/strata.css.gz", b"gzipped bytes");
+
+    let response = get_with(&wiki.app(), "/static/css/strata.css", &[("accept-encoding", "gzip, deflate")]).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-encoding"), Some("gzip"));
+    assert_eq!(response.header("content-type"), Some("text/css; charset=utf-8"));
+    assert_eq!(response.header("vary"), Some("accept-encoding"));
+    assert_eq!(response.body, b"gzipped bytes");
+}
+
+#[tokio::test]
+async fn other_clients_get_the_plain_file() {
+    let wiki = TestWiki::new();
+    wiki.asset("css/strata.css", b"body{}").asset("css/strata.css.gz", b"gzipped bytes");
+    let app = wiki.app();
+
+    for headers in [&[][..], &[("accept-encoding", "identity")][..], &[("accept-encoding", "gzip;q=0")][..]] {
+        let response = get_with(&app, "/static/css/strata.css", headers).await;
+        assert_eq!(response.header("content-encoding"), None, "{headers:?}");
+        assert_eq!(response.header("vary"), Some("accept-encoding"));
+        assert_eq!(response.body, b"body{}");
+    }
+}
+
+#[tokio::test]
+async fn brotli_is_preferred_when_both_are_accepted() {
+    let wiki = TestWiki::new();
+    wiki.asset("app.js", b"plain").asset("app.js.gz", b"gzip").asset("app.js.br", b"brotli");
+    let app = wiki.app();
+
+    let response = get_with(&app, "/static/app.js", &[("accept-encoding", "gzip, br")]).await;
+    assert_eq!(response.header("content-encoding"), Some("br"));
+    assert_eq!(response.body, b"brotli");
+    let response = get_with(&app, "/static/app.js", &[("accept-encoding", "gzip")]).await;
+    assert_eq!(response.body, b"gzip");
+}
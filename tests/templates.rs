@@ -0,0 +1,80 @@
+//! Page shell templates from a configured theme directory
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, write, TestWiki};
+
+#[tokio::test]
+async fn theme_directory_supplies_the_page_template() {
+    let mut wiki = TestWiki::new();
+    let theme = wiki.dir.path().join("theme");
+    write(
+        &theme.join("base.html"),
+        b"<html><title>{{TITLE}}</title><nav>{{SIDEBAR}}</nav><main>{{CONTENT}}</main></html>",
+    );
+    wiki.config.template_dir = Some(theme);
+    wiki.page("guide/intro.md", "# Intro\n\nHello there");
+
+    let body = get(&wiki.app(), "/guide/intro").await.text();
+    assert!(body.starts_with("<html><title>Intro — "), "{body}");
+    assert!(body.contains("<nav><div class=\"sidebar-nav\">"), "{body}");
+    assert!(body.contains("<main><nav class=\"breadcrumbs\">"));
+    assert!(body.contains("<p>Hello there</p>\n"));
+    assert!(!body.contains("{{"));
+}
+
+#[tokio::test]
+async fn missing_theme_template_falls_back_to_the_built_in_shell() {
+    let mut wiki = TestWiki::new();
+    wiki.config.template_dir = Some(wiki.dir.path().join("no-such-theme"));
+    wiki.page("intro.md", "# Intro");
+
+    let response = get(&wiki.app(), "/intro").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.text();
+    assert!(body.starts_with("<!doctype html>"), "{body}");
+    assert!(body.contains("<aside class=\"sidebar glass\">"));
+}
+
+#[tokio::test]
+async fn theme_assets_override_bundled_static_files() {
+    let mut wiki = TestWiki::new();
+    let theme = wiki.dir.path().join("theme");
+    write(&theme.join("css/strata.css"), b"body{color:red}");
+    wiki.config.template_dir = Some(theme);
+    wiki.asset("css/strata.css", b"body{color:blue}").asset("js/app.js", b"// bundled");
+    let app = wiki.app();
+
+    assert_eq!(get(&app, "/static/css/strata.css").await.text(), "body{color:red}");
+    assert_eq!(get(&app, "/static/js/app.js").await.text(), "// bundled");
+}
+
+
+#[tokio::test]
+async fn configured_theme_reaches_pages_and_the_raw_view() {
+    let mut wiki = TestWiki::new().with_repo_template();
+    wiki.config.theme = "dark".to_string();
+    wiki.config.theme_toggle = true;
+    wiki.page("guide/intro.md", "# Intro");
+    let app = wiki.app();
+
+    for uri in ["/guide/intro", "/raw/guide/intro.md"] {
+        let body = get(&app, uri).await.text();
+        assert!(body.contains("<html lang=\"en\" class=\"theme-dark\">"), "{uri}: {body}");
+        let head = &body[..body.find("</head>").unwrap()];
+        assert!(head.contains("localStorage.getItem(\"strata-theme\")"), "{uri}: {body}");
+    }
+    assert!(get(&app, "/guide/intro").await.text().contains("class=\"theme-toggle glass\""));
+}
+
+#[tokio::test]
+async fn default_theme_is_auto_without_a_toggle() {
+    let wiki = TestWiki::new().with_repo_template();
+    wiki.page("intro.md", "# Intro");
+
+    let body = get(&wiki.app(), "/intro").await.text();
+    assert!(body.contains("<html lang=\"en\" class=\"theme-auto\">"), "{body}");
+    assert!(!body.contains("strata-theme"));
+    assert!(!body.contains("theme-toggle"));
+}